@@ -1,14 +1,17 @@
 use std::collections::HashMap;
 
-use crate::{Colour, Colours};
+use crate::{Colour, Colours, palette_swap::PaletteSwap, quantise::Dither};
 
 pub(crate) trait Config {
     fn images(&self) -> HashMap<String, &dyn Image>;
     fn transparent_colour(&self) -> Option<Colour>;
+    fn palette_swaps(&self) -> &[PaletteSwap];
 }
 
 pub(crate) trait Image {
     fn filename(&self) -> String;
     fn colours(&self) -> Colours;
     fn deduplicate(&self) -> bool;
+    fn quantise(&self) -> Option<Dither>;
+    fn submodule(&self) -> Option<String>;
 }
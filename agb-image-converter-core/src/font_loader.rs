@@ -0,0 +1,494 @@
+use std::collections::HashSet;
+
+use crate::{ByteString, image_loader::Image};
+use quote::{ToTokens, quote};
+
+use proc_macro2::TokenStream;
+
+#[derive(Clone)]
+struct KerningData {
+    previous_character: char,
+    amount: f32,
+}
+
+#[derive(Clone)]
+struct LetterData {
+    character: char,
+    width: usize,
+    height: usize,
+    xmin: i32,
+    ymin: i32,
+    advance_width: f32,
+    rendered: Vec<u8>,
+    kerning_data: Vec<KerningData>,
+}
+
+impl ToTokens for LetterData {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let character = self.character;
+        let data_raw = ByteString(&self.rendered);
+        let height = self.height as u8;
+        let width = self.width as u8;
+        let xmin = self.xmin as i8;
+        let ymin = self.ymin as i8;
+        let advance_width = self.advance_width.ceil() as u8;
+        let kerning_amounts = self.kerning_data.iter().map(|kerning_data| {
+            let amount = kerning_data.amount as i8;
+            let c = kerning_data.previous_character;
+            quote! {
+                (#c, #amount)
+            }
+        });
+
+        quote!(
+            FontLetter::new(
+                #character,
+                #width,
+                #height,
+                #data_raw,
+                #xmin,
+                #ymin,
+                #advance_width,
+                &[
+                    #(#kerning_amounts),*
+                ]
+            )
+        )
+        .to_tokens(tokens);
+    }
+}
+
+/// The characters to include in a font, or `None` to include every character
+pub type Subset<'a> = Option<&'a HashSet<char>>;
+
+fn in_subset(subset: Subset, character: char) -> bool {
+    subset.is_none_or(|subset| subset.contains(&character))
+}
+
+pub fn load_font(font_data: &[u8], pixels_per_em: f32, subset: Subset) -> TokenStream {
+    let font = fontdue::Font::from_bytes(
+        font_data,
+        fontdue::FontSettings {
+            scale: pixels_per_em,
+            ..Default::default()
+        },
+    )
+    .expect("Invalid font data");
+
+    let line_metrics = font.horizontal_line_metrics(pixels_per_em).unwrap();
+
+    let line_height = line_metrics.new_line_size as i32;
+    let ascent = line_metrics.ascent as i32;
+
+    let letters: Vec<_> = font
+        .chars()
+        .iter()
+        .filter(|&(&c, _)| in_subset(subset, c))
+        .map(|(&c, &index)| (c, index, font.rasterize(c, pixels_per_em)))
+        .map(|(c, index, (metrics, bitmap))| {
+            let width = metrics.width.div_ceil(8) * 8;
+            let height = metrics.height;
+
+            let rendered = if bitmap.is_empty() {
+                vec![]
+            } else {
+                bitmap
+                    .chunks(metrics.width)
+                    .flat_map(|row| {
+                        row.chunks(8).map(|chunk| {
+                            let mut output = 0u8;
+                            for (i, &value) in chunk.iter().enumerate() {
+                                if value > 100 {
+                                    output |= 1 << i;
+                                }
+                            }
+
+                            output
+                        })
+                    })
+                    .collect()
+            };
+
+            let mut kerning_data: Vec<_> = font
+                .chars()
+                .iter()
+                .filter(|&(&left_char, _)| in_subset(subset, left_char))
+                .filter_map(|(&left_char, &left_index)| {
+                    let kerning = font.horizontal_kern_indexed(
+                        left_index.into(),
+                        index.into(),
+                        pixels_per_em,
+                    )?;
+
+                    Some(KerningData {
+                        previous_character: left_char,
+                        amount: kerning,
+                    })
+                })
+                .collect();
+
+            kerning_data.sort_unstable_by_key(|kd| kd.previous_character);
+
+            LetterData {
+                character: c,
+                width,
+                height,
+                rendered,
+                xmin: metrics.xmin,
+                ymin: metrics.ymin,
+                advance_width: metrics.advance_width,
+                kerning_data,
+            }
+        })
+        .collect();
+
+    font_tokens(letters, line_height, ascent)
+}
+
+/// Loads a font in the [BDF](https://en.wikipedia.org/wiki/Glyph_Bitmap_Distribution_Format)
+/// format. The glyphs and their widths are used exactly as they are in the file.
+pub fn load_bdf(font_data: &str, subset: Subset) -> TokenStream {
+    let mut bounding_box = None;
+    let mut ascent = None;
+    let mut descent = None;
+    let mut letters = vec![];
+
+    let mut lines = font_data.lines();
+    while let Some(line) = lines.next() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("FONTBOUNDINGBOX") => bounding_box = Some(bdf_numbers::<4>(words, line)),
+            Some("FONT_ASCENT") => ascent = Some(bdf_numbers::<1>(words, line)[0]),
+            Some("FONT_DESCENT") => descent = Some(bdf_numbers::<1>(words, line)[0]),
+            Some("STARTCHAR") => letters.extend(load_bdf_letter(&mut lines)),
+            _ => {}
+        }
+    }
+
+    let [_, bounding_height, _, bounding_y] =
+        bounding_box.expect("Invalid BDF font: missing FONTBOUNDINGBOX");
+
+    let ascent = ascent.unwrap_or(bounding_height + bounding_y);
+    let line_height = match descent {
+        Some(descent) => ascent + descent,
+        None => bounding_height,
+    };
+
+    letters.retain(|letter| in_subset(subset, letter.character));
+    assert!(!letters.is_empty(), "Invalid BDF font: no characters");
+
+    font_tokens(letters, line_height, ascent)
+}
+
+fn bdf_numbers<'a, const N: usize>(words: impl Iterator<Item = &'a str>, line: &str) -> [i32; N] {
+    let numbers: Vec<i32> = words
+        .map(|word| {
+            word.parse()
+                .unwrap_or_else(|_| panic!("Invalid BDF font: expected numbers in `{line}`"))
+        })
+        .collect();
+
+    numbers
+        .try_into()
+        .unwrap_or_else(|_| panic!("Invalid BDF font: expected {N} numbers in `{line}`"))
+}
+
+/// Reads the lines after a STARTCHAR up to and including the ENDCHAR. Returns
+/// `None` for glyphs which don't have a unicode encoding.
+fn load_bdf_letter<'a>(lines: &mut impl Iterator<Item = &'a str>) -> Option<LetterData> {
+    let mut encoding = None;
+    let mut advance_width = None;
+    let mut bounding_box = None;
+    let mut bitmap = vec![];
+    let mut in_bitmap = false;
+
+    for line in lines.by_ref() {
+        let mut words = line.split_whitespace();
+        let keyword = words.next();
+
+        match keyword {
+            Some("ENDCHAR") => break,
+            _ if in_bitmap => bitmap.push(line.trim()),
+            Some("ENCODING") => encoding = Some(bdf_numbers::<1>(words.take(1), line)[0]),
+            Some("DWIDTH") => advance_width = Some(bdf_numbers::<2>(words, line)[0]),
+            Some("BBX") => bounding_box = Some(bdf_numbers::<4>(words, line)),
+            Some("BITMAP") => in_bitmap = true,
+            _ => {}
+        }
+    }
+
+    let character = char::from_u32(u32::try_from(encoding?).ok()?)?;
+    let [width, height, xmin, ymin] =
+        bounding_box.unwrap_or_else(|| panic!("Invalid BDF font: missing BBX for {character:?}"));
+    let (width, height) = (width as usize, height as usize);
+
+    assert!(
+        bitmap.len() == height,
+        "Invalid BDF font: expected {height} rows of bitmap for {character:?}, got {}",
+        bitmap.len()
+    );
+
+    let padded_width = width.div_ceil(8) * 8;
+    let mut rendered = vec![0; padded_width / 8 * height];
+
+    for (y, row) in bitmap.iter().enumerate() {
+        let row: Vec<u8> = (0..row.len() / 2)
+            .map(|i| {
+                u8::from_str_radix(&row[i * 2..i * 2 + 2], 16).unwrap_or_else(|_| {
+                    panic!("Invalid BDF font: bad bitmap row `{row}` for {character:?}")
+                })
+            })
+            .collect();
+
+        for x in 0..width {
+            if row
+                .get(x / 8)
+                .is_some_and(|byte| byte & (0x80 >> (x % 8)) != 0)
+            {
+                rendered[y * padded_width / 8 + x / 8] |= 1 << (x % 8);
+            }
+        }
+    }
+
+    Some(LetterData {
+        character,
+        width: padded_width,
+        height,
+        xmin,
+        ymin,
+        advance_width: advance_width.unwrap_or(width as i32) as f32,
+        rendered,
+        kerning_data: vec![],
+    })
+}
+
+/// Loads a font from an image split into a grid of `cell_width` by
+/// `cell_height` cells, read left to right and top to bottom, one for each
+/// character in `characters`.
+///
+/// Pixels which are transparent, or the same colour as the top left pixel of
+/// the image, are background. Each glyph is placed in the line as it is in its
+/// cell, and advances by one pixel past its right most pixel. Empty cells, such
+/// as space, advance by half the cell width.
+///
+/// Returns an error if the image doesn't have a cell for every character.
+pub fn load_png_grid(
+    image: &Image,
+    cell_width: usize,
+    cell_height: usize,
+    characters: &str,
+    subset: Subset,
+) -> Result<TokenStream, String> {
+    let mut letters = png_grid_letters(image, cell_width, cell_height, characters)?;
+    letters.retain(|letter| in_subset(subset, letter.character));
+    if letters.is_empty() {
+        return Err("Font image needs at least one character".to_string());
+    }
+
+    Ok(font_tokens(letters, cell_height as i32, cell_height as i32))
+}
+
+fn png_grid_letters(
+    image: &Image,
+    cell_width: usize,
+    cell_height: usize,
+    characters: &str,
+) -> Result<Vec<LetterData>, String> {
+    if cell_width == 0 || cell_height == 0 {
+        return Err("Font cells must have a non zero size".to_string());
+    }
+
+    if image.width < cell_width || image.height < cell_height {
+        return Err(format!(
+            "Font image is {}x{}, which is smaller than a single {cell_width}x{cell_height} cell",
+            image.width, image.height
+        ));
+    }
+
+    let columns = image.width / cell_width;
+    let rows = image.height / cell_height;
+
+    let characters: Vec<_> = characters
+        .chars()
+        .filter(|&c| c != '\n' && c != '\r')
+        .collect();
+    if characters.len() > columns * rows {
+        return Err(format!(
+            "Font image has {columns}x{rows} cells of {cell_width}x{cell_height}, which isn't enough for the {} characters given",
+            characters.len()
+        ));
+    }
+
+    let background = image.colour(0, 0);
+    let is_set =
+        |x, y| -> bool { !image.colour(x, y).is_transparent() && image.colour(x, y) != background };
+
+    Ok(characters
+        .into_iter()
+        .enumerate()
+        .map(|(i, character)| {
+            let cell_x = (i % columns) * cell_width;
+            let cell_y = (i / columns) * cell_height;
+
+            let set_pixels: Vec<(usize, usize)> = (0..cell_height)
+                .flat_map(|y| (0..cell_width).map(move |x| (x, y)))
+                .filter(|&(x, y)| is_set(cell_x + x, cell_y + y))
+                .collect();
+
+            let (Some(left), Some(right), Some(top), Some(bottom)) = (
+                set_pixels.iter().map(|&(x, _)| x).min(),
+                set_pixels.iter().map(|&(x, _)| x).max(),
+                set_pixels.iter().map(|&(_, y)| y).min(),
+                set_pixels.iter().map(|&(_, y)| y).max(),
+            ) else {
+                return LetterData {
+                    character,
+                    width: 0,
+                    height: 0,
+                    xmin: 0,
+                    ymin: 0,
+                    advance_width: (cell_width / 2) as f32,
+                    rendered: vec![],
+                    kerning_data: vec![],
+                };
+            };
+
+            let width = right - left + 1;
+            let height = bottom - top + 1;
+            let padded_width = width.div_ceil(8) * 8;
+
+            let mut rendered = vec![0; padded_width / 8 * height];
+            for (x, y) in set_pixels {
+                let (x, y) = (x - left, y - top);
+                rendered[y * padded_width / 8 + x / 8] |= 1 << (x % 8);
+            }
+
+            LetterData {
+                character,
+                width: padded_width,
+                height,
+                xmin: left as i32,
+                // the baseline is the bottom of the cell
+                ymin: (cell_height - bottom - 1) as i32,
+                advance_width: (right + 2) as f32,
+                rendered,
+                kerning_data: vec![],
+            }
+        })
+        .collect())
+}
+
+fn font_tokens(mut letters: Vec<LetterData>, line_height: i32, mut ascent: i32) -> TokenStream {
+    letters.sort_unstable_by_key(|letter| letter.character);
+
+    let maximum_above_line = letters
+        .iter()
+        .map(|x| x.height as i32 + x.ymin)
+        .max()
+        .unwrap();
+
+    if (ascent - maximum_above_line) < 0 {
+        ascent = maximum_above_line;
+    }
+
+    let ascii_letters = (0x21..0x7F).map(|idx| {
+        let c = char::from_u32(idx).expect("ascii character should be valid");
+        let letter_idx = letters
+            .binary_search_by_key(&c, |x| x.character)
+            .unwrap_or(0);
+
+        letters[letter_idx].clone()
+    });
+
+    let non_ascii_letters = letters
+        .iter()
+        .filter(|&x| !(0x21..0x7F).contains(&(x.character as u32)))
+        .cloned();
+
+    quote![
+        Font::new(&[#(#ascii_letters),*], &[#(#non_ascii_letters),*], #line_height, #ascent)
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colour::Colour;
+
+    use super::*;
+
+    #[test]
+    fn bdf_letters_keep_their_bitmap_and_metrics() {
+        let bdf = "ENCODING 65
+DWIDTH 6 0
+BBX 9 2 1 -1
+BITMAP
+8080
+4100
+ENDCHAR";
+
+        let letter = load_bdf_letter(&mut bdf.lines()).unwrap();
+
+        assert_eq!(letter.character, 'A');
+        assert_eq!((letter.width, letter.height), (16, 2));
+        assert_eq!((letter.xmin, letter.ymin), (1, -1));
+        assert_eq!(letter.advance_width, 6.0);
+        assert_eq!(letter.rendered, [0b0000_0001, 0b0000_0001, 0b1000_0010, 0]);
+    }
+
+    #[test]
+    fn bdf_letters_without_encoding_are_skipped() {
+        let bdf = "ENCODING -1\nBBX 0 0 0 0\nBITMAP\nENDCHAR";
+        assert!(load_bdf_letter(&mut bdf.lines()).is_none());
+    }
+
+    #[test]
+    fn png_grid_letters_are_trimmed_to_their_pixels() {
+        let background = Colour::from_rgb(0, 0, 0, 255);
+        let ink = Colour::from_rgb(255, 255, 255, 255);
+
+        // two 4x4 cells, the first empty and the second with a 2x3 glyph
+        let mut colours = vec![background; 8 * 4];
+        for (x, y) in [(5, 0), (6, 1), (5, 2)] {
+            colours[y * 8 + x] = ink;
+        }
+
+        let image = Image::new(8, 4, colours);
+        let letters = png_grid_letters(&image, 4, 4, " >").unwrap();
+
+        assert_eq!(letters[0].character, ' ');
+        assert_eq!(letters[0].advance_width, 2.0);
+        assert!(letters[0].rendered.is_empty());
+
+        let letter = &letters[1];
+        assert_eq!(letter.character, '>');
+        assert_eq!((letter.width, letter.height), (8, 3));
+        assert_eq!((letter.xmin, letter.ymin), (1, 1));
+        assert_eq!(letter.advance_width, 4.0);
+        assert_eq!(letter.rendered, [0b01, 0b10, 0b01]);
+    }
+
+    #[test]
+    fn png_grid_which_is_too_small_is_an_error() {
+        let colours = vec![Colour::from_rgb(0, 0, 0, 255); 6 * 4];
+        let image = Image::new(6, 4, colours);
+
+        let error = |cell_width, cell_height, characters| {
+            png_grid_letters(&image, cell_width, cell_height, characters)
+                .err()
+                .expect("should be an error")
+        };
+
+        let err = error(8, 4, "A");
+        assert!(err.contains("smaller than a single 8x4 cell"), "{err}");
+
+        let err = error(4, 8, "A");
+        assert!(err.contains("smaller than a single 4x8 cell"), "{err}");
+
+        // one cell, two characters
+        let err = error(4, 4, "AB");
+        assert!(err.contains("1x1 cells"), "{err}");
+        assert!(err.contains("2 characters"), "{err}");
+
+        assert!(png_grid_letters(&image, 4, 4, "A\n").is_ok());
+    }
+}
@@ -43,6 +43,16 @@ impl Image {
         }
     }
 
+    pub fn new(width: usize, height: usize, colour_data: Vec<Colour>) -> Self {
+        assert_eq!(colour_data.len(), width * height);
+
+        Self {
+            width,
+            height,
+            colour_data,
+        }
+    }
+
     pub fn from_colour_data(colour_data: Vec<Colour>) -> Self {
         Self {
             height: colour_data.len() / 8,
@@ -54,4 +64,8 @@ impl Image {
     pub fn colour(&self, x: usize, y: usize) -> Colour {
         self.colour_data[x + y * self.width]
     }
+
+    pub fn colours(&self) -> &[Colour] {
+        &self.colour_data
+    }
 }
@@ -0,0 +1,1051 @@
+use palette16::{Palette16OptimisationResults, Palette16Optimiser};
+use palette256::Palette256;
+use proc_macro2::{Literal, TokenStream};
+use syn::parse::{Parse, Parser};
+use syn::punctuated::Punctuated;
+use syn::{Expr, ExprLit, Lit, Token};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::{path::Path, str};
+
+use quote::{ToTokens, format_ident, quote};
+
+mod aseprite;
+mod colour;
+mod config;
+mod deduplicator;
+mod font_loader;
+mod image_loader;
+mod palette16;
+mod palette256;
+mod palette_swap;
+pub mod pipeline;
+mod quantise;
+mod rust_generator;
+mod strings;
+
+use image_loader::Image;
+
+use colour::Colour;
+use palette_swap::PaletteSwap;
+use quantise::Dither;
+
+mod sprite;
+
+pub use pipeline::{AssetPipeline, PipelineError};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Colours {
+    Colours16,
+    Colours256,
+}
+
+struct BackgroundGfxOption {
+    module_name: String,
+    submodule: Option<String>,
+    file_name: String,
+    colours: Colours,
+    deduplicate: bool,
+    quantise: Option<Dither>,
+}
+
+impl config::Image for BackgroundGfxOption {
+    fn filename(&self) -> String {
+        self.file_name
+            .clone()
+            .replace(OUT_DIR_TOKEN, &get_out_dir(&self.file_name))
+    }
+
+    fn colours(&self) -> Colours {
+        self.colours
+    }
+
+    fn deduplicate(&self) -> bool {
+        self.deduplicate
+    }
+
+    fn quantise(&self) -> Option<Dither> {
+        self.quantise
+    }
+
+    fn submodule(&self) -> Option<String> {
+        self.submodule.clone()
+    }
+}
+
+impl Parse for BackgroundGfxOption {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let module_name: syn::Ident = input.parse()?;
+        let _: Token![=>] = input.parse()?;
+
+        let lookahead = input.lookahead1();
+
+        let colours = if lookahead.peek(syn::LitInt) {
+            let num_colours: syn::LitInt = input.parse()?;
+
+            match num_colours.base10_parse()? {
+                16 => Colours::Colours16,
+                256 => Colours::Colours256,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        num_colours,
+                        "Number of colours must be 16 or 256",
+                    ));
+                }
+            }
+        } else {
+            Colours::Colours16
+        };
+
+        let mut deduplicate = false;
+        let mut quantise = None;
+
+        while input.peek(syn::Ident) {
+            let option: syn::Ident = input.parse()?;
+
+            match option.to_string().as_str() {
+                "deduplicate" => deduplicate = true,
+                "quantise" => quantise = quantise.or(Some(Dither::None)),
+                "dither_ordered" => quantise = Some(Dither::Ordered),
+                "dither_floyd_steinberg" => quantise = Some(Dither::FloydSteinberg),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        option,
+                        "Must be one of deduplicate, quantise, dither_ordered or dither_floyd_steinberg",
+                    ));
+                }
+            }
+        }
+
+        let file_name: syn::LitStr = input.parse()?;
+
+        Ok(Self {
+            module_name: module_name.to_string(),
+            submodule: None,
+            file_name: file_name.value(),
+            colours,
+            deduplicate,
+            quantise,
+        })
+    }
+}
+
+struct IncludeBackgroundGfxInput {
+    module_name: syn::Ident,
+    visibility: syn::Visibility,
+    transparent_colour: Colour,
+    background_gfx_options: Vec<BackgroundGfxOption>,
+    palette_swaps: Vec<PaletteSwap>,
+}
+
+enum BackgroundGfxEntry {
+    Image(BackgroundGfxOption),
+    PaletteSwap(PaletteSwap),
+    Module(syn::Ident, Vec<BackgroundGfxOption>),
+}
+
+impl Parse for BackgroundGfxEntry {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![mod]) {
+            let _: Token![mod] = input.parse()?;
+            let module_name: syn::Ident = input.parse()?;
+
+            let content;
+            syn::braced!(content in input);
+            let options = content.parse_terminated(BackgroundGfxOption::parse, Token![,])?;
+
+            Ok(Self::Module(module_name, options.into_iter().collect()))
+        } else if input.peek(syn::Ident) && input.peek2(syn::Ident) {
+            let keyword: syn::Ident = input.parse()?;
+            if keyword != "palette_swap" {
+                return Err(syn::Error::new_spanned(
+                    keyword,
+                    "Expected palette_swap or an image name",
+                ));
+            }
+
+            Ok(Self::PaletteSwap(input.parse()?))
+        } else {
+            Ok(Self::Image(input.parse()?))
+        }
+    }
+}
+
+impl Parse for IncludeBackgroundGfxInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let visibility: syn::Visibility = input.parse()?;
+
+        let _: Token![mod] = input.parse()?;
+        let module_name: syn::Ident = input.parse()?;
+        let _: Token![,] = input.parse()?;
+
+        let lookahead = input.lookahead1();
+        let transparent_colour: Colour = if lookahead.peek(syn::LitStr) {
+            let colour_str: syn::LitStr = input.parse()?;
+            let _: Token![,] = input.parse()?;
+            colour_str
+                .value()
+                .parse()
+                .map_err(|msg| syn::Error::new_spanned(colour_str, msg))?
+        } else {
+            Colour::from_rgb(255, 0, 255, 0)
+        };
+
+        let entries = input.parse_terminated(BackgroundGfxEntry::parse, Token![,])?;
+
+        let mut background_gfx_options = vec![];
+        let mut palette_swaps = vec![];
+        for entry in entries {
+            match entry {
+                BackgroundGfxEntry::Image(option) => background_gfx_options.push(option),
+                BackgroundGfxEntry::PaletteSwap(swap) => palette_swaps.push(swap),
+                BackgroundGfxEntry::Module(submodule, options) => {
+                    background_gfx_options.extend(options.into_iter().map(|option| {
+                        BackgroundGfxOption {
+                            submodule: Some(submodule.to_string()),
+                            ..option
+                        }
+                    }));
+                }
+            }
+        }
+
+        Ok(Self {
+            module_name,
+            visibility,
+            transparent_colour,
+            background_gfx_options,
+            palette_swaps,
+        })
+    }
+}
+
+impl config::Config for IncludeBackgroundGfxInput {
+    fn images(&self) -> HashMap<String, &dyn config::Image> {
+        self.background_gfx_options
+            .iter()
+            .map(|options| {
+                let name = match &options.submodule {
+                    Some(submodule) => format!("{submodule}::{}", options.module_name),
+                    None => options.module_name.clone(),
+                };
+
+                (name, options as &dyn config::Image)
+            })
+            .collect()
+    }
+
+    fn transparent_colour(&self) -> Option<Colour> {
+        Some(self.transparent_colour)
+    }
+
+    fn palette_swaps(&self) -> &[PaletteSwap] {
+        &self.palette_swaps
+    }
+}
+
+#[doc(hidden)]
+pub fn include_background_gfx(input: TokenStream) -> TokenStream {
+    let config = match syn::parse2::<IncludeBackgroundGfxInput>(input) {
+        Ok(config) => Box::new(config),
+        Err(err) => return err.to_compile_error(),
+    };
+
+    let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
+
+    let module_name = config.module_name.clone();
+    let visibility = config.visibility.clone();
+    include_gfx_from_config(config, visibility, module_name, Path::new(&root))
+}
+
+fn include_gfx_from_config(
+    config: Box<dyn config::Config>,
+    visibility: syn::Visibility,
+    module_name: syn::Ident,
+    parent: &Path,
+) -> TokenStream {
+    let images = config.images();
+
+    let mut tile_palettes = vec![];
+    let mut assignment_offsets = HashMap::new();
+    let mut assignment_offset = 0;
+
+    let mut palette256 = Palette256::new();
+
+    // The palette assignments are stored in the order the images are added,
+    // so sort them to keep the output stable between builds.
+    let mut sorted_images: Vec<_> = images.iter().collect();
+    sorted_images.sort_by_key(|(name, _)| *name);
+
+    for (name, settings) in sorted_images {
+        let image_filename = &parent.join(settings.filename());
+        let image = load_image(*settings, image_filename, config.transparent_colour());
+
+        match settings.colours() {
+            Colours::Colours16 => {
+                let tile_size = 8;
+                if !image.width.is_multiple_of(tile_size) || !image.height.is_multiple_of(tile_size)
+                {
+                    panic!("Image size not a multiple of tile size");
+                }
+
+                tile_palettes.push((
+                    name,
+                    tile_palettes_for_image(
+                        &image,
+                        tile_size,
+                        tile_size,
+                        config.transparent_colour(),
+                    ),
+                ));
+
+                let num_tiles = image.width * image.height / 8usize.pow(2);
+                assignment_offsets.insert(name, assignment_offset);
+                assignment_offset += num_tiles;
+            }
+            Colours::Colours256 => {
+                palette256.add_image(&image);
+            }
+        }
+    }
+
+    let optimisation_results =
+        match optimise_palette_group(&tile_palettes, config.transparent_colour()) {
+            Ok(optimisation_results) => optimisation_results,
+            Err(message) => {
+                return syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    format!("Backgrounds in `{module_name}` {message}"),
+                )
+                .to_compile_error();
+            }
+        };
+    let optimisation_results = palette256.extend_results(&optimisation_results);
+
+    let mut image_code: BTreeMap<Option<String>, Vec<_>> = BTreeMap::new();
+
+    for (image_name, &image) in images.iter() {
+        let assignment_offset = match image.colours() {
+            Colours::Colours16 => Some(assignment_offsets[image_name]),
+            _ => None,
+        };
+
+        let variable_name = image_name.rsplit("::").next().unwrap_or(image_name);
+
+        image_code
+            .entry(image.submodule())
+            .or_default()
+            .push(convert_image(
+                image,
+                parent,
+                variable_name,
+                &optimisation_results,
+                assignment_offset,
+                config.transparent_colour(),
+            ));
+    }
+
+    let image_code = image_code
+        .into_iter()
+        .map(|(submodule, code)| match submodule {
+            Some(submodule) => {
+                let submodule = format_ident!("{}", submodule);
+                quote! {
+                    pub mod #submodule {
+                        #(#code)*
+                    }
+                }
+            }
+            None => quote! { #(#code)* },
+        });
+
+    let palette_code = rust_generator::generate_palette_code(&optimisation_results);
+
+    let palette_swap_code = config.palette_swaps().iter().map(|swap| {
+        let resolve_path =
+            |filename: &str| parent.join(filename.replace(OUT_DIR_TOKEN, &get_out_dir(filename)));
+
+        let colour_map = swap
+            .colour_map(resolve_path)
+            .unwrap_or_else(|err| panic!("Failed to generate palette swap: {err}"));
+
+        rust_generator::generate_palette_swap_code(
+            &swap.name,
+            &optimisation_results,
+            &colour_map,
+            &swap.input_files(resolve_path),
+        )
+    });
+
+    let module = quote! {
+        #visibility mod #module_name {
+            #palette_code
+
+            #(#palette_swap_code)*
+
+            #(#image_code)*
+        }
+    };
+
+    module
+}
+
+use quote::TokenStreamExt;
+struct ByteString<'a>(&'a [u8]);
+impl ToTokens for ByteString<'_> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.append(Literal::byte_string(self.0));
+    }
+}
+
+struct IncludeColoursInput {
+    module_name: syn::Path,
+    filename: String,
+}
+
+impl Parse for IncludeColoursInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let module_name: syn::Path = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let filename: syn::LitStr = input.parse()?;
+
+        Ok(Self {
+            module_name,
+            filename: filename.value(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub fn include_colours_inner(input: TokenStream) -> TokenStream {
+    let input = match syn::parse2::<IncludeColoursInput>(input) {
+        Ok(input) => input,
+        Err(err) => return err.to_compile_error(),
+    };
+    let input_filename = input.filename;
+
+    let module_name = input.module_name.clone();
+
+    let input_filename = resolve_path(&input_filename);
+
+    let image = Image::load_from_file(Path::new(&input_filename));
+
+    let mut palette_data = Vec::with_capacity(image.width * image.height);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let rgb15 = image.colour(x, y).to_rgb15();
+            palette_data.push(quote!(#module_name::display::Rgb15(#rgb15)));
+        }
+    }
+
+    let filename = input_filename.to_string_lossy();
+
+    quote! {
+        {
+            const _: &[u8] = include_bytes!(#filename);
+            [#(#palette_data),*]
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn include_aseprite_inner(input: TokenStream) -> TokenStream {
+    sprite::include_regular(input)
+}
+
+#[doc(hidden)]
+pub fn include_aseprite_256_inner(input: TokenStream) -> TokenStream {
+    sprite::include_multi(input)
+}
+
+fn convert_image(
+    settings: &dyn config::Image,
+    parent: &Path,
+    variable_name: &str,
+    optimisation_results: &Palette16OptimisationResults,
+    assignment_offset: Option<usize>,
+    transparent_colour: Option<Colour>,
+) -> TokenStream {
+    let image_filename = &parent.join(settings.filename());
+    let image = load_image(settings, image_filename, transparent_colour);
+    let deduplicate = settings.deduplicate();
+
+    rust_generator::generate_code(
+        variable_name,
+        optimisation_results,
+        &image,
+        &image_filename.to_string_lossy(),
+        assignment_offset,
+        deduplicate,
+    )
+}
+
+fn load_image(
+    settings: &dyn config::Image,
+    image_filename: &Path,
+    transparent_colour: Option<Colour>,
+) -> Image {
+    let image = Image::load_from_file(image_filename);
+
+    match (settings.quantise(), settings.colours()) {
+        (None, _) => image,
+        (Some(dither), Colours::Colours16) => {
+            palette16::quantise(&image, 8, 8, transparent_colour, dither)
+        }
+        (Some(dither), Colours::Colours256) => {
+            palette256::quantise(&image, transparent_colour, dither)
+        }
+    }
+}
+
+/// Optimises the tiles of every 16 colour image in a group into a single set of
+/// palettes. If they don't fit, the error names the images along with how much
+/// of the budget each one uses on its own.
+fn optimise_palette_group(
+    tile_palettes: &[(&String, Vec<palette16::Palette16>)],
+    transparent_colour: Option<Colour>,
+) -> Result<Palette16OptimisationResults, String> {
+    let colour_count = |palettes: &[palette16::Palette16]| {
+        palettes
+            .iter()
+            .flat_map(|palette| palette.colours())
+            .collect::<HashSet<_>>()
+            .len()
+    };
+
+    let all_palettes: Vec<_> = tile_palettes
+        .iter()
+        .flat_map(|(_, palettes)| palettes.iter().cloned())
+        .collect();
+
+    let total_colours = colour_count(&all_palettes);
+    if total_colours > 256 {
+        let mut usage: Vec<_> = tile_palettes
+            .iter()
+            .map(|&(name, ref palettes)| (colour_count(palettes), name))
+            .collect();
+        usage.sort_by(|a, b| b.cmp(a));
+
+        return Err(format!(
+            "use {total_colours} colours but at most 256 are available. Colours used by each image: {}",
+            describe_usage(&usage)
+        ));
+    }
+
+    let mut optimiser = Palette16Optimiser::new(transparent_colour);
+    for palette in all_palettes {
+        optimiser.add_palette(palette);
+    }
+
+    optimiser.optimise_palettes().map_err(|err| {
+        let mut usage: Vec<_> = tile_palettes
+            .iter()
+            .map(|&(name, ref palettes)| {
+                let mut optimiser = Palette16Optimiser::new(transparent_colour);
+                for palette in palettes {
+                    optimiser.add_palette(palette.clone());
+                }
+
+                let count = match optimiser.optimise_palettes() {
+                    Ok(results) => results.optimised_palettes.len(),
+                    Err(err) => err.count(),
+                };
+
+                (count, name)
+            })
+            .collect();
+        usage.sort_by(|a, b| b.cmp(a));
+
+        format!(
+            "need {} palettes but only 16 are available. Palettes needed by each image on its own: {}",
+            err.count(),
+            describe_usage(&usage)
+        )
+    })
+}
+
+fn describe_usage(usage: &[(usize, &String)]) -> String {
+    usage
+        .iter()
+        .map(|(count, name)| format!("{name} ({count})"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn tile_palettes_for_image(
+    image: &Image,
+    tile_width: usize,
+    tile_height: usize,
+    transparent_colour: Option<Colour>,
+) -> Vec<palette16::Palette16> {
+    let tiles_x = image.width / tile_width;
+    let tiles_y = image.height / tile_height;
+
+    let mut palettes = Vec::with_capacity(tiles_x * tiles_y);
+
+    for y in 0..tiles_y {
+        for x in 0..tiles_x {
+            let mut palette = palette16::Palette16::new();
+
+            for j in 0..tile_height {
+                for i in 0..tile_width {
+                    let colour = image.colour(x * tile_width + i, y * tile_height + j);
+
+                    palette.add_colour(match (colour.is_transparent(), transparent_colour) {
+                        (true, Some(transparent_colour)) => transparent_colour,
+                        _ => colour,
+                    });
+                }
+            }
+
+            palettes.push(palette);
+        }
+    }
+
+    palettes
+}
+
+fn collapse_to_4bpp(tile_data: &[u8]) -> Vec<u8> {
+    tile_data
+        .chunks(2)
+        .map(|chunk| chunk[0] | (chunk[1] << 4))
+        .collect()
+}
+
+fn add_image_to_tile_data(
+    tile_data: &mut Vec<u8>,
+    image: &Image,
+    optimiser: &Palette16OptimisationResults,
+    assignment_offset: usize,
+    is_sprite: bool,
+    remap_index: &[usize],
+) {
+    let tile_size = 8;
+    let tiles_x = image.width / tile_size;
+    let tiles_y = image.height / tile_size;
+
+    for y in 0..tiles_y {
+        for x in 0..tiles_x {
+            let assignment = if is_sprite {
+                assignment_offset
+            } else {
+                remap_index[y * tiles_x + x] + assignment_offset
+            };
+
+            let palette_index = optimiser.assignments[assignment];
+            let palette = &optimiser.optimised_palettes[palette_index];
+
+            for inner_y in 0..tile_size / 8 {
+                for inner_x in 0..tile_size / 8 {
+                    for j in inner_y * 8..inner_y * 8 + 8 {
+                        for i in inner_x * 8..inner_x * 8 + 8 {
+                            let colour = image.colour(x * tile_size + i, y * tile_size + j);
+                            tile_data.push(palette.colour_index(colour));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn add_image_256_to_tile_data(
+    tile_data: &mut Vec<u8>,
+    image: &Image,
+    optimiser: &Palette16OptimisationResults,
+) {
+    let tile_size = 8;
+    let tiles_x = image.width / tile_size;
+    let tiles_y = image.height / tile_size;
+
+    let all_colours: Vec<_> = optimiser
+        .optimised_palettes
+        .iter()
+        .flat_map(|p| p.colours())
+        .collect();
+
+    for y in 0..tiles_y {
+        for x in 0..tiles_x {
+            for inner_y in 0..tile_size / 8 {
+                for inner_x in 0..tile_size / 8 {
+                    for j in inner_y * 8..inner_y * 8 + 8 {
+                        for i in inner_x * 8..inner_x * 8 + 8 {
+                            let colour = image.colour(x * tile_size + i, y * tile_size + j);
+                            tile_data.push(all_colours.iter().position(|c| **c == colour).unwrap() as u8);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn flatten_group(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Group(group) => &group.expr,
+        _ => expr,
+    }
+}
+
+/// An argument to `include_font!`, either positional or written as
+/// `name = "value"` or `name = ["value", ...]`
+enum FontArgument {
+    Positional(Expr),
+    Named(syn::Ident, Vec<syn::LitStr>),
+}
+
+impl Parse for FontArgument {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::Ident) && input.peek2(Token![=]) {
+            let name = input.parse()?;
+            input.parse::<Token![=]>()?;
+
+            let values = if input.peek(syn::token::Bracket) {
+                let content;
+                syn::bracketed!(content in input);
+                Punctuated::<syn::LitStr, Token![,]>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect()
+            } else {
+                vec![input.parse()?]
+            };
+
+            Ok(Self::Named(name, values))
+        } else {
+            Ok(Self::Positional(input.parse()?))
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn include_font(input: TokenStream) -> TokenStream {
+    let parser = Punctuated::<FontArgument, syn::Token![,]>::parse_terminated;
+    let parsed = match parser.parse2(input) {
+        Ok(e) => e,
+        Err(e) => return e.to_compile_error(),
+    };
+
+    let mut all_args = vec![];
+    let mut subset: Option<HashSet<char>> = None;
+    let mut subset_files = vec![];
+
+    for argument in parsed {
+        match argument {
+            FontArgument::Positional(expr) => all_args.push(expr),
+            FontArgument::Named(name, values) if name == "subset" => {
+                subset.get_or_insert_default().extend(
+                    values
+                        .iter()
+                        .flat_map(|value| value.value().chars().collect::<Vec<_>>()),
+                );
+            }
+            FontArgument::Named(name, values) if name == "subset_files" => {
+                for value in values {
+                    let path = resolve_path(&value.value());
+                    let mut content = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+                        panic!("Failed to read subset file {}", path.display())
+                    });
+
+                    // only the text of translations needs to be displayed, not the identifiers,
+                    // but numbers are often filled in to their placeholders
+                    if path.extension().is_some_and(|extension| extension == "ftl") {
+                        let messages = strings::parse_ftl(&content).unwrap_or_else(|err| {
+                            panic!("Failed to parse {}: {err}", path.display())
+                        });
+                        content = strings::characters(&messages) + "0123456789-";
+                    }
+
+                    subset.get_or_insert_default().extend(content.chars());
+                    subset_files.push(path.to_string_lossy().into_owned());
+                }
+            }
+            FontArgument::Named(name, _) => {
+                return syn::Error::new_spanned(
+                    &name,
+                    format!(
+                        "Unknown include_font option `{name}`, expected `subset` or `subset_files`"
+                    ),
+                )
+                .to_compile_error();
+            }
+        }
+    }
+
+    if all_args.is_empty() {
+        panic!("Expected literal string as first argument to include_font");
+    }
+
+    // space is needed to lay out any text, so always keep it
+    if let Some(subset) = &mut subset {
+        subset.insert(' ');
+    }
+    let subset = subset.as_ref();
+
+    let filename = match flatten_group(&all_args[0]) {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(str_lit),
+            ..
+        }) => str_lit.value(),
+        _ => panic!("Expected literal string as first argument to include_font"),
+    };
+
+    let path = resolve_path(&filename);
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+    let rendered = match extension.as_deref() {
+        Some("bdf") => {
+            if all_args.len() != 1 {
+                panic!(
+                    "Include_font requires 1 argument for bdf fonts, got {}",
+                    all_args.len()
+                );
+            }
+
+            let file_content = std::fs::read_to_string(&path).expect("Failed to read bdf file");
+            font_loader::load_bdf(&file_content, subset)
+        }
+        Some("png") => {
+            if all_args.len() != 4 {
+                panic!(
+                    "Include_font requires 4 arguments for png fonts (file, cell width, cell height, characters), got {}",
+                    all_args.len()
+                );
+            }
+
+            let cell_width = font_integer_argument(&all_args[1], "cell width");
+            let cell_height = font_integer_argument(&all_args[2], "cell height");
+            let characters = match flatten_group(&all_args[3]) {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(str_lit),
+                    ..
+                }) => str_lit.value(),
+                _ => panic!(
+                    "Expected literal string of characters as fourth argument to include_font"
+                ),
+            };
+
+            let image = Image::load_from_file(&path);
+            match font_loader::load_png_grid(&image, cell_width, cell_height, &characters, subset) {
+                Ok(rendered) => rendered,
+                Err(message) => {
+                    return syn::Error::new_spanned(
+                        &all_args[0],
+                        format!("{}: {message}", path.display()),
+                    )
+                    .to_compile_error();
+                }
+            }
+        }
+        _ => {
+            if all_args.len() != 2 {
+                panic!("Include_font requires 2 arguments, got {}", all_args.len());
+            }
+
+            let font_size = match flatten_group(&all_args[1]) {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Float(value),
+                    ..
+                }) => value.base10_parse::<f32>().expect("Invalid float literal"),
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(value),
+                    ..
+                }) => value
+                    .base10_parse::<i32>()
+                    .expect("Invalid integer literal") as f32,
+                _ => panic!("Expected literal float or integer as second argument to include_font"),
+            };
+
+            let file_content = std::fs::read(&path).expect("Failed to read ttf file");
+            font_loader::load_font(&file_content, font_size, subset)
+        }
+    };
+
+    let include_path = path.to_string_lossy();
+
+    quote!({
+        let _ = include_bytes!(#include_path);
+        #(let _ = include_bytes!(#subset_files);)*
+
+        #rendered
+    })
+}
+
+struct IncludeStringsInput {
+    crate_path: syn::Path,
+    module_name: syn::Ident,
+    files: Vec<syn::LitStr>,
+}
+
+impl Parse for IncludeStringsInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let crate_path = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let _: Token![mod] = input.parse()?;
+        let module_name = input.parse()?;
+
+        let mut files = vec![];
+        while !input.is_empty() {
+            let _: Token![,] = input.parse()?;
+            if input.is_empty() {
+                break;
+            }
+            files.push(input.parse()?);
+        }
+
+        if files.is_empty() {
+            return Err(input.error("Expected at least one translation file"));
+        }
+
+        Ok(Self {
+            crate_path,
+            module_name,
+            files,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub fn include_strings_inner(input: TokenStream) -> TokenStream {
+    let input = match syn::parse2::<IncludeStringsInput>(input) {
+        Ok(input) => input,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    let mut languages = vec![];
+    let mut files = vec![];
+
+    for file in &input.files {
+        let path = resolve_path(&file.value());
+        let error = |message: String| syn::Error::new_spanned(file, message).to_compile_error();
+
+        if path.extension().is_none_or(|extension| extension != "ftl") {
+            return error(format!(
+                "{} is not a Fluent translation file, expected a .ftl file",
+                path.display()
+            ));
+        }
+
+        let Some(code) = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+        else {
+            return error(format!("{} has no language code", path.display()));
+        };
+
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => return error(format!("Failed to read {}: {err}", path.display())),
+        };
+
+        let messages = match strings::parse_ftl(&content) {
+            Ok(messages) => messages,
+            Err(err) => return error(format!("Failed to parse {}: {err}", path.display())),
+        };
+
+        languages.push(strings::Language { code, messages });
+        files.push(path.to_string_lossy().into_owned());
+    }
+
+    strings::generate(&input.crate_path, &input.module_name, &languages, &files)
+        .unwrap_or_else(|err| syn::Error::new_spanned(&input.files[0], err).to_compile_error())
+}
+
+fn font_integer_argument(expr: &Expr, name: &str) -> usize {
+    match flatten_group(expr) {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(value),
+            ..
+        }) => value
+            .base10_parse::<usize>()
+            .expect("Invalid integer literal"),
+        _ => panic!("Expected literal integer {name} in include_font"),
+    }
+}
+
+const OUT_DIR_TOKEN: &str = "$OUT_DIR";
+
+fn get_out_dir(raw_input: &str) -> String {
+    if raw_input.contains(OUT_DIR_TOKEN) {
+        std::env::var("OUT_DIR").expect("Failed to get OUT_DIR")
+    } else {
+        String::new()
+    }
+}
+
+/// Resolve a file path relative to CARGO_MANIFEST_DIR, with fallback to workspace context
+pub(crate) fn resolve_path(file_path: &str) -> std::path::PathBuf {
+    use std::path::Path;
+
+    let path = Path::new(file_path);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
+    let root = Path::new(&root);
+
+    // Try relative to CARGO_MANIFEST_DIR first
+    let manifest_relative = root.join(path);
+    if manifest_relative.exists() {
+        manifest_relative
+    } else {
+        // If not found, try one level up (workspace context)
+        let workspace_relative = root
+            .parent()
+            .map(|parent| parent.join(path))
+            .unwrap_or_else(|| manifest_relative.clone());
+
+        if workspace_relative.exists() {
+            workspace_relative
+        } else {
+            // Fall back to original behavior
+            manifest_relative
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use asefile::AnimationDirection;
+
+    use super::*;
+
+    #[test]
+    // These directions defined in agb and have these values. This is important
+    // when outputting code for agb. If more animation directions are added then
+    // we will have to support them there.
+    fn directions_to_agb() {
+        assert_eq!(AnimationDirection::Forward as usize, 0);
+        assert_eq!(AnimationDirection::Reverse as usize, 1);
+        assert_eq!(AnimationDirection::PingPong as usize, 2);
+    }
+
+    #[test]
+    fn palette_group_over_budget_names_the_images() {
+        // Tiles with no colours in common, so each needs its own palette
+        let palettes = |tiles: std::ops::Range<u8>| -> Vec<palette16::Palette16> {
+            tiles
+                .map(|tile| {
+                    let colours: Vec<_> = (0..15)
+                        .map(|i| Colour::from_rgb(tile * 15 + i, 0, 0, 255))
+                        .collect();
+                    palette16::Palette16::from(&colours)
+                })
+                .collect()
+        };
+
+        let forest = "forest::TREES".to_string();
+        let hud = "hud::HUD".to_string();
+
+        let fits = optimise_palette_group(&[(&forest, palettes(0..9))], None);
+        assert!(fits.is_ok());
+
+        let err =
+            optimise_palette_group(&[(&forest, palettes(0..9)), (&hud, palettes(9..17))], None)
+                .unwrap_err();
+        assert!(err.contains("forest::TREES (9)"), "{err}");
+        assert!(err.contains("hud::HUD (8)"), "{err}");
+    }
+}
@@ -0,0 +1,509 @@
+use snafu::Snafu;
+
+use crate::{
+    colour::Colour,
+    image_loader::Image,
+    quantise::{self, Dither, Rgb5},
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+const MAX_COLOURS: usize = 256;
+const MAX_COLOURS_PER_PALETTE: usize = 16;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub(crate) struct Palette16 {
+    colours: Vec<Colour>,
+}
+
+impl Palette16 {
+    pub fn new() -> Self {
+        Palette16 {
+            colours: Vec::with_capacity(MAX_COLOURS_PER_PALETTE),
+        }
+    }
+
+    pub fn add_colour(&mut self, colour: Colour) -> bool {
+        if self.colours.contains(&colour) {
+            return false;
+        }
+
+        if self.colours.len() == MAX_COLOURS_PER_PALETTE {
+            panic!("Can have at most 16 colours in a single palette");
+        }
+        self.colours.push(colour);
+        true
+    }
+
+    pub fn try_add_colour(&mut self, colour: Colour) -> bool {
+        if self.colours.contains(&colour) {
+            return true;
+        }
+
+        if self.colours.len() == MAX_COLOURS_PER_PALETTE {
+            return false;
+        }
+
+        self.colours.push(colour);
+        true
+    }
+
+    pub fn colour_index(&self, colour: Colour) -> u8 {
+        // A transparent color is always index 0
+        if colour.is_transparent() {
+            return 0;
+        }
+
+        self.colours
+            .iter()
+            .position(|c| *c == colour)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Can't get a colour index without it existing, looking for {:?}, got {:?}",
+                    colour, self.colours
+                )
+            }) as u8
+    }
+
+    pub fn colours(&self) -> impl Iterator<Item = &Colour> {
+        self.colours.iter()
+    }
+
+    fn with_transparent(&self, transparent_colour: Colour) -> Self {
+        let mut new_colours = self.colours.clone();
+        let transparent_colour_index = new_colours
+            .iter()
+            .position(|&c| c == transparent_colour)
+            .expect("Could not find tranparent colour in palette");
+        new_colours.swap(0, transparent_colour_index);
+
+        Self::from(&new_colours)
+    }
+
+    fn is_satisfied_by(&self, other: &Palette16) -> bool {
+        self.colours
+            .iter()
+            .collect::<HashSet<_>>()
+            .is_subset(&other.colours.iter().collect::<HashSet<_>>())
+    }
+}
+
+impl IntoIterator for Palette16 {
+    type Item = Colour;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.colours.into_iter()
+    }
+}
+
+impl<'a, T> From<T> for Palette16
+where
+    T: IntoIterator<Item = &'a Colour>,
+{
+    fn from(value: T) -> Self {
+        let mut palette = Palette16::new();
+        for colour in value.into_iter() {
+            palette.add_colour(*colour);
+        }
+
+        palette
+    }
+}
+
+pub(crate) struct Palette16Optimiser {
+    palettes: Vec<Palette16>,
+    colours: Vec<Colour>,
+    transparent_colour: Option<Colour>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Palette16OptimisationResults {
+    pub optimised_palettes: Vec<Palette16>,
+    pub assignments: Vec<usize>,
+    pub transparent_colour: Option<Colour>,
+}
+
+impl Palette16Optimiser {
+    pub fn new(transparent_colour: Option<Colour>) -> Self {
+        Palette16Optimiser {
+            palettes: vec![],
+            colours: Vec::new(),
+            transparent_colour,
+        }
+    }
+
+    pub fn add_palette(&mut self, palette: Palette16) {
+        self.palettes.push(palette.clone());
+
+        for colour in palette.colours {
+            if self.colours.contains(&colour) {
+                continue;
+            }
+
+            self.colours.push(colour);
+        }
+
+        if self.colours.len() > MAX_COLOURS {
+            panic!("Cannot have over 256 colours");
+        }
+    }
+
+    pub fn optimise_palettes(&self) -> Result<Palette16OptimisationResults, DoesNotFitError> {
+        let transparent_colour = self
+            .transparent_colour
+            .unwrap_or_else(|| Colour::from_rgb(255, 0, 255, 0));
+
+        let palettes_to_optimise = self
+            .palettes
+            .iter()
+            .cloned()
+            .map(|mut palette| {
+                // ensure each palette we're creating the covering for has the transparent colour in it
+                palette.add_colour(transparent_colour);
+                palette
+            })
+            .collect::<BTreeSet<Palette16>>()
+            .into_iter()
+            .map(|palette| palette.colours)
+            .collect::<Vec<_>>();
+
+        let packed_palettes =
+            pagination_packing::overload_and_remove::<_, _, Vec<_>>(&palettes_to_optimise, 16);
+
+        let optimised_palettes = packed_palettes
+            .iter()
+            .map(|packed_palette| {
+                let colours = packed_palette.unique_symbols(&palettes_to_optimise);
+                Palette16::from(colours).with_transparent(transparent_colour)
+            })
+            .collect::<Vec<_>>();
+
+        if optimised_palettes.len() > 16 {
+            return Err(DoesNotFitError {
+                count: packed_palettes.len(),
+            });
+        }
+
+        let mut assignments = vec![0; self.palettes.len()];
+
+        for (i, overall_palette) in self.palettes.iter().enumerate() {
+            assignments[i] = optimised_palettes
+                .iter()
+                .position(|palette| overall_palette.is_satisfied_by(palette))
+                .unwrap();
+        }
+
+        Ok(Palette16OptimisationResults {
+            optimised_palettes,
+            assignments,
+            transparent_colour: self.transparent_colour,
+        })
+    }
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(display(
+    "Could not fit colours into palette, needed {count} bins but can have at most 16"
+))]
+pub struct DoesNotFitError {
+    count: usize,
+}
+
+impl DoesNotFitError {
+    /// The number of palettes which would be needed
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Reduces the colours in an image so that every tile fits in a single
+/// palette, and all the tiles fit in the 16 available palettes.
+///
+/// Images which already fit are returned with their colours rounded to RGB15,
+/// which doesn't change how they look on the GBA. Otherwise the tiles are
+/// grouped by their average colour into at most 16 groups, and each group gets
+/// a palette of 15 colours chosen by median cut.
+pub(crate) fn quantise(
+    image: &Image,
+    tile_width: usize,
+    tile_height: usize,
+    transparent_colour: Option<Colour>,
+    dither: Dither,
+) -> Image {
+    let is_transparent =
+        |colour: Colour| colour.is_transparent() || Some(colour) == transparent_colour;
+
+    let tiles_x = image.width.div_ceil(tile_width);
+    let tiles_y = image.height.div_ceil(tile_height);
+
+    let tile_colours = |tile_x: usize, tile_y: usize| {
+        (tile_y * tile_height..((tile_y + 1) * tile_height).min(image.height)).flat_map(move |y| {
+            (tile_x * tile_width..((tile_x + 1) * tile_width).min(image.width))
+                .map(move |x| image.colour(x, y))
+        })
+    };
+
+    let histograms: Vec<_> = (0..tiles_y)
+        .flat_map(|y| (0..tiles_x).map(move |x| (x, y)))
+        .map(|(x, y)| quantise::histogram(tile_colours(x, y), is_transparent))
+        .collect();
+
+    let snapped = Image::new(
+        image.width,
+        image.height,
+        image
+            .colours()
+            .iter()
+            .map(|&colour| {
+                if is_transparent(colour) {
+                    colour
+                } else {
+                    quantise::snap(colour)
+                }
+            })
+            .collect(),
+    );
+
+    if fits_in_palettes(&histograms, transparent_colour) {
+        return snapped;
+    }
+
+    let groups = group_tiles(&histograms, MAX_COLOURS / MAX_COLOURS_PER_PALETTE);
+
+    let group_palettes: Vec<_> = (0..=groups.iter().copied().max().unwrap_or(0))
+        .map(|group| {
+            let mut histogram = HashMap::new();
+            for tile in histograms
+                .iter()
+                .zip(&groups)
+                .filter(|&(_, &tile_group)| tile_group == group)
+                .map(|(tile, _)| tile)
+            {
+                for (&colour, &count) in tile {
+                    *histogram.entry(colour).or_default() += count;
+                }
+            }
+
+            quantise::median_cut(&histogram, MAX_COLOURS_PER_PALETTE - 1)
+        })
+        .collect();
+
+    let colours = quantise::remap(
+        image.width,
+        image.height,
+        image.colours(),
+        is_transparent,
+        |x, y| &group_palettes[groups[x / tile_width + (y / tile_height) * tiles_x]],
+        dither,
+    );
+
+    Image::new(image.width, image.height, colours)
+}
+
+fn fits_in_palettes(
+    histograms: &[HashMap<Rgb5, usize>],
+    transparent_colour: Option<Colour>,
+) -> bool {
+    if histograms
+        .iter()
+        .any(|tile| tile.len() >= MAX_COLOURS_PER_PALETTE)
+    {
+        return false;
+    }
+
+    let all_colours: HashSet<_> = histograms.iter().flat_map(|tile| tile.keys()).collect();
+    if all_colours.len() >= MAX_COLOURS {
+        return false;
+    }
+
+    let mut optimiser = Palette16Optimiser::new(transparent_colour);
+    for tile in histograms {
+        optimiser.add_palette(Palette16::from(
+            &tile
+                .keys()
+                .map(|&colour| quantise::from_rgb5(colour))
+                .collect::<Vec<_>>(),
+        ));
+    }
+
+    optimiser.optimise_palettes().is_ok()
+}
+
+/// Groups tiles with k-means on their average colour. Returns the group each
+/// tile belongs to.
+fn group_tiles(histograms: &[HashMap<Rgb5, usize>], max_groups: usize) -> Vec<usize> {
+    let averages: Vec<Option<Rgb5>> = histograms
+        .iter()
+        .map(|tile| {
+            let total: usize = tile.values().sum();
+            (total > 0).then(|| {
+                [0, 1, 2].map(|channel| {
+                    let sum: usize = tile
+                        .iter()
+                        .map(|(colour, &count)| colour[channel] as usize * count)
+                        .sum();
+                    (sum / total) as i32
+                })
+            })
+        })
+        .collect();
+
+    let points: Vec<Rgb5> = averages.iter().flatten().copied().collect();
+    let Some(&first) = points.first() else {
+        return vec![0; histograms.len()];
+    };
+
+    // Start from the points which are furthest apart so similar tiles don't
+    // end up using up several groups.
+    let mut centroids = vec![first];
+    while centroids.len() < max_groups {
+        let Some(furthest) = points
+            .iter()
+            .copied()
+            .filter(|point| !centroids.contains(point))
+            .max_by_key(|&point| {
+                centroids
+                    .iter()
+                    .map(|&centroid| quantise::distance(point, centroid))
+                    .min()
+            })
+        else {
+            break;
+        };
+
+        centroids.push(furthest);
+    }
+
+    let assign = |centroids: &[Rgb5]| -> Vec<usize> {
+        averages
+            .iter()
+            .map(|average| {
+                average.map_or(0, |average| {
+                    (0..centroids.len())
+                        .min_by_key(|&i| quantise::distance(centroids[i], average))
+                        .unwrap()
+                })
+            })
+            .collect()
+    };
+
+    let mut groups = assign(&centroids);
+    for _ in 0..16 {
+        for (i, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<_> = averages
+                .iter()
+                .zip(&groups)
+                .filter_map(|(average, &group)| average.filter(|_| group == i))
+                .collect();
+
+            if !members.is_empty() {
+                *centroid = [0, 1, 2].map(|channel| {
+                    members.iter().map(|m| m[channel]).sum::<i32>() / members.len() as i32
+                });
+            }
+        }
+
+        let new_groups = assign(&centroids);
+        if new_groups == groups {
+            break;
+        }
+        groups = new_groups;
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod test {
+    use quickcheck::{Arbitrary, quickcheck};
+
+    use super::*;
+
+    quickcheck! {
+        fn less_than_256_colours_always_fits(palettes: Vec<Palette16>, transparent_colour: Colour) -> bool {
+            let mut optimiser = Palette16Optimiser::new(Some(transparent_colour));
+            for palette in palettes.clone().into_iter().take(16) {
+                optimiser.add_palette(palette);
+            }
+
+            let Ok(optimisation_results) = optimiser.optimise_palettes() else {
+                return false
+            };
+
+            check_palette_invariants(palettes.iter().take(16), optimisation_results, transparent_colour)
+        }
+    }
+
+    #[test]
+    fn quantise_makes_a_gradient_fit_in_16_palettes() {
+        // 32 tiles wide, every pixel a different colour
+        let (width, height) = (256, 8);
+        let colours = (0..height)
+            .flat_map(|y| {
+                (0..width)
+                    .map(move |x| Colour::from_rgb(x as u8, (y * 32) as u8, 255 - x as u8, 255))
+            })
+            .collect();
+
+        let image = Image::new(width, height, colours);
+        let quantised = quantise(&image, 8, 8, None, Dither::FloydSteinberg);
+
+        let mut optimiser = Palette16Optimiser::new(None);
+        for tile_x in 0..width / 8 {
+            let tile: Vec<_> = (0..8)
+                .flat_map(|y| (0..8).map(move |x| (x, y)))
+                .map(|(x, y)| quantised.colour(tile_x * 8 + x, y))
+                .collect();
+            optimiser.add_palette(Palette16::from(&tile));
+        }
+
+        assert!(optimiser.optimise_palettes().is_ok());
+    }
+
+    #[test]
+    fn quantise_keeps_images_which_already_fit() {
+        let red = Colour::from_rgb(255, 0, 0, 255);
+        let clear = Colour::from_rgb(0, 0, 0, 0);
+        let image = Image::new(8, 8, [red, clear].repeat(32));
+
+        let quantised = quantise(&image, 8, 8, None, Dither::FloydSteinberg);
+
+        assert_eq!(quantised.colours(), image.colours());
+    }
+
+    fn check_palette_invariants<'a>(
+        palettes: impl Iterator<Item = &'a Palette16>,
+        optimisation_results: Palette16OptimisationResults,
+        transparent_colour: Colour,
+    ) -> bool {
+        for (i, palette) in palettes.enumerate() {
+            let optimised_palette =
+                &optimisation_results.optimised_palettes[optimisation_results.assignments[i]];
+            if !palette.is_satisfied_by(optimised_palette) {
+                return false;
+            }
+
+            if optimised_palette.colour_index(transparent_colour) != 0 {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    impl Arbitrary for Palette16 {
+        fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+            let mut palette = Palette16::new();
+
+            let size: usize = Arbitrary::arbitrary(g);
+            // never entirely fill the palette, will give at most 15 colours
+            let size = size.rem_euclid(16);
+
+            for _ in 0..size {
+                palette.add_colour(Arbitrary::arbitrary(g));
+            }
+
+            palette
+        }
+    }
+}
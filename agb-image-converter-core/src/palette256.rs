@@ -4,6 +4,7 @@ use crate::{
     colour::Colour,
     image_loader::Image,
     palette16::{Palette16, Palette16OptimisationResults},
+    quantise::{self, Dither},
 };
 
 pub struct Palette256 {
@@ -42,10 +43,7 @@ impl Palette256 {
             .collect();
 
         let current_colours_set = BTreeSet::from_iter(optimised_palette_colours.iter().cloned());
-        let new_colours: BTreeSet<_> = self
-            .colours
-            .symmetric_difference(&current_colours_set)
-            .collect();
+        let new_colours: BTreeSet<_> = self.colours.difference(&current_colours_set).collect();
 
         assert!(
             new_colours.len() + optimised_palette_colours.len() <= 256,
@@ -70,3 +68,24 @@ impl Palette256 {
         }
     }
 }
+
+/// Reduces the colours in an image to the 255 which fit alongside the
+/// transparent colour in a 256 colour palette.
+pub(crate) fn quantise(image: &Image, transparent_colour: Option<Colour>, dither: Dither) -> Image {
+    let is_transparent =
+        |colour: Colour| colour.is_transparent() || Some(colour) == transparent_colour;
+
+    let histogram = quantise::histogram(image.colours().iter().copied(), is_transparent);
+    let palette = quantise::median_cut(&histogram, 255);
+
+    let colours = quantise::remap(
+        image.width,
+        image.height,
+        image.colours(),
+        is_transparent,
+        |_, _| &palette,
+        dither,
+    );
+
+    Image::new(image.width, image.height, colours)
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use image::{DynamicImage, GenericImageView};
+use snafu::{Snafu, ensure};
+use syn::{Ident, LitStr, Token, braced, parse::Parse, punctuated::Punctuated};
+
+use crate::{aseprite, colour::Colour};
+
+/// An alternative set of colours for an image, written as either
+///
+/// * `NAME "base.aseprite" => "variant.aseprite"`, where the variant has the
+///   same frames as the base but is recoloured, or
+/// * `NAME { "ff0000" => "00ff00", ... }`, listing each colour to replace.
+pub(crate) struct PaletteSwap {
+    pub name: Ident,
+    source: SwapSource,
+}
+
+enum SwapSource {
+    Images { base: String, variant: String },
+    Table(Vec<(Colour, Colour)>),
+}
+
+impl Parse for PaletteSwap {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+
+        let source = if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+
+            let entries = Punctuated::<(LitStr, LitStr), Token![,]>::parse_terminated_with(
+                &content,
+                |input| {
+                    let from: LitStr = input.parse()?;
+                    let _: Token![=>] = input.parse()?;
+                    let to: LitStr = input.parse()?;
+                    Ok((from, to))
+                },
+            )?;
+
+            let parse_colour = |lit: &LitStr| {
+                lit.value()
+                    .parse()
+                    .map_err(|msg| syn::Error::new_spanned(lit, msg))
+            };
+
+            SwapSource::Table(
+                entries
+                    .iter()
+                    .map(|(from, to)| Ok((parse_colour(from)?, parse_colour(to)?)))
+                    .collect::<syn::Result<_>>()?,
+            )
+        } else {
+            let base: LitStr = input.parse()?;
+            let _: Token![=>] = input.parse()?;
+            let variant: LitStr = input.parse()?;
+
+            SwapSource::Images {
+                base: base.value(),
+                variant: variant.value(),
+            }
+        };
+
+        Ok(Self { name, source })
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub(crate) enum PaletteSwapError {
+    #[snafu(display(
+        "Palette swap {name} has {variant} frames but the base image has {base} frames"
+    ))]
+    FrameCountMismatch {
+        name: String,
+        base: usize,
+        variant: usize,
+    },
+    #[snafu(display(
+        "Palette swap {name} has a frame of size {variant_width}x{variant_height} but the base image is {base_width}x{base_height}"
+    ))]
+    SizeMismatch {
+        name: String,
+        base_width: u32,
+        base_height: u32,
+        variant_width: u32,
+        variant_height: u32,
+    },
+    #[snafu(display(
+        "Palette swap {name} maps {from:?} to both {first:?} and {second:?}, each colour can only have one replacement"
+    ))]
+    ConflictingColours {
+        name: String,
+        from: Colour,
+        first: Colour,
+        second: Colour,
+    },
+}
+
+/// Replacement colours, keyed by the RGB15 value of the original colour.
+#[derive(Clone)]
+pub(crate) struct ColourMap(HashMap<u16, u16>);
+
+impl ColourMap {
+    /// Returns the replacement for the colour, or the colour itself if it
+    /// isn't replaced.
+    pub fn apply(&self, rgb15: u16) -> u16 {
+        self.0.get(&rgb15).copied().unwrap_or(rgb15)
+    }
+}
+
+impl PaletteSwap {
+    /// The files this palette swap reads, resolved with `resolve_path`.
+    pub fn input_files(&self, resolve_path: impl Fn(&str) -> PathBuf) -> Vec<PathBuf> {
+        match &self.source {
+            SwapSource::Images { base, variant } => vec![resolve_path(base), resolve_path(variant)],
+            SwapSource::Table(_) => vec![],
+        }
+    }
+
+    pub fn colour_map(
+        &self,
+        resolve_path: impl Fn(&str) -> PathBuf,
+    ) -> Result<ColourMap, PaletteSwapError> {
+        let name = self.name.to_string();
+
+        let pairs = match &self.source {
+            SwapSource::Table(table) => table.clone(),
+            SwapSource::Images { base, variant } => {
+                let base = load_frames(&resolve_path(base));
+                let variant = load_frames(&resolve_path(variant));
+
+                colour_pairs(&name, &base, &variant)?
+            }
+        };
+
+        let mut map: HashMap<u16, Colour> = HashMap::new();
+        for (from, to) in pairs {
+            if from.is_transparent() {
+                continue;
+            }
+
+            let first = *map.entry(from.to_rgb15()).or_insert(to);
+            ensure!(
+                first.to_rgb15() == to.to_rgb15(),
+                ConflictingColoursSnafu {
+                    name: name.clone(),
+                    from,
+                    first,
+                    second: to,
+                }
+            );
+        }
+
+        Ok(ColourMap(
+            map.into_iter()
+                .map(|(from, to)| (from, to.to_rgb15()))
+                .collect(),
+        ))
+    }
+}
+
+fn load_frames(path: &Path) -> Vec<DynamicImage> {
+    if path.extension() == Some(OsStr::new("aseprite")) {
+        aseprite::generate_from_file(path).0
+    } else {
+        vec![image::open(path).expect("Expected image to exist")]
+    }
+}
+
+/// Pairs up the colours of every pixel in the base frames with the colour at
+/// the same position in the variant frames.
+fn colour_pairs(
+    name: &str,
+    base: &[DynamicImage],
+    variant: &[DynamicImage],
+) -> Result<Vec<(Colour, Colour)>, PaletteSwapError> {
+    ensure!(
+        base.len() == variant.len(),
+        FrameCountMismatchSnafu {
+            name,
+            base: base.len(),
+            variant: variant.len(),
+        }
+    );
+
+    let mut pairs = Vec::new();
+    for (base, variant) in base.iter().zip(variant) {
+        let (base_width, base_height) = base.dimensions();
+        let (variant_width, variant_height) = variant.dimensions();
+        ensure!(
+            (base_width, base_height) == (variant_width, variant_height),
+            SizeMismatchSnafu {
+                name,
+                base_width,
+                base_height,
+                variant_width,
+                variant_height,
+            }
+        );
+
+        let to_colour = |pixel: image::Rgba<u8>| {
+            Colour::from_rgb(pixel.0[0], pixel.0[1], pixel.0[2], pixel.0[3])
+        };
+
+        pairs.extend(
+            base.pixels()
+                .zip(variant.pixels())
+                .map(|((_, _, from), (_, _, to))| (to_colour(from), to_colour(to))),
+        );
+    }
+
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]]) -> DynamicImage {
+        let mut image = RgbaImage::new(pixels.len() as u32, 1);
+        for (x, &pixel) in pixels.iter().enumerate() {
+            image.put_pixel(x as u32, 0, Rgba(pixel));
+        }
+
+        DynamicImage::ImageRgba8(image)
+    }
+
+    #[test]
+    fn pairs_colours_by_position() {
+        let base = image(&[[255, 0, 0, 255], [0, 0, 0, 0]]);
+        let variant = image(&[[0, 255, 0, 255], [0, 0, 0, 0]]);
+
+        let pairs = colour_pairs("TEST", &[base], &[variant]).unwrap();
+
+        assert_eq!(
+            pairs[0],
+            (
+                Colour::from_rgb(255, 0, 0, 255),
+                Colour::from_rgb(0, 255, 0, 255)
+            )
+        );
+    }
+
+    #[test]
+    fn mismatched_sizes_are_an_error() {
+        let base = image(&[[255, 0, 0, 255]]);
+        let variant = image(&[[255, 0, 0, 255], [255, 0, 0, 255]]);
+
+        assert!(matches!(
+            colour_pairs("TEST", &[base], &[variant]),
+            Err(PaletteSwapError::SizeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn conflicting_replacements_are_an_error() {
+        let swap: PaletteSwap =
+            syn::parse_str(r#"TEST { "ff0000" => "00ff00", "ff0000" => "0000ff" }"#).unwrap();
+
+        assert!(matches!(
+            swap.colour_map(|path: &str| PathBuf::from(path)),
+            Err(PaletteSwapError::ConflictingColours { .. })
+        ));
+    }
+
+    #[test]
+    fn unmapped_colours_are_kept() {
+        let swap: PaletteSwap = syn::parse_str(r#"TEST { "ff0000" => "00ff00" }"#).unwrap();
+        let map = swap.colour_map(|path: &str| PathBuf::from(path)).unwrap();
+
+        let red = Colour::from_rgb(255, 0, 0, 255).to_rgb15();
+        let green = Colour::from_rgb(0, 255, 0, 255).to_rgb15();
+        let blue = Colour::from_rgb(0, 0, 255, 255).to_rgb15();
+
+        assert_eq!(map.apply(red), green);
+        assert_eq!(map.apply(blue), blue);
+    }
+}
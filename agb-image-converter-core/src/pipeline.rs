@@ -0,0 +1,912 @@
+//! Converting assets from a build script rather than a proc macro.
+//!
+//! The `include_*` macros in `agb` convert their assets every time the crate
+//! is compiled. For larger projects, you can instead list your assets in a
+//! TOML manifest and convert them once from `build.rs`:
+//!
+//! ```toml
+//! [[backgrounds]]
+//! module = "backgrounds"
+//! transparent_colour = "2ce8f4"
+//! images = [
+//!     { name = "BEACH", file = "gfx/beach-background.aseprite", colours = 256, options = ["deduplicate"] },
+//! ]
+//!
+//! [[sprites]]
+//! module = "sprites"
+//! files = ["gfx/crab.aseprite", { file = "gfx/boss.aseprite", options = ["meta"] }]
+//!
+//! [[fonts]]
+//! name = "FONT"
+//! file = "fonts/font.ttf"
+//! size = 12
+//!
+//! [[fonts]]
+//! name = "PIXEL_FONT"
+//! file = "fonts/glyphs.png"
+//! cell_width = 8
+//! cell_height = 8
+//! characters = " !?0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ"
+//! ```
+//!
+//! Every `[[backgrounds]]` entry normally gets its own 16 palettes, like a
+//! separate `include_background_gfx!` call. Entries which are shown at the same
+//! time can share their palettes by giving them the same `group`. The entries
+//! in a group are optimised together into a module named after the group, which
+//! holds the shared `PALETTES`, and each entry's module is a submodule of it
+//! which is also exported under its own name. If the group needs more than 16
+//! palettes, [`build`](AssetPipeline::build) fails with an error listing how
+//! many each image needs.
+//!
+//! ```toml
+//! [[backgrounds]]
+//! module = "beach"
+//! group = "world"
+//! images = [{ name = "BEACH", file = "gfx/beach-background.aseprite" }]
+//!
+//! [[backgrounds]]
+//! module = "hud"
+//! group = "world"
+//! images = [{ name = "HUD", file = "gfx/hud.aseprite" }]
+//! ```
+//!
+//! Here `world::PALETTES` are the palettes for both `beach::BEACH` and
+//! `hud::HUD`.
+//!
+//! Fonts take the same arguments as `include_font!`, so bdf fonts only need a
+//! `file`. They can also be given a `subset` list of strings and a
+//! `subset_files` list of files to only include the letters which are used.
+//!
+//! ```rust,no_run
+//! // in build.rs
+//! agb_image_converter_core::AssetPipeline::new("assets.toml")
+//!     .build()
+//!     .expect("failed to convert assets");
+//! ```
+//!
+//! and then include the generated modules in your game with
+//!
+//! ```rust,ignore
+//! include!(concat!(env!("OUT_DIR"), "/agb_assets.rs"));
+//! ```
+//!
+//! The generated code is the same as the corresponding macro would produce.
+//! Each entry is cached in `OUT_DIR` keyed by a hash of its settings and the
+//! contents of its files, so only entries which have changed are converted
+//! again.
+
+use std::{
+    fs, io,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use proc_macro2::{Literal, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use serde::Deserialize;
+use snafu::{ResultExt, Snafu};
+
+/// The name of the file written to the output directory
+pub const OUTPUT_FILE_NAME: &str = "agb_assets.rs";
+
+const CACHE_DIRECTORY: &str = "agb-asset-cache";
+
+/// Errors which can happen while converting the assets in a manifest
+#[derive(Debug, Snafu)]
+pub enum PipelineError {
+    /// The manifest couldn't be read
+    #[snafu(display("Failed to read asset manifest {}: {source}", path.display()))]
+    ReadManifest {
+        /// The path of the manifest
+        path: PathBuf,
+        /// The underlying error
+        source: io::Error,
+    },
+    /// The manifest isn't in the expected format
+    #[snafu(display("Failed to parse asset manifest {}: {source}", path.display()))]
+    ParseManifest {
+        /// The path of the manifest
+        path: PathBuf,
+        /// The underlying error
+        source: toml::de::Error,
+    },
+    /// The manifest contains a value which can't be converted
+    #[snafu(display("Invalid entry in asset manifest: {message}"))]
+    InvalidEntry {
+        /// What is wrong with the entry
+        message: String,
+    },
+    /// An asset referred to by the manifest couldn't be read
+    #[snafu(display("Failed to read asset {}: {source}", path.display()))]
+    ReadAsset {
+        /// The path of the asset
+        path: PathBuf,
+        /// The underlying error
+        source: io::Error,
+    },
+    /// The generated code couldn't be written
+    #[snafu(display("Failed to write {}: {source}", path.display()))]
+    WriteOutput {
+        /// The path being written to
+        path: PathBuf,
+        /// The underlying error
+        source: io::Error,
+    },
+    /// An asset couldn't be converted
+    #[snafu(display("Failed to convert {}: {message}", describe_paths(paths)))]
+    ConvertAsset {
+        /// The paths of the assets being converted
+        paths: Vec<PathBuf>,
+        /// Why the conversion failed
+        message: String,
+    },
+    /// No output directory was given and `OUT_DIR` isn't set
+    #[snafu(display("OUT_DIR is not set, the asset pipeline should be run from a build script"))]
+    MissingOutDir,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(default)]
+    backgrounds: Vec<BackgroundsEntry>,
+    #[serde(default)]
+    sprites: Vec<SpritesEntry>,
+    #[serde(default)]
+    fonts: Vec<FontEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BackgroundsEntry {
+    module: String,
+    group: Option<String>,
+    transparent_colour: Option<String>,
+    images: Vec<BackgroundImage>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BackgroundImage {
+    name: String,
+    file: String,
+    colours: Option<u32>,
+    #[serde(default)]
+    options: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpritesEntry {
+    module: String,
+    #[serde(default = "default_sprite_colours")]
+    colours: u32,
+    files: Vec<SpriteFile>,
+}
+
+fn default_sprite_colours() -> u32 {
+    16
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SpriteFile {
+    Path(String),
+    WithOptions {
+        file: String,
+        #[serde(default)]
+        options: Vec<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FontEntry {
+    name: String,
+    file: String,
+    /// Only for ttf fonts
+    size: Option<f32>,
+    /// Only for png glyph sheets
+    cell_width: Option<u32>,
+    cell_height: Option<u32>,
+    characters: Option<String>,
+    #[serde(default)]
+    subset: Vec<String>,
+    #[serde(default)]
+    subset_files: Vec<String>,
+}
+
+/// A single conversion, along with the files it reads
+struct Job {
+    input: TokenStream,
+    files: Vec<PathBuf>,
+    convert: fn(TokenStream) -> TokenStream,
+    wrap: Box<dyn Fn(TokenStream) -> TokenStream>,
+}
+
+/// Converts the assets listed in a manifest into a rust file in `OUT_DIR`.
+/// See the [module level documentation](self) for the manifest format.
+pub struct AssetPipeline {
+    manifest: PathBuf,
+    out_dir: Option<PathBuf>,
+}
+
+impl AssetPipeline {
+    /// Creates a pipeline for the given manifest. Paths in the manifest are
+    /// relative to the directory containing it.
+    pub fn new(manifest: impl Into<PathBuf>) -> Self {
+        Self {
+            manifest: manifest.into(),
+            out_dir: None,
+        }
+    }
+
+    /// Writes the output somewhere other than `OUT_DIR`
+    #[must_use]
+    pub fn with_out_dir(self, out_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            out_dir: Some(out_dir.into()),
+            ..self
+        }
+    }
+
+    /// Converts every asset in the manifest, reusing cached output for any
+    /// entries which haven't changed. Returns the path of the generated file,
+    /// and tells cargo to rerun the build script when any of the assets change.
+    /// Assets which fail to convert are reported as a
+    /// [`PipelineError::ConvertAsset`] and aren't cached.
+    pub fn build(&self) -> Result<PathBuf, PipelineError> {
+        let out_dir = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or(PipelineError::MissingOutDir)?,
+        };
+
+        let manifest_content = fs::read_to_string(&self.manifest).context(ReadManifestSnafu {
+            path: &self.manifest,
+        })?;
+        let manifest: Manifest = toml::from_str(&manifest_content).context(ParseManifestSnafu {
+            path: &self.manifest,
+        })?;
+
+        println!("cargo::rerun-if-changed={}", self.manifest.display());
+
+        let root = self
+            .manifest
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let root = fs::canonicalize(root).context(ReadManifestSnafu { path: root })?;
+
+        let cache_dir = out_dir.join(CACHE_DIRECTORY);
+        fs::create_dir_all(&cache_dir).context(WriteOutputSnafu { path: &cache_dir })?;
+
+        let mut output = String::new();
+        for job in jobs(&manifest, &root)? {
+            let code = run_cached(&job, &cache_dir)?;
+            output.push_str(&(job.wrap)(code).to_string());
+            output.push('\n');
+        }
+
+        let output_path = out_dir.join(OUTPUT_FILE_NAME);
+        fs::write(&output_path, output).context(WriteOutputSnafu { path: &output_path })?;
+
+        Ok(output_path)
+    }
+}
+
+fn jobs(manifest: &Manifest, root: &Path) -> Result<Vec<Job>, PipelineError> {
+    let mut jobs = Vec::new();
+
+    // Entries in the same palette group become submodules of a single call,
+    // so they're optimised together and share its `PALETTES`.
+    let mut groups: Vec<(Option<&str>, Vec<&BackgroundsEntry>)> = Vec::new();
+    for backgrounds in &manifest.backgrounds {
+        let group = backgrounds.group.as_deref();
+        match groups
+            .iter_mut()
+            .find(|(existing, _)| group.is_some() && *existing == group)
+        {
+            Some((_, members)) => members.push(backgrounds),
+            None => groups.push((group, vec![backgrounds])),
+        }
+    }
+
+    for (group, members) in groups {
+        let mut transparent_colours = members
+            .iter()
+            .filter_map(|backgrounds| backgrounds.transparent_colour.as_ref());
+        let transparent_colour = transparent_colours.next();
+        if let Some(other) = transparent_colours.find(|&colour| Some(colour) != transparent_colour)
+        {
+            return InvalidEntrySnafu {
+                message: format!(
+                    "backgrounds in palette group `{}` have different transparent colours, {} and {other}",
+                    group.unwrap_or_default(),
+                    transparent_colour.unwrap(),
+                ),
+            }
+            .fail();
+        }
+        let transparent_colour = transparent_colour.map(|colour| quote!(#colour,));
+
+        let mut files = Vec::new();
+        let mut modules = Vec::new();
+        let mut entries = Vec::new();
+        for backgrounds in &members {
+            let module = parse_ident(&backgrounds.module)?;
+
+            let images = backgrounds
+                .images
+                .iter()
+                .map(|image| {
+                    let name = parse_ident(&image.name)?;
+                    let colours = image.colours.map(Literal::u32_unsuffixed);
+                    let options = parse_idents(&image.options)?;
+                    let file = root.join(&image.file);
+                    let file_str = file.to_string_lossy().into_owned();
+                    files.push(file);
+
+                    Ok(quote!(#name => #colours #(#options)* #file_str))
+                })
+                .collect::<Result<Vec<_>, PipelineError>>()?;
+
+            entries.push(match group {
+                Some(_) => quote!(mod #module { #(#images),* }),
+                None => quote!(#(#images),*),
+            });
+            modules.push(module);
+        }
+
+        let (input, wrap): (_, Box<dyn Fn(TokenStream) -> TokenStream>) = match group {
+            Some(group) => {
+                let group = parse_ident(group)?;
+                if modules.contains(&group) {
+                    return InvalidEntrySnafu {
+                        message: format!(
+                            "the palette group `{group}` has the same name as one of its modules"
+                        ),
+                    }
+                    .fail();
+                }
+
+                (
+                    quote!(pub mod #group, #transparent_colour #(#entries),*),
+                    Box::new(move |code| {
+                        quote! {
+                            #code
+                            pub use #group::{#(#modules),*};
+                        }
+                    }),
+                )
+            }
+            None => {
+                let module = &modules[0];
+                (
+                    quote!(pub mod #module, #transparent_colour #(#entries),*),
+                    Box::new(|code| code),
+                )
+            }
+        };
+
+        jobs.push(Job {
+            input,
+            files,
+            convert: crate::include_background_gfx,
+            wrap,
+        });
+    }
+
+    for sprites in &manifest.sprites {
+        let module = parse_ident(&sprites.module)?;
+
+        let mut files = Vec::new();
+        let entries = sprites
+            .files
+            .iter()
+            .map(|entry| {
+                let (file, options) = match entry {
+                    SpriteFile::Path(file) => (file, &[][..]),
+                    SpriteFile::WithOptions { file, options } => (file, &options[..]),
+                };
+
+                let options = parse_idents(options)?;
+                let file = root.join(file);
+                let file_str = file.to_string_lossy().into_owned();
+                files.push(file);
+
+                Ok(quote!(#(#options)* #file_str))
+            })
+            .collect::<Result<Vec<_>, PipelineError>>()?;
+
+        // This matches the imports made by `include_aseprite!` and
+        // `include_aseprite_256!` in agb
+        let (convert, palette_import): (fn(TokenStream) -> TokenStream, _) = match sprites.colours {
+            16 => (crate::include_aseprite_inner, quote!()),
+            256 => (crate::include_aseprite_256_inner, quote!(PaletteMulti,)),
+            colours => {
+                return InvalidEntrySnafu {
+                    message: format!("sprites can have 16 or 256 colours, got {colours}"),
+                }
+                .fail();
+            }
+        };
+
+        jobs.push(Job {
+            input: quote!(#(#entries),*),
+            files,
+            convert,
+            wrap: Box::new(move |code| {
+                quote! {
+                    pub mod #module {
+                        #[allow(unused_imports)]
+                        use agb::display::object::{
+                            MetaSprite, MetaSpritePiece, MetaTag, #palette_import PaletteSwap, Size, Sprite, Tag,
+                        };
+                        use agb::display::{Palette16, Rgb15};
+                        use agb::align_bytes;
+
+                        #code
+                    }
+                }
+            }),
+        });
+    }
+
+    for font in &manifest.fonts {
+        let name = parse_ident(&font.name)?;
+        let file = root.join(&font.file);
+        let file_str = file.to_string_lossy().into_owned();
+        let arguments = match (
+            font.size,
+            font.cell_width,
+            font.cell_height,
+            &font.characters,
+        ) {
+            (Some(size), None, None, None) => {
+                let size = Literal::f32_unsuffixed(size);
+                quote!(, #size)
+            }
+            (None, Some(cell_width), Some(cell_height), Some(characters)) => {
+                let cell_width = Literal::u32_unsuffixed(cell_width);
+                let cell_height = Literal::u32_unsuffixed(cell_height);
+                quote!(, #cell_width, #cell_height, #characters)
+            }
+            (None, None, None, None) => quote!(),
+            _ => {
+                return InvalidEntrySnafu {
+                    message: format!(
+                        "font `{}` should have a size for ttf fonts, cell_width, cell_height and characters for png fonts, or none of these for bdf fonts",
+                        font.name
+                    ),
+                }
+                .fail();
+            }
+        };
+
+        let mut files = vec![file];
+
+        let subset = &font.subset;
+        let subset = (!subset.is_empty()).then(|| quote!(, subset = [#(#subset),*]));
+
+        let subset_files: Vec<_> = font
+            .subset_files
+            .iter()
+            .map(|path| root.join(path))
+            .collect();
+        let subset_file_strs = subset_files
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned());
+        let subset_files_arg =
+            (!subset_files.is_empty()).then(|| quote!(, subset_files = [#(#subset_file_strs),*]));
+        files.extend(subset_files);
+
+        jobs.push(Job {
+            input: quote!(#file_str #arguments #subset #subset_files_arg),
+            files,
+            convert: crate::include_font,
+            wrap: Box::new(move |code| {
+                quote! {
+                    pub static #name: agb::display::font::Font = {
+                        use agb::display::font::{Font, FontLetter};
+                        #code
+                    };
+                }
+            }),
+        });
+    }
+
+    Ok(jobs)
+}
+
+fn run_cached(job: &Job, cache_dir: &Path) -> Result<TokenStream, PipelineError> {
+    let mut hash = Fnv1a::new();
+    hash.write(env!("CARGO_PKG_VERSION").as_bytes());
+    hash.write(job.input.to_string().as_bytes());
+
+    for file in &job.files {
+        println!("cargo::rerun-if-changed={}", file.display());
+
+        let content = fs::read(file).context(ReadAssetSnafu { path: file })?;
+        hash.write(&content);
+    }
+
+    let cache_path = cache_dir.join(format!("{:016x}.rs", hash.finish()));
+
+    let code = match fs::read_to_string(&cache_path) {
+        Ok(code) => code,
+        Err(_) => {
+            let code = convert(job)?.to_string();
+            fs::write(&cache_path, &code).context(WriteOutputSnafu { path: &cache_path })?;
+            code
+        }
+    };
+
+    code.parse().map_err(|err| PipelineError::InvalidEntry {
+        message: format!(
+            "cached output in {} is invalid: {err}",
+            cache_path.display()
+        ),
+    })
+}
+
+/// Runs the conversion, turning panics and `compile_error!`s in the output
+/// into errors so that a bad asset fails the build script rather than being
+/// cached.
+fn convert(job: &Job) -> Result<TokenStream, PipelineError> {
+    let code = panic::catch_unwind(AssertUnwindSafe(|| (job.convert)(job.input.clone()))).map_err(
+        |payload| {
+            let message = payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or("the converter panicked");
+
+            PipelineError::ConvertAsset {
+                paths: job.files.clone(),
+                message: message.to_string(),
+            }
+        },
+    )?;
+
+    match compile_error_message(code.clone()) {
+        Some(message) => ConvertAssetSnafu {
+            paths: job.files.clone(),
+            message,
+        }
+        .fail(),
+        None => Ok(code),
+    }
+}
+
+/// Finds the message of the first `compile_error!` in the generated code.
+fn compile_error_message(code: TokenStream) -> Option<String> {
+    let mut tokens = code.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident == "compile_error" => {
+                if let Some(TokenTree::Punct(punct)) = tokens.peek()
+                    && punct.as_char() == '!'
+                {
+                    tokens.next();
+                    if let Some(TokenTree::Group(arguments)) = tokens.next() {
+                        let message = syn::parse2::<syn::LitStr>(arguments.stream())
+                            .map(|message| message.value())
+                            .unwrap_or_else(|_| arguments.stream().to_string());
+                        return Some(message);
+                    }
+                }
+            }
+            TokenTree::Group(group) => {
+                if let Some(message) = compile_error_message(group.stream()) {
+                    return Some(message);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn describe_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn parse_ident(name: &str) -> Result<proc_macro2::Ident, PipelineError> {
+    syn::parse_str(name).map_err(|_| PipelineError::InvalidEntry {
+        message: format!("`{name}` is not a valid rust identifier"),
+    })
+}
+
+fn parse_idents(names: &[String]) -> Result<Vec<proc_macro2::Ident>, PipelineError> {
+    names
+        .iter()
+        .map(|name| parse_ident(name).map(|_| format_ident!("{}", name)))
+        .collect()
+}
+
+/// The cache key only needs to be stable between builds, so use a simple hash
+/// rather than pulling in a dependency.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("agb-asset-pipeline-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn converts_and_caches_manifest() {
+        let gfx = Path::new(env!("CARGO_MANIFEST_DIR")).join("../agb/examples/gfx");
+        let dir = temp_dir("converts");
+
+        let manifest = dir.join("assets.toml");
+        fs::write(
+            &manifest,
+            format!(
+                r#"
+                [[backgrounds]]
+                module = "backgrounds"
+                images = [{{ name = "HUD", file = "{gfx}/hud.aseprite", options = ["deduplicate"] }}]
+
+                [[sprites]]
+                module = "sprites"
+                files = [{{ file = "{gfx}/crab.aseprite", options = ["trim"] }}]
+
+                [[fonts]]
+                name = "FONT"
+                file = "{gfx}/../../fnt/ark-pixel-10px-proportional-latin.bdf"
+                subset = ["Hello"]
+                "#,
+                gfx = gfx.display()
+            ),
+        )
+        .unwrap();
+
+        let out_dir = dir.join("out");
+        let pipeline = AssetPipeline::new(&manifest).with_out_dir(&out_dir);
+
+        let output_path = pipeline.build().unwrap();
+        let output = fs::read_to_string(&output_path).unwrap();
+        assert!(output.contains("pub mod backgrounds"));
+        assert!(output.contains("pub mod sprites"));
+        assert!(output.contains("pub static FONT"));
+        assert!(output.parse::<TokenStream>().is_ok());
+
+        let cached = || fs::read_dir(out_dir.join(CACHE_DIRECTORY)).unwrap().count();
+        assert_eq!(cached(), 3);
+
+        // building again reuses the cache and produces the same output
+        pipeline.build().unwrap();
+        assert_eq!(cached(), 3);
+        assert_eq!(fs::read_to_string(&output_path).unwrap(), output);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn palette_groups_share_palettes_between_entries() {
+        let gfx = Path::new(env!("CARGO_MANIFEST_DIR")).join("../agb/examples/gfx");
+        let dir = temp_dir("groups");
+
+        let manifest = dir.join("assets.toml");
+        fs::write(
+            &manifest,
+            format!(
+                r#"
+                [[backgrounds]]
+                module = "beach"
+                group = "world"
+                images = [{{ name = "BEACH", file = "{gfx}/beach-background.aseprite" }}]
+
+                [[backgrounds]]
+                module = "title"
+                images = [{{ name = "TITLE", file = "{gfx}/beach-background.aseprite" }}]
+
+                [[backgrounds]]
+                module = "hud"
+                group = "world"
+                images = [{{ name = "HUD", file = "{gfx}/hud.aseprite" }}]
+                "#,
+                gfx = gfx.display()
+            ),
+        )
+        .unwrap();
+
+        let output_path = AssetPipeline::new(&manifest)
+            .with_out_dir(dir.join("out"))
+            .build()
+            .unwrap();
+        let output = fs::read_to_string(output_path).unwrap();
+
+        assert!(output.contains("pub mod world"));
+        assert!(output.contains("pub mod title"));
+        assert!(output.parse::<TokenStream>().is_ok());
+
+        // one set of palettes for the group, and one for the entry outside it
+        assert_eq!(output.matches("pub static PALETTES").count(), 2);
+        assert!(
+            output.contains(
+                &quote!(
+                    pub use world::{beach, hud};
+                )
+                .to_string()
+            )
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn palette_group_over_budget_fails_naming_the_images() {
+        use image::{Rgba, RgbaImage};
+
+        let dir = temp_dir("group-budget");
+
+        // Every tile has 15 colours which no other tile uses, so each tile
+        // needs a palette of its own.
+        let write_tiles = |name: &str, tiles: std::ops::Range<u32>| {
+            let mut image = RgbaImage::new(tiles.len() as u32 * 8, 8);
+            for (x, tile) in tiles.enumerate() {
+                for i in 0..64 {
+                    let colour = (i % 15) as u8;
+                    image.put_pixel(
+                        x as u32 * 8 + i % 8,
+                        i / 8,
+                        Rgba([colour * 16, tile as u8 * 8, 0, 255]),
+                    );
+                }
+            }
+
+            let path = dir.join(name);
+            image.save(&path).unwrap();
+            path
+        };
+
+        let forest = write_tiles("forest.png", 0..9);
+        let hud = write_tiles("hud.png", 9..17);
+
+        let manifest = dir.join("assets.toml");
+        let entry = |module: &str, name: &str, file: &Path| {
+            format!(
+                r#"
+                [[backgrounds]]
+                module = "{module}"
+                group = "world"
+                images = [{{ name = "{name}", file = "{}" }}]
+                "#,
+                file.display()
+            )
+        };
+
+        // each entry fits on its own
+        for (module, name, file) in [("forest", "TREES", &forest), ("hud", "HUD", &hud)] {
+            fs::write(&manifest, entry(module, name, file)).unwrap();
+            AssetPipeline::new(&manifest)
+                .with_out_dir(dir.join("out"))
+                .build()
+                .unwrap();
+        }
+
+        fs::write(
+            &manifest,
+            entry("forest", "TREES", &forest) + &entry("hud", "HUD", &hud),
+        )
+        .unwrap();
+        let result = AssetPipeline::new(&manifest)
+            .with_out_dir(dir.join("out"))
+            .build();
+
+        let Err(PipelineError::ConvertAsset { message, .. }) = result else {
+            panic!("expected the group to be over budget");
+        };
+        assert!(message.contains("forest::TREES (9)"), "{message}");
+        assert!(message.contains("hud::HUD (8)"), "{message}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_conversions_are_not_cached() {
+        let gfx = Path::new(env!("CARGO_MANIFEST_DIR")).join("../agb/examples/gfx");
+        let dir = temp_dir("failed");
+
+        let bad_sprite = dir.join("bad.aseprite");
+        fs::write(&bad_sprite, "not an aseprite file").unwrap();
+
+        let manifests = [
+            // panics while reading the file
+            format!(
+                r#"
+                [[sprites]]
+                module = "sprites"
+                files = ["{}"]
+                "#,
+                bad_sprite.display()
+            ),
+            // produces a compile error
+            format!(
+                r#"
+                [[backgrounds]]
+                module = "backgrounds"
+                images = [{{ name = "HUD", file = "{}/hud.aseprite", options = ["squash"] }}]
+                "#,
+                gfx.display()
+            ),
+        ];
+
+        for (i, content) in manifests.iter().enumerate() {
+            let manifest = dir.join(format!("assets{i}.toml"));
+            fs::write(&manifest, content).unwrap();
+
+            let out_dir = dir.join(format!("out{i}"));
+            let result = AssetPipeline::new(&manifest).with_out_dir(&out_dir).build();
+
+            let Err(PipelineError::ConvertAsset { paths, message }) = result else {
+                panic!("expected a conversion error for manifest {i}");
+            };
+            assert_eq!(paths.len(), 1);
+            assert!(!message.is_empty());
+
+            assert_eq!(
+                fs::read_dir(out_dir.join(CACHE_DIRECTORY)).unwrap().count(),
+                0
+            );
+            assert!(!out_dir.join(OUTPUT_FILE_NAME).exists());
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        let dir = temp_dir("invalid");
+        let manifest = dir.join("assets.toml");
+        fs::write(
+            &manifest,
+            r#"
+            [[sprites]]
+            module = "not valid"
+            files = []
+            "#,
+        )
+        .unwrap();
+
+        let result = AssetPipeline::new(&manifest)
+            .with_out_dir(dir.join("out"))
+            .build();
+        assert!(matches!(result, Err(PipelineError::InvalidEntry { .. })));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
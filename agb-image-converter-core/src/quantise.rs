@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use crate::colour::Colour;
+
+/// How to spread the error when a colour is replaced by its nearest match in
+/// the reduced palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Dither {
+    None,
+    Ordered,
+    FloydSteinberg,
+}
+
+/// A colour in RGB15 space, with each component between 0 and 31.
+pub(crate) type Rgb5 = [i32; 3];
+
+/// Errors are tracked in 1/16ths of an RGB15 step.
+const ERROR_SCALE: i32 = 16;
+
+const BAYER_4X4: [[i32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+pub(crate) fn to_rgb5(colour: Colour) -> Rgb5 {
+    [
+        i32::from(colour.r >> 3),
+        i32::from(colour.g >> 3),
+        i32::from(colour.b >> 3),
+    ]
+}
+
+pub(crate) fn from_rgb5(rgb: Rgb5) -> Colour {
+    let expand = |c: i32| {
+        let c = c.clamp(0, 31) as u8;
+        (c << 3) | (c >> 2)
+    };
+
+    Colour::from_rgb(expand(rgb[0]), expand(rgb[1]), expand(rgb[2]), 255)
+}
+
+/// Rounds a colour to the nearest one the GBA can display. Colours which only
+/// differ in the bits which get discarded then share a palette entry.
+pub(crate) fn snap(colour: Colour) -> Colour {
+    from_rgb5(to_rgb5(colour))
+}
+
+/// An approximation of perceptual distance (the 'redmean' weighting) which
+/// accounts for the eye being more sensitive to green than red or blue.
+pub(crate) fn distance(a: Rgb5, b: Rgb5) -> i32 {
+    let r_mean = (a[0] + b[0]) / 2;
+    let (dr, dg, db) = (a[0] - b[0], a[1] - b[1], a[2] - b[2]);
+
+    (64 + r_mean) * dr * dr + 128 * dg * dg + (95 - r_mean) * db * db
+}
+
+pub(crate) fn nearest(palette: &[Rgb5], colour: Rgb5) -> Rgb5 {
+    palette
+        .iter()
+        .copied()
+        .min_by_key(|&candidate| distance(candidate, colour))
+        .expect("palette should not be empty")
+}
+
+/// Counts how often each opaque colour appears.
+pub(crate) fn histogram(
+    colours: impl IntoIterator<Item = Colour>,
+    is_transparent: impl Fn(Colour) -> bool,
+) -> HashMap<Rgb5, usize> {
+    let mut histogram = HashMap::new();
+    for colour in colours {
+        if !is_transparent(colour) {
+            *histogram.entry(to_rgb5(colour)).or_default() += 1;
+        }
+    }
+
+    histogram
+}
+
+/// Reduces the colours to at most `max_colours` using median cut, splitting
+/// the box with the widest spread of colours along its longest axis.
+pub(crate) fn median_cut(histogram: &HashMap<Rgb5, usize>, max_colours: usize) -> Vec<Rgb5> {
+    let mut colours: Vec<_> = histogram.iter().map(|(&c, &count)| (c, count)).collect();
+    colours.sort();
+
+    if colours.len() <= max_colours {
+        return colours.into_iter().map(|(colour, _)| colour).collect();
+    }
+
+    let spread = |bucket: &[(Rgb5, usize)]| {
+        (0..3)
+            .map(|channel| {
+                let min = bucket.iter().map(|(c, _)| c[channel]).min().unwrap();
+                let max = bucket.iter().map(|(c, _)| c[channel]).max().unwrap();
+                (max - min, channel)
+            })
+            .max()
+            .unwrap()
+    };
+
+    let mut buckets = vec![colours];
+    while buckets.len() < max_colours {
+        let Some((index, channel)) = buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, bucket)| bucket.len() > 1)
+            .map(|(index, bucket)| {
+                let (range, channel) = spread(bucket);
+                let weight: usize = bucket.iter().map(|(_, count)| count).sum();
+                (range as usize * weight, index, channel)
+            })
+            .max()
+            .map(|(_, index, channel)| (index, channel))
+        else {
+            break;
+        };
+
+        let mut bucket = buckets.swap_remove(index);
+        bucket.sort_by_key(|(c, _)| c[channel]);
+
+        let total: usize = bucket.iter().map(|(_, count)| count).sum();
+        let mut running = 0;
+        let split = bucket
+            .iter()
+            .position(|(_, count)| {
+                running += count;
+                running * 2 >= total
+            })
+            .map_or(1, |position| position + 1)
+            .clamp(1, bucket.len() - 1);
+
+        let upper = bucket.split_off(split);
+        buckets.push(bucket);
+        buckets.push(upper);
+    }
+
+    buckets
+        .iter()
+        .map(|bucket| {
+            let total: usize = bucket.iter().map(|(_, count)| count).sum();
+            let mut average = [0; 3];
+            for channel in 0..3 {
+                let sum: usize = bucket
+                    .iter()
+                    .map(|(c, count)| c[channel] as usize * count)
+                    .sum();
+                average[channel] = ((sum + total / 2) / total) as i32;
+            }
+            average
+        })
+        .collect()
+}
+
+/// Replaces every opaque colour with the nearest colour from the palette
+/// which applies at that pixel, optionally dithering to hide banding.
+pub(crate) fn remap<'a>(
+    width: usize,
+    height: usize,
+    colours: &[Colour],
+    is_transparent: impl Fn(Colour) -> bool,
+    palette_at: impl Fn(usize, usize) -> &'a [Rgb5],
+    dither: Dither,
+) -> Vec<Colour> {
+    let mut errors = vec![[0; 3]; width * height];
+    let mut output = colours.to_vec();
+
+    for y in 0..height {
+        for x in 0..width {
+            let colour = colours[x + y * width];
+            if is_transparent(colour) {
+                continue;
+            }
+
+            let mut target = to_rgb5(colour).map(|c| c * ERROR_SCALE);
+            match dither {
+                Dither::None => {}
+                Dither::Ordered => {
+                    let threshold = (BAYER_4X4[y % 4][x % 4] * 2 - 15) * 2;
+                    target = target.map(|c| c + threshold);
+                }
+                Dither::FloydSteinberg => {
+                    let error = errors[x + y * width];
+                    for channel in 0..3 {
+                        target[channel] += error[channel];
+                    }
+                }
+            }
+
+            let target = target.map(|c| c.clamp(0, 31 * ERROR_SCALE));
+            let chosen = nearest(
+                palette_at(x, y),
+                target.map(|c| (c + ERROR_SCALE / 2) / ERROR_SCALE),
+            );
+            output[x + y * width] = from_rgb5(chosen);
+
+            if dither == Dither::FloydSteinberg {
+                let error: Rgb5 = [0, 1, 2].map(|i| target[i] - chosen[i] * ERROR_SCALE);
+
+                let mut spread = |dx: isize, dy: usize, weight: i32| {
+                    let nx = x as isize + dx;
+                    let ny = y + dy;
+                    if nx >= 0 && (nx as usize) < width && ny < height {
+                        let neighbour = &mut errors[nx as usize + ny * width];
+                        for channel in 0..3 {
+                            neighbour[channel] += error[channel] * weight / 16;
+                        }
+                    }
+                };
+
+                spread(1, 0, 7);
+                spread(-1, 1, 3);
+                spread(0, 1, 5);
+                spread(1, 1, 1);
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_cut_keeps_colours_when_under_budget() {
+        let histogram = HashMap::from([([0, 0, 0], 3), ([31, 31, 31], 1)]);
+        let mut palette = median_cut(&histogram, 15);
+        palette.sort();
+
+        assert_eq!(palette, vec![[0, 0, 0], [31, 31, 31]]);
+    }
+
+    #[test]
+    fn median_cut_reduces_gradient_to_budget() {
+        let histogram = (0..32).map(|i| ([i, i, i], 1)).collect();
+        let palette = median_cut(&histogram, 4);
+
+        assert_eq!(palette.len(), 4);
+        for i in 0..32 {
+            let closest = nearest(&palette, [i, i, i]);
+            assert!((closest[0] - i).abs() <= 4, "{i} maps to {closest:?}");
+        }
+    }
+
+    #[test]
+    fn dithering_a_flat_colour_mixes_neighbouring_palette_entries() {
+        let grey = from_rgb5([15, 15, 15]);
+        let colours = vec![grey; 64];
+        let palette = [[0, 0, 0], [31, 31, 31]];
+
+        for dither in [Dither::Ordered, Dither::FloydSteinberg] {
+            let output = remap(
+                8,
+                8,
+                &colours,
+                |c| c.is_transparent(),
+                |_, _| &palette,
+                dither,
+            );
+            let whites = output
+                .iter()
+                .filter(|&&c| to_rgb5(c) == [31, 31, 31])
+                .count();
+
+            assert!(
+                (24..=40).contains(&whites),
+                "{dither:?} gave {whites} whites"
+            );
+        }
+    }
+}
@@ -1,21 +1,57 @@
 use crate::deduplicator::{DeduplicatedData, Transformation};
+use crate::palette_swap::ColourMap;
 use crate::palette16::Palette16OptimisationResults;
 use crate::{ByteString, image_loader::Image};
 use crate::{add_image_256_to_tile_data, add_image_to_tile_data, collapse_to_4bpp};
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 
 use std::collections::BTreeMap;
 use std::iter;
+use std::path::PathBuf;
 
 pub(crate) fn generate_palette_code(results: &Palette16OptimisationResults) -> TokenStream {
+    let palettes = palettes_code(results, |rgb15| rgb15);
+
+    quote! {
+        pub static PALETTES: &[agb::display::Palette16] = #palettes;
+    }
+}
+
+/// Generates a copy of `PALETTES` with the colours replaced, so each palette
+/// lines up index for index with the original.
+pub(crate) fn generate_palette_swap_code(
+    name: &Ident,
+    results: &Palette16OptimisationResults,
+    colour_map: &ColourMap,
+    input_files: &[PathBuf],
+) -> TokenStream {
+    let palettes = palettes_code(results, |rgb15| colour_map.apply(rgb15));
+    let input_files = input_files.iter().map(|file| {
+        let file = file.to_string_lossy();
+        quote!(
+            const _: &[u8] = include_bytes!(#file);
+        )
+    });
+
+    quote! {
+        #(#input_files)*
+
+        pub static #name: &[agb::display::Palette16] = #palettes;
+    }
+}
+
+fn palettes_code(
+    results: &Palette16OptimisationResults,
+    map_colour: impl Fn(u16) -> u16,
+) -> TokenStream {
     let palettes = results.optimised_palettes.iter().map(|palette| {
         let colours = palette
             .clone()
             .into_iter()
             .map(|colour| {
-                let rgb15 = colour.to_rgb15();
+                let rgb15 = map_colour(colour.to_rgb15());
                 quote!(agb::display::Rgb15(#rgb15))
             })
             .chain(iter::repeat(quote!(agb::display::Rgb15(0))))
@@ -28,9 +64,7 @@ pub(crate) fn generate_palette_code(results: &Palette16OptimisationResults) -> T
         }
     });
 
-    quote! {
-        pub static PALETTES: &[agb::display::Palette16] = &[#(#palettes),*];
-    }
+    quote!(&[#(#palettes),*])
 }
 
 pub(crate) fn generate_code(
@@ -1,6 +1,8 @@
 mod common;
+mod meta;
 mod multi;
 mod regular;
+mod trim;
 
 pub use multi::include_multi;
 pub use regular::include_regular;
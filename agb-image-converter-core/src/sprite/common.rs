@@ -0,0 +1,665 @@
+use std::{collections::HashMap, error::Error, ops::Range};
+
+use asefile::AnimationDirection;
+use image::{DynamicImage, GenericImageView};
+use quote::{format_ident, quote};
+use snafu::{Snafu, ensure};
+use syn::{Ident, LitInt, LitStr, Token, parse::Parse};
+
+use crate::{
+    OUT_DIR_TOKEN, aseprite,
+    colour::Colour,
+    get_out_dir,
+    palette_swap::{ColourMap, PaletteSwap},
+    palette16::Palette16,
+    quantise::{self, Dither},
+};
+
+use super::{
+    meta::split_meta_frame,
+    trim::{Trim, trim_frame},
+};
+
+pub const TRANSPARENT_COLOUR: Colour = Colour::from_rgb(255, 0, 255, 0);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FileMode {
+    Frames,
+    Meta,
+    Trim,
+}
+
+pub struct FileEntry {
+    pub path: String,
+    pub size_override: Option<(u32, u32)>,
+    pub mode: FileMode,
+    pub quantise: Option<Dither>,
+}
+
+impl Parse for Input {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut files = Vec::new();
+        let mut palette_swaps = Vec::new();
+
+        while !input.is_empty() {
+            if input
+                .fork()
+                .parse::<Ident>()
+                .is_ok_and(|ident| ident == "palette_swap")
+            {
+                input.parse::<Ident>()?;
+                palette_swaps.push(input.parse()?);
+
+                if !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+
+                continue;
+            }
+
+            let mut mode = FileMode::Frames;
+            let mut quantise = None;
+
+            while input.peek(Ident) {
+                let ident: Ident = input.parse()?;
+                match ident.to_string().as_str() {
+                    "meta" if mode == FileMode::Frames => mode = FileMode::Meta,
+                    "trim" if mode == FileMode::Frames => mode = FileMode::Trim,
+                    "quantise" => quantise = quantise.or(Some(Dither::None)),
+                    "dither_ordered" => quantise = Some(Dither::Ordered),
+                    "dither_floyd_steinberg" => quantise = Some(Dither::FloydSteinberg),
+                    _ => {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            format!(
+                                "expected `meta`, `trim`, `quantise`, `dither_ordered`, `dither_floyd_steinberg` or a file path, got {ident}"
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            let size_override = if mode == FileMode::Frames && input.peek(LitInt) {
+                let lit: LitInt = input.parse()?;
+                let digits = lit.base10_digits();
+                let suffix = lit.suffix();
+
+                if !suffix.starts_with('x') {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        format!(
+                            "expected size in WIDTHxHEIGHT format, e.g. 32x16, got {digits}{suffix}"
+                        ),
+                    ));
+                }
+
+                let width: u32 = digits
+                    .parse()
+                    .map_err(|_| syn::Error::new(lit.span(), "invalid width in size override"))?;
+                let height: u32 = suffix[1..]
+                    .parse()
+                    .map_err(|_| syn::Error::new(lit.span(), "invalid height in size override"))?;
+
+                Some((width, height))
+            } else {
+                None
+            };
+
+            let path_lit: LitStr = input.parse()?;
+            let path = path_lit
+                .value()
+                .replace(OUT_DIR_TOKEN, &get_out_dir(&path_lit.value()));
+
+            files.push(FileEntry {
+                path,
+                size_override,
+                mode,
+                quantise,
+            });
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(Input {
+            files,
+            palette_swaps,
+        })
+    }
+}
+
+fn valid_sprite_size(width: u32, height: u32) -> bool {
+    match (width, height) {
+        (8, 8) => true,
+        (16, 16) => true,
+        (32, 32) => true,
+        (64, 64) => true,
+        (16, 8) => true,
+        (32, 8) => true,
+        (32, 16) => true,
+        (64, 32) => true,
+        (8, 16) => true,
+        (8, 32) => true,
+        (16, 32) => true,
+        (32, 64) => true,
+        (_, _) => false,
+    }
+}
+
+pub struct Input {
+    pub files: Vec<FileEntry>,
+    pub palette_swaps: Vec<PaletteSwap>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Tag {
+    pub name: String,
+    pub from: u32,
+    pub to: u32,
+    pub animation_type: AnimationDirection,
+}
+
+/// A piece of a meta sprite, referring to a sprite by index along with its
+/// offset from the top left of the frame.
+#[derive(Clone, Debug)]
+pub struct MetaPiece {
+    pub sprite: usize,
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct MetaFrame {
+    pub size: (u32, u32),
+    pub pieces: Vec<MetaPiece>,
+}
+
+pub struct Expanded {
+    pub input_files: Vec<String>,
+    pub sprites: Vec<DynamicImage>,
+    pub trims: HashMap<usize, Trim>,
+    pub quantise_groups: Vec<(Range<usize>, Dither)>,
+    pub palette_swaps: Vec<(Ident, ColourMap)>,
+    pub tags: Vec<Tag>,
+    pub meta_frames: Vec<MetaFrame>,
+    pub meta_tags: Vec<Tag>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Sprite {
+    pub size: (u32, u32),
+    pub data: Vec<Colour>,
+    pub trim: Option<Trim>,
+}
+
+impl Sprite {
+    pub fn palette(&self) -> Option<Palette16> {
+        let mut palette = Palette16::new();
+        for &colour in self.data.iter() {
+            if !palette.try_add_colour(if !colour.is_transparent() {
+                colour
+            } else {
+                TRANSPARENT_COLOUR
+            }) {
+                return None;
+            }
+        }
+        Some(palette)
+    }
+}
+
+pub struct PreOptimisation {
+    pub input_files: Vec<String>,
+    pub sprites: Vec<Sprite>,
+    pub quantise_groups: Vec<(Range<usize>, Dither)>,
+    pub palette_swaps: Vec<(Ident, ColourMap)>,
+    pub tags: Vec<Tag>,
+    pub meta_frames: Vec<MetaFrame>,
+    pub meta_tags: Vec<Tag>,
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(display("The sprite size ({x}, {y}) is invalid"))]
+struct InvalidSpriteSize {
+    x: u32,
+    y: u32,
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(display(
+    "Frame size ({frame_w}x{frame_h}) is not evenly divisible by target size ({target_w}x{target_h})"
+))]
+struct FrameNotDivisible {
+    frame_w: u32,
+    frame_h: u32,
+    target_w: u32,
+    target_h: u32,
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(display(
+    "The meta sprite tag '{tag}' and a sprite tag both generate a static called {name}. Rename one of the tags so they can live in the same module"
+))]
+struct MetaTagNameCollision {
+    tag: String,
+    name: String,
+}
+
+/// Meta tags and sprite tags become statics in the same module, so they can't
+/// share a name.
+fn check_meta_tag_names(tags: &[Tag], meta_tags: &[Tag]) -> Result<(), MetaTagNameCollision> {
+    for meta_tag in meta_tags {
+        let name = tag_static_name(&meta_tag.name);
+        ensure!(
+            !tags.iter().any(|tag| tag_static_name(&tag.name) == name),
+            MetaTagNameCollisionSnafu {
+                tag: meta_tag.name.clone(),
+                name,
+            }
+        );
+    }
+
+    Ok(())
+}
+
+fn split_frame(frame: &DynamicImage, target_w: u32, target_h: u32) -> Vec<DynamicImage> {
+    let (frame_w, frame_h) = frame.dimensions();
+    let cols = frame_w / target_w;
+    let rows = frame_h / target_h;
+
+    let mut sub_frames = Vec::with_capacity((cols * rows) as usize);
+
+    for row in 0..rows {
+        for col in 0..cols {
+            sub_frames.push(frame.crop_imm(col * target_w, row * target_h, target_w, target_h));
+        }
+    }
+
+    sub_frames
+}
+
+impl Input {
+    pub fn to_expanded(&self) -> Result<Expanded, Box<dyn Error>> {
+        let mut tag_index = 0;
+        let mut sprites = Vec::new();
+        let mut trims = HashMap::new();
+        let mut quantise_groups = Vec::new();
+        let mut tags = Vec::new();
+        let mut meta_frames = Vec::new();
+        let mut meta_tags = Vec::new();
+
+        // Resolve paths for both local crate and workspace contexts
+        let resolved_files: Vec<_> = self
+            .files
+            .iter()
+            .map(|entry| (crate::resolve_path(&entry.path), entry.size_override))
+            .collect();
+
+        for ((resolved_path, size_override), entry) in resolved_files.iter().zip(&self.files) {
+            let (images, file_tags) = aseprite::generate_from_file(resolved_path);
+            let first_sprite = sprites.len();
+
+            if entry.mode == FileMode::Meta {
+                let meta_index = meta_frames.len() as u32;
+
+                for tag in &file_tags {
+                    meta_tags.push(Tag {
+                        name: tag.name().to_string(),
+                        from: tag.from_frame() + meta_index,
+                        to: tag.to_frame() + meta_index,
+                        animation_type: tag.animation_direction(),
+                    });
+                }
+
+                for image in &images {
+                    let mut pieces = Vec::new();
+                    for (piece, sub_frame) in split_meta_frame(image) {
+                        pieces.push(MetaPiece {
+                            sprite: sprites.len(),
+                            x: piece.x,
+                            y: piece.y,
+                        });
+                        sprites.push(sub_frame);
+                    }
+
+                    meta_frames.push(MetaFrame {
+                        size: image.dimensions(),
+                        pieces,
+                    });
+                }
+
+                tag_index = u32::try_from(sprites.len())?;
+
+                if let Some(dither) = entry.quantise {
+                    quantise_groups.push((first_sprite..sprites.len(), dither));
+                }
+
+                continue;
+            }
+
+            let split_factor = if let &Some((target_w, target_h)) = size_override
+                && let Some(first) = images.first()
+            {
+                let (frame_w, frame_h) = first.dimensions();
+
+                ensure!(
+                    valid_sprite_size(target_w, target_h),
+                    InvalidSpriteSizeSnafu {
+                        x: target_w,
+                        y: target_h
+                    }
+                );
+                ensure!(
+                    frame_w % target_w == 0 && frame_h % target_h == 0,
+                    FrameNotDivisibleSnafu {
+                        frame_w,
+                        frame_h,
+                        target_w,
+                        target_h
+                    }
+                );
+                (frame_w / target_w) * (frame_h / target_h)
+            } else {
+                1
+            };
+
+            for tag in &file_tags {
+                tags.push(Tag {
+                    name: tag.name().to_string(),
+                    from: tag.from_frame() * split_factor + tag_index,
+                    to: (tag.to_frame() + 1) * split_factor - 1 + tag_index,
+                    animation_type: tag.animation_direction(),
+                });
+            }
+
+            let num_original_frames = u32::try_from(images.len())?;
+            tag_index += num_original_frames * split_factor;
+
+            for image in &images {
+                if split_factor > 1 {
+                    let (target_w, target_h) = size_override.unwrap();
+                    for sub_frame in split_frame(image, target_w, target_h) {
+                        sprites.push(sub_frame);
+                    }
+                } else if entry.mode == FileMode::Trim {
+                    let (trim, trimmed) = trim_frame(image)?;
+                    trims.insert(sprites.len(), trim);
+                    sprites.push(trimmed);
+                } else {
+                    let size = image.dimensions();
+                    ensure!(
+                        valid_sprite_size(size.0, size.1),
+                        InvalidSpriteSizeSnafu {
+                            x: size.0,
+                            y: size.1
+                        }
+                    );
+                    sprites.push(image.clone());
+                }
+            }
+
+            if let Some(dither) = entry.quantise {
+                quantise_groups.push((first_sprite..sprites.len(), dither));
+            }
+        }
+
+        check_meta_tag_names(&tags, &meta_tags)?;
+
+        let resolve_path =
+            |path: &str| crate::resolve_path(&path.replace(OUT_DIR_TOKEN, &get_out_dir(path)));
+
+        let mut input_files: Vec<_> = resolved_files
+            .iter()
+            .map(|(path, _)| path.to_string_lossy().into_owned())
+            .collect();
+        let mut palette_swaps = Vec::new();
+
+        for swap in &self.palette_swaps {
+            input_files.extend(
+                swap.input_files(resolve_path)
+                    .iter()
+                    .map(|path| path.to_string_lossy().into_owned()),
+            );
+            palette_swaps.push((swap.name.clone(), swap.colour_map(resolve_path)?));
+        }
+
+        Ok(Expanded {
+            input_files,
+            sprites,
+            trims,
+            quantise_groups,
+            palette_swaps,
+            tags,
+            meta_frames,
+            meta_tags,
+        })
+    }
+}
+
+impl Expanded {
+    pub fn to_pre_optimisation(&self) -> Result<PreOptimisation, Box<dyn Error>> {
+        Ok(PreOptimisation {
+            input_files: self.input_files.clone(),
+            quantise_groups: self.quantise_groups.clone(),
+            palette_swaps: self.palette_swaps.clone(),
+            tags: self.tags.clone(),
+            meta_frames: self.meta_frames.clone(),
+            meta_tags: self.meta_tags.clone(),
+            sprites: self
+                .sprites
+                .iter()
+                .enumerate()
+                .map(|(idx, sprite)| {
+                    let size = sprite.dimensions();
+
+                    Sprite {
+                        size,
+                        data: sprite
+                            .pixels()
+                            .map(|(_, _, colour)| {
+                                Colour::from_rgb(colour.0[0], colour.0[1], colour.0[2], colour.0[3])
+                            })
+                            .collect(),
+                        trim: self.trims.get(&idx).copied(),
+                    }
+                })
+                .collect(),
+        })
+    }
+}
+
+impl PreOptimisation {
+    /// Reduces the colours of each file which asked for quantisation so that
+    /// all of its frames share at most `max_colours` colours. Sharing the
+    /// colours between frames keeps animations from flickering between
+    /// slightly different palettes.
+    pub fn quantised(mut self, max_colours: usize) -> Self {
+        for (range, dither) in &self.quantise_groups {
+            let sprites = &mut self.sprites[range.clone()];
+
+            let histogram = quantise::histogram(
+                sprites
+                    .iter()
+                    .flat_map(|sprite| sprite.data.iter().copied()),
+                Colour::is_transparent,
+            );
+            let palette = quantise::median_cut(&histogram, max_colours);
+
+            for sprite in sprites {
+                sprite.data = quantise::remap(
+                    sprite.size.0 as usize,
+                    sprite.size.1 as usize,
+                    &sprite.data,
+                    Colour::is_transparent,
+                    |_, _| &palette,
+                    *dither,
+                );
+            }
+        }
+
+        self
+    }
+}
+
+/// The name of the static generated for a tag.
+pub fn tag_static_name(name: &str) -> String {
+    name.to_ascii_uppercase()
+        .replace(" ", "_")
+        .replace("-", "_")
+}
+
+/// Generates the call which records where a trimmed sprite sits in its frame.
+pub fn trim_tokens(trim: Option<Trim>) -> proc_macro2::TokenStream {
+    match trim {
+        Some(trim) => {
+            let x = trim.x as u16;
+            let y = trim.y as u16;
+            let frame_width = trim.frame_width as u16;
+            let frame_height = trim.frame_height as u16;
+
+            quote! { .with_trim(#x, #y, #frame_width, #frame_height) }
+        }
+        None => quote! {},
+    }
+}
+
+/// Generates the meta sprites and their tags. The meta sprites refer to the
+/// `SPRITES` static by index, so this should be placed alongside it.
+pub fn meta_sprite_tokens(
+    meta_frames: &[MetaFrame],
+    meta_tags: &[Tag],
+) -> proc_macro2::TokenStream {
+    if meta_frames.is_empty() {
+        return quote! {};
+    }
+
+    let pieces = meta_frames
+        .iter()
+        .flat_map(|frame| &frame.pieces)
+        .map(|piece| {
+            let sprite = piece.sprite;
+            let x = piece.x as u16;
+            let y = piece.y as u16;
+
+            quote! { MetaSpritePiece::new(&SPRITES[#sprite], #x, #y) }
+        });
+
+    let mut piece_index = 0;
+    let frames = meta_frames.iter().map(|frame| {
+        let from = piece_index;
+        let len = frame.pieces.len();
+        piece_index += len;
+
+        let width = frame.size.0 as u16;
+        let height = frame.size.1 as u16;
+
+        quote! {
+            MetaSprite::new(unsafe { core::slice::from_raw_parts(META_SPRITE_PIECES.as_ptr().add(#from), #len) }, #width, #height)
+        }
+    });
+
+    let tags = meta_tags.iter().map(|tag| {
+        let ident = format_ident!("{}", tag_static_name(&tag.name));
+        let from = tag.from as usize;
+        let to = tag.to as usize;
+        let len = to - from + 1;
+        let direction = tag.animation_type as usize;
+
+        quote! {
+            pub static #ident: MetaTag = MetaTag::new(unsafe { core::slice::from_raw_parts(META_SPRITES.as_ptr().add(#from), #len) }, #direction);
+        }
+    });
+
+    quote! {
+        static META_SPRITE_PIECES: &[MetaSpritePiece] = &[#(#pieces),*];
+        static META_SPRITES: &[MetaSprite] = &[#(#frames),*];
+
+        #(#tags)*
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    fn tag(name: &str, from: u32, to: u32) -> Tag {
+        Tag {
+            name: name.to_string(),
+            from,
+            to,
+            animation_type: AnimationDirection::Forward,
+        }
+    }
+
+    #[test]
+    fn meta_sprite_pieces_have_their_offsets() {
+        // A 16x16 block on the left, with single tiles at the top right and
+        // towards the right of the bottom row.
+        let mut frame = RgbaImage::new(48, 16);
+        for (x, y, w, h) in [(0, 0, 16, 16), (40, 0, 8, 8), (32, 8, 8, 8)] {
+            for j in y..y + h {
+                for i in x..x + w {
+                    frame.put_pixel(i, j, Rgba([255, 0, 0, 255]));
+                }
+            }
+        }
+
+        let pieces: Vec<_> = split_meta_frame(&DynamicImage::ImageRgba8(frame))
+            .into_iter()
+            .enumerate()
+            .map(|(sprite, (piece, _))| MetaPiece {
+                sprite,
+                x: piece.x,
+                y: piece.y,
+            })
+            .collect();
+
+        assert_eq!(
+            pieces
+                .iter()
+                .map(|piece| (piece.sprite, piece.x, piece.y))
+                .collect::<Vec<_>>(),
+            [(0, 0, 0), (1, 40, 0), (2, 32, 8)]
+        );
+
+        let tokens = meta_sprite_tokens(
+            &[MetaFrame {
+                size: (48, 16),
+                pieces,
+            }],
+            &[tag("walk", 0, 0)],
+        )
+        .to_string();
+
+        let expected_pieces = quote! {
+            static META_SPRITE_PIECES: &[MetaSpritePiece] = &[
+                MetaSpritePiece::new(&SPRITES[0usize], 0u16, 0u16),
+                MetaSpritePiece::new(&SPRITES[1usize], 40u16, 0u16),
+                MetaSpritePiece::new(&SPRITES[2usize], 32u16, 8u16)
+            ];
+        };
+        let expected_frame = quote! {
+            MetaSprite::new(unsafe { core::slice::from_raw_parts(META_SPRITE_PIECES.as_ptr().add(0usize), 3usize) }, 48u16, 16u16)
+        };
+
+        assert!(tokens.contains(&expected_pieces.to_string()), "{tokens}");
+        assert!(tokens.contains(&expected_frame.to_string()), "{tokens}");
+        assert!(tokens.contains("pub static WALK : MetaTag"), "{tokens}");
+    }
+
+    #[test]
+    fn meta_tag_sharing_a_name_with_a_sprite_tag_is_an_error() {
+        let tags = [tag("Idle", 0, 1), tag("walk", 2, 3)];
+
+        assert!(check_meta_tag_names(&tags, &[tag("jump", 0, 0)]).is_ok());
+
+        let err = check_meta_tag_names(&tags, &[tag("jump", 0, 0), tag("idle", 1, 1)])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'idle'"), "{err}");
+        assert!(err.contains("IDLE"), "{err}");
+    }
+}
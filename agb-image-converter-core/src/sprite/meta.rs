@@ -0,0 +1,184 @@
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+/// Valid sprite sizes measured in tiles.
+pub(super) const SIZES_IN_TILES: [(usize, usize); 12] = [
+    (8, 8),
+    (8, 4),
+    (4, 8),
+    (4, 4),
+    (4, 2),
+    (2, 4),
+    (2, 2),
+    (4, 1),
+    (1, 4),
+    (2, 1),
+    (1, 2),
+    (1, 1),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Piece {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Covers every occupied tile in the grid with non overlapping hardware sized
+/// pieces. Pieces are placed greedily from the top left, picking the size
+/// which covers the most occupied tiles while being at least half full.
+fn partition(occupied: &[bool], tiles_w: usize, tiles_h: usize) -> Vec<Piece> {
+    let mut covered = vec![false; occupied.len()];
+    let mut pieces = Vec::new();
+
+    for y in 0..tiles_h {
+        for x in 0..tiles_w {
+            if !occupied[x + y * tiles_w] || covered[x + y * tiles_w] {
+                continue;
+            }
+
+            let (width, height) = SIZES_IN_TILES
+                .iter()
+                .copied()
+                .filter(|&(w, h)| x + w <= tiles_w && y + h <= tiles_h)
+                .filter(|&(w, h)| (y..y + h).all(|j| (x..x + w).all(|i| !covered[i + j * tiles_w])))
+                .map(|(w, h)| {
+                    let used = (y..y + h)
+                        .flat_map(|j| (x..x + w).map(move |i| (i, j)))
+                        .filter(|&(i, j)| occupied[i + j * tiles_w])
+                        .count();
+                    ((w, h), used)
+                })
+                .filter(|&((w, h), used)| used * 2 >= w * h)
+                .max_by_key(|&((w, h), used)| (used, core::cmp::Reverse(w * h)))
+                .map(|(size, _)| size)
+                .expect("a single tile is always a valid piece");
+
+            for j in y..y + height {
+                for i in x..x + width {
+                    covered[i + j * tiles_w] = true;
+                }
+            }
+
+            pieces.push(Piece {
+                x: x as u32 * 8,
+                y: y as u32 * 8,
+                width: width as u32 * 8,
+                height: height as u32 * 8,
+            });
+        }
+    }
+
+    pieces
+}
+
+/// Splits a frame of any size into hardware sized pieces, skipping any
+/// tiles which are fully transparent.
+pub fn split_meta_frame(frame: &DynamicImage) -> Vec<(Piece, DynamicImage)> {
+    let (frame_w, frame_h) = frame.dimensions();
+    let tiles_w = frame_w.div_ceil(8) as usize;
+    let tiles_h = frame_h.div_ceil(8) as usize;
+
+    let mut padded = RgbaImage::new(tiles_w as u32 * 8, tiles_h as u32 * 8);
+    image::imageops::replace(&mut padded, &frame.to_rgba8(), 0, 0);
+
+    let occupied: Vec<_> = (0..tiles_h)
+        .flat_map(|y| (0..tiles_w).map(move |x| (x as u32, y as u32)))
+        .map(|(x, y)| {
+            (0..8).any(|j| (0..8).any(|i| padded.get_pixel(x * 8 + i, y * 8 + j).0[3] == 255))
+        })
+        .collect();
+
+    let padded = DynamicImage::ImageRgba8(padded);
+
+    partition(&occupied, tiles_w, tiles_h)
+        .into_iter()
+        .map(|piece| {
+            let image = padded.crop_imm(piece.x, piece.y, piece.width, piece.height);
+            (piece, image)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> (Vec<bool>, usize, usize) {
+        let occupied = rows
+            .iter()
+            .flat_map(|row| row.chars().map(|c| c == '#'))
+            .collect();
+        (occupied, rows[0].len(), rows.len())
+    }
+
+    fn assert_valid_cover(occupied: &[bool], tiles_w: usize, pieces: &[Piece]) {
+        let mut coverage = vec![0; occupied.len()];
+        for piece in pieces {
+            assert!(
+                SIZES_IN_TILES.contains(&(piece.width as usize / 8, piece.height as usize / 8)),
+                "{piece:?} is not a hardware size"
+            );
+
+            for j in piece.y / 8..(piece.y + piece.height) / 8 {
+                for i in piece.x / 8..(piece.x + piece.width) / 8 {
+                    coverage[i as usize + j as usize * tiles_w] += 1;
+                }
+            }
+        }
+
+        for (idx, (&count, &occupied)) in coverage.iter().zip(occupied).enumerate() {
+            assert!(count <= 1, "tile {idx} is covered more than once");
+            assert!(!occupied || count == 1, "tile {idx} is not covered");
+        }
+    }
+
+    #[test]
+    fn full_large_frame_uses_largest_pieces() {
+        let (occupied, w, h) = grid(&["#".repeat(16).as_str(); 8]);
+        let pieces = partition(&occupied, w, h);
+
+        assert_eq!(pieces.len(), 2);
+        assert_valid_cover(&occupied, w, &pieces);
+    }
+
+    #[test]
+    fn empty_frame_has_no_pieces() {
+        let (occupied, w, h) = grid(&["....", "...."]);
+        assert!(partition(&occupied, w, h).is_empty());
+    }
+
+    #[test]
+    fn skips_transparent_areas() {
+        let (occupied, w, h) = grid(&[
+            "##......##", //
+            "##......##",
+            "..........",
+            "....##....",
+        ]);
+        let pieces = partition(&occupied, w, h);
+
+        assert_eq!(pieces.len(), 3);
+        assert_valid_cover(&occupied, w, &pieces);
+        assert!(
+            pieces
+                .iter()
+                .all(|piece| piece.width * piece.height <= 16 * 16)
+        );
+    }
+
+    #[test]
+    fn irregular_shape_is_covered_exactly_once() {
+        let (occupied, w, h) = grid(&[
+            "..####.....",
+            ".######....",
+            "#########..",
+            "###########",
+            ".#########.",
+            "...#...#...",
+        ]);
+        let pieces = partition(&occupied, w, h);
+
+        assert_valid_cover(&occupied, w, &pieces);
+    }
+}
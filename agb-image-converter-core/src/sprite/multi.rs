@@ -3,17 +3,26 @@ use std::{
     error::Error,
 };
 
-use crate::{ByteString, colour::Colour};
-use proc_macro::TokenStream;
+use crate::{ByteString, colour::Colour, palette_swap::ColourMap};
+use proc_macro2::TokenStream;
 use quote::{ToTokens, format_ident};
 use snafu::prelude::*;
-use syn::parse_macro_input;
-
-use super::common::{Input, PreOptimisation, Sprite, Tag};
+use syn::Ident;
+
+use super::{
+    common::{
+        Input, MetaFrame, PreOptimisation, Sprite, Tag, meta_sprite_tokens, tag_static_name,
+        trim_tokens,
+    },
+    trim::Trim,
+};
 use quote::quote;
 
 pub fn include_multi(tokens: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(tokens as Input);
+    let input = match syn::parse2::<Input>(tokens) {
+        Ok(input) => input,
+        Err(err) => return err.to_compile_error(),
+    };
 
     match process_input(&input) {
         Ok(tokens) => tokens,
@@ -25,14 +34,16 @@ fn process_input(input: &Input) -> Result<TokenStream, Box<dyn Error>> {
     let output = input
         .to_expanded()?
         .to_pre_optimisation()?
+        .quantised(255)
         .to_optimised_multi()?
         .to_output()?;
 
-    Ok(quote! {#output}.into())
+    Ok(quote! {#output})
 }
 
 struct SpriteIndexed {
     size: (u32, u32),
+    trim: Option<Trim>,
     data: Vec<u8>,
 }
 
@@ -41,6 +52,9 @@ struct Optimised {
     palettes: Vec<u16>,
     sprites: Vec<SpriteIndexed>,
     tags: Vec<Tag>,
+    meta_frames: Vec<MetaFrame>,
+    meta_tags: Vec<Tag>,
+    palette_swaps: Vec<(Ident, ColourMap)>,
 }
 
 fn generate_palette(sprites: &[Sprite]) -> Vec<u16> {
@@ -107,6 +121,7 @@ impl PreOptimisation {
                     })
                     .collect(),
                 size: x.size,
+                trim: x.trim,
             })
             .collect();
 
@@ -115,6 +130,9 @@ impl PreOptimisation {
             palettes: palette,
             sprites: sprites_indexed,
             tags: self.tags.clone(),
+            meta_frames: self.meta_frames.clone(),
+            meta_tags: self.meta_tags.clone(),
+            palette_swaps: self.palette_swaps.clone(),
         })
     }
 }
@@ -122,6 +140,7 @@ impl PreOptimisation {
 struct SpriteCompacted {
     data: Vec<u8>,
     size: (u32, u32),
+    trim: Option<Trim>,
 }
 
 struct Output {
@@ -129,6 +148,9 @@ struct Output {
     palette: Vec<u16>,
     sprites: Vec<SpriteCompacted>,
     tags: Vec<Tag>,
+    meta_frames: Vec<MetaFrame>,
+    meta_tags: Vec<Tag>,
+    palette_swaps: Vec<(Ident, ColourMap)>,
 }
 
 impl SpriteIndexed {
@@ -147,6 +169,7 @@ impl SpriteIndexed {
 
         SpriteCompacted {
             size: self.size,
+            trim: self.trim,
             data: compacted,
         }
     }
@@ -163,35 +186,50 @@ impl Optimised {
                 .map(SpriteIndexed::to_compacted)
                 .collect(),
             tags: self.tags.clone(),
+            meta_frames: self.meta_frames.clone(),
+            meta_tags: self.meta_tags.clone(),
+            palette_swaps: self.palette_swaps.clone(),
         })
     }
 }
 
 impl ToTokens for Output {
-    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
         let sprites = self.sprites.iter().map(|sprite| {
             let data = ByteString(&sprite.data);
             let x = sprite.size.0 as usize;
             let y = sprite.size.1 as usize;
+            let trim = trim_tokens(sprite.trim);
 
             quote! {
-                unsafe { Sprite::new_multi(&PALETTE, align_bytes!(u32, #data), Size::from_width_height(#x, #y)) }
+                unsafe { Sprite::new_multi(&PALETTE, align_bytes!(u32, #data), Size::from_width_height(#x, #y)) } #trim
             }
         });
 
-        let palettes = self.palette.chunks(16).map(|palette| {
-            let palette = palette.iter().map(|c| quote!(Rgb15(#c)));
-            quote! { Palette16::new([#(#palette),*])}
+        let palettes_with = |map_colour: &dyn Fn(u16) -> u16| {
+            let palettes = self.palette.chunks(16).map(|palette| {
+                let palette = palette
+                    .iter()
+                    .map(|&c| map_colour(c))
+                    .map(|c| quote!(Rgb15(#c)));
+                quote! { Palette16::new([#(#palette),*])}
+            });
+
+            quote!(&[#(#palettes),*])
+        };
+
+        let palettes = palettes_with(&|c| c);
+
+        let palette_swaps = self.palette_swaps.iter().map(|(name, colour_map)| {
+            let palettes = palettes_with(&|c| colour_map.apply(c));
+
+            quote! {
+                pub static #name: PaletteSwap = PaletteSwap::new_multi(&PALETTE, &PaletteMulti::new(#palettes));
+            }
         });
 
         let tags = self.tags.iter().map(|tag| {
-            let ident = format_ident!(
-                "{}",
-                tag.name
-                    .to_ascii_uppercase()
-                    .replace(" ", "_")
-                    .replace("-", "_")
-            );
+            let ident = format_ident!("{}", tag_static_name(&tag.name));
             let from = tag.from as usize;
             let to = tag.to as usize;
             let len = to - from + 1;
@@ -208,13 +246,19 @@ impl ToTokens for Output {
             }
         });
 
+        let meta_sprites = meta_sprite_tokens(&self.meta_frames, &self.meta_tags);
+
         tokens.extend(quote! {
             #(#input_files)*
 
-            static PALETTE: PaletteMulti = PaletteMulti::new(&[#(#palettes),*] );
+            static PALETTE: PaletteMulti = PaletteMulti::new(#palettes);
             static SPRITES: &[Sprite] = &[#(#sprites),*];
 
             #(#tags)*
+
+            #meta_sprites
+
+            #(#palette_swaps)*
         });
     }
 }
@@ -1,15 +1,24 @@
 use std::error::Error;
 
-use proc_macro::TokenStream;
+use proc_macro2::TokenStream;
 use quote::{ToTokens, format_ident, quote};
-use syn::parse_macro_input;
+use syn::Ident;
 
-use crate::{ByteString, Palette16Optimiser, colour::Colour, palette16::Palette16};
+use crate::{ByteString, Palette16Optimiser, palette_swap::ColourMap, palette16::Palette16};
 
-use super::common::{Input, PreOptimisation, TRANSPARENT_COLOUR, Tag};
+use super::{
+    common::{
+        Input, MetaFrame, PreOptimisation, TRANSPARENT_COLOUR, Tag, meta_sprite_tokens,
+        tag_static_name, trim_tokens,
+    },
+    trim::Trim,
+};
 
 pub fn include_regular(tokens: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(tokens as Input);
+    let input = match syn::parse2::<Input>(tokens) {
+        Ok(input) => input,
+        Err(err) => return err.to_compile_error(),
+    };
 
     match process_input(&input) {
         Ok(tokens) => tokens,
@@ -21,15 +30,17 @@ fn process_input(input: &Input) -> Result<TokenStream, Box<dyn Error>> {
     let output = input
         .to_expanded()?
         .to_pre_optimisation()?
+        .quantised(15)
         .to_optimised()?
         .to_output()?;
 
-    Ok(quote! {#output}.into())
+    Ok(quote! {#output})
 }
 
 #[derive(Clone, Debug)]
 struct SpriteIndexed {
     size: (u32, u32),
+    trim: Option<Trim>,
     data: Vec<u8>,
     palette: u32,
 }
@@ -50,6 +61,7 @@ impl SpriteIndexed {
 
         SpriteCompacted {
             size: self.size,
+            trim: self.trim,
             palette: self.palette,
             data: compacted,
         }
@@ -61,10 +73,14 @@ struct Optimised {
     palettes: Vec<Palette16>,
     sprites: Vec<SpriteIndexed>,
     tags: Vec<Tag>,
+    meta_frames: Vec<MetaFrame>,
+    meta_tags: Vec<Tag>,
+    palette_swaps: Vec<(Ident, ColourMap)>,
 }
 
 struct SpriteCompacted {
     size: (u32, u32),
+    trim: Option<Trim>,
     data: Vec<u8>,
     palette: u32,
 }
@@ -74,6 +90,9 @@ struct Output {
     palettes: Vec<Palette16>,
     sprites: Vec<SpriteCompacted>,
     tags: Vec<Tag>,
+    meta_frames: Vec<MetaFrame>,
+    meta_tags: Vec<Tag>,
+    palette_swaps: Vec<(Ident, ColourMap)>,
 }
 
 #[derive(snafu::Snafu, Debug)]
@@ -100,6 +119,7 @@ impl PreOptimisation {
 
                     SpriteIndexed {
                         size: sprite.size,
+                        trim: sprite.trim,
                         data: sprite
                             .data
                             .iter()
@@ -116,6 +136,9 @@ impl PreOptimisation {
                 })
                 .collect(),
             tags: self.tags.clone(),
+            meta_frames: self.meta_frames.clone(),
+            meta_tags: self.meta_tags.clone(),
+            palette_swaps: self.palette_swaps.clone(),
             palettes: optimised_palettes.optimised_palettes,
         })
     }
@@ -132,42 +155,58 @@ impl Optimised {
                 .map(SpriteIndexed::to_compacted)
                 .collect(),
             tags: self.tags.clone(),
+            meta_frames: self.meta_frames.clone(),
+            meta_tags: self.meta_tags.clone(),
+            palette_swaps: self.palette_swaps.clone(),
         })
     }
 }
 
 impl ToTokens for Output {
-    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
         let sprites = self.sprites.iter().map(|sprite| {
             let data = ByteString(&sprite.data);
             let x = sprite.size.0 as usize;
             let y = sprite.size.1 as usize;
+            let trim = trim_tokens(sprite.trim);
             let palette_idx = sprite.palette as usize;
 
             quote! {
-                unsafe { Sprite::new(&PALETTES[#palette_idx], align_bytes!(u32, #data), Size::from_width_height(#x, #y)) }
+                unsafe { Sprite::new(&PALETTES[#palette_idx], align_bytes!(u32, #data), Size::from_width_height(#x, #y)) } #trim
             }
         });
 
-        let palettes = self.palettes.iter().map(|palette| {
-            let mut colours: Vec<_> = palette.colours().copied().map(Colour::to_rgb15).collect();
-            colours.resize(16, 0);
+        let palettes_with = |map_colour: &dyn Fn(u16) -> u16| {
+            let palettes = self.palettes.iter().map(|palette| {
+                let mut colours: Vec<_> = palette
+                    .colours()
+                    .copied()
+                    .map(|c| map_colour(c.to_rgb15()))
+                    .collect();
+                colours.resize(16, 0);
 
-            let colours = colours.iter().map(|c| quote!(Rgb15(#c)));
+                let colours = colours.iter().map(|c| quote!(Rgb15(#c)));
+
+                quote! {
+                    Palette16::new([#(#colours),*])
+                }
+            });
+
+            quote!(&[#(#palettes),*])
+        };
+
+        let palettes = palettes_with(&|c| c);
+
+        let palette_swaps = self.palette_swaps.iter().map(|(name, colour_map)| {
+            let palettes = palettes_with(&|c| colour_map.apply(c));
 
             quote! {
-                Palette16::new([#(#colours),*])
+                pub static #name: PaletteSwap = PaletteSwap::new(PALETTES, #palettes);
             }
         });
 
         let tags = self.tags.iter().map(|tag| {
-            let ident = format_ident!(
-                "{}",
-                tag.name
-                    .to_ascii_uppercase()
-                    .replace(" ", "_")
-                    .replace("-", "_")
-            );
+            let ident = format_ident!("{}", tag_static_name(&tag.name));
             let from = tag.from as usize;
             let to = tag.to as usize;
             let len = to - from + 1;
@@ -184,13 +223,19 @@ impl ToTokens for Output {
             }
         });
 
+        let meta_sprites = meta_sprite_tokens(&self.meta_frames, &self.meta_tags);
+
         tokens.extend(quote! {
             #(#input_files)*
 
-            static PALETTES: &[Palette16] = &[#(#palettes),*];
+            static PALETTES: &[Palette16] = #palettes;
             static SPRITES: &[Sprite] = &[#(#sprites),*];
 
             #(#tags)*
+
+            #meta_sprites
+
+            #(#palette_swaps)*
         });
     }
 }
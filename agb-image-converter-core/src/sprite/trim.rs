@@ -0,0 +1,129 @@
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use snafu::Snafu;
+
+use super::meta::SIZES_IN_TILES;
+
+/// Where a trimmed sprite sits within the frame it was cut from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Trim {
+    pub x: u32,
+    pub y: u32,
+    pub frame_width: u32,
+    pub frame_height: u32,
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(display(
+    "The content of a frame ({width}x{height}) is too large to fit in a single sprite, consider using meta instead"
+))]
+pub struct ContentTooLarge {
+    width: u32,
+    height: u32,
+}
+
+/// The smallest sprite size which can hold content of the given size.
+fn smallest_size(width: u32, height: u32) -> Option<(u32, u32)> {
+    SIZES_IN_TILES
+        .iter()
+        .map(|&(w, h)| (w as u32 * 8, h as u32 * 8))
+        .filter(|&(w, h)| w >= width && h >= height)
+        .min_by_key(|&(w, h)| w * h)
+}
+
+/// Trims a frame down to the smallest sprite which contains all of its non
+/// transparent pixels. Fully transparent frames become an empty 8x8 sprite.
+pub fn trim_frame(frame: &DynamicImage) -> Result<(Trim, DynamicImage), ContentTooLarge> {
+    let (frame_width, frame_height) = frame.dimensions();
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, colour) in frame.pixels() {
+        if colour.0[3] == 255 {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x + 1);
+            max_y = max_y.max(y + 1);
+        }
+    }
+
+    if min_x > max_x {
+        (min_x, min_y, max_x, max_y) = (0, 0, 0, 0);
+    }
+
+    let (content_width, content_height) = (max_x - min_x, max_y - min_y);
+    let (width, height) = smallest_size(content_width, content_height).ok_or(ContentTooLarge {
+        width: content_width,
+        height: content_height,
+    })?;
+
+    // Keep the sprite inside the frame where possible so that the offset is
+    // never negative.
+    let x = min_x.min(frame_width.saturating_sub(width));
+    let y = min_y.min(frame_height.saturating_sub(height));
+
+    let mut trimmed = RgbaImage::new(width, height);
+    let visible = frame.crop_imm(x, y, width, height).to_rgba8();
+    image::imageops::replace(&mut trimmed, &visible, 0, 0);
+
+    Ok((
+        Trim {
+            x,
+            y,
+            frame_width,
+            frame_height,
+        },
+        DynamicImage::ImageRgba8(trimmed),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    fn frame_with_content(width: u32, height: u32, content: (u32, u32, u32, u32)) -> DynamicImage {
+        let (cx, cy, cw, ch) = content;
+        let mut image = RgbaImage::new(width, height);
+        for y in cy..cy + ch {
+            for x in cx..cx + cw {
+                image.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+
+        DynamicImage::ImageRgba8(image)
+    }
+
+    #[test]
+    fn picks_smallest_size_which_fits() {
+        assert_eq!(smallest_size(8, 8), Some((8, 8)));
+        assert_eq!(smallest_size(9, 3), Some((16, 8)));
+        assert_eq!(smallest_size(30, 50), Some((32, 64)));
+        assert_eq!(smallest_size(65, 1), None);
+    }
+
+    #[test]
+    fn trims_to_content() {
+        let (trim, image) = trim_frame(&frame_with_content(64, 64, (20, 30, 10, 5))).unwrap();
+
+        assert_eq!(image.dimensions(), (16, 8));
+        assert_eq!(
+            trim,
+            Trim {
+                x: 20,
+                y: 30,
+                frame_width: 64,
+                frame_height: 64
+            }
+        );
+    }
+
+    #[test]
+    fn keeps_trimmed_sprite_inside_frame() {
+        let (trim, image) = trim_frame(&frame_with_content(32, 32, (28, 28, 4, 4))).unwrap();
+
+        assert_eq!(image.dimensions(), (8, 8));
+        assert_eq!((trim.x, trim.y), (24, 24));
+        assert_eq!(image.get_pixel(4, 4).0[3], 255);
+        assert_eq!(image.get_pixel(3, 3).0[3], 0);
+    }
+}
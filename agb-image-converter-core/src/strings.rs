@@ -0,0 +1,693 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Part {
+    Text(String),
+    Variable(String),
+    Colour(u8),
+    Select {
+        variable: String,
+        variants: Vec<(Key, Vec<Part>)>,
+        default: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Key {
+    Category(PluralCategory),
+    Number(i32),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "zero" => Self::Zero,
+            "one" => Self::One,
+            "two" => Self::Two,
+            "few" => Self::Few,
+            "many" => Self::Many,
+            "other" => Self::Other,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Message {
+    pub(crate) id: String,
+    pub(crate) parts: Vec<Part>,
+}
+
+/// Parses the subset of [Fluent](https://projectfluent.org) which can be used for game text.
+///
+/// Supported are comments, messages and terms (including multiline ones), variables, string
+/// literals, term references, selectors with plural categories, numbers or strings as keys, and
+/// the `COLOUR(n)` function to change the text colour.
+pub(crate) fn parse_ftl(source: &str) -> Result<Vec<Message>, String> {
+    let mut entries: Vec<(String, String)> = vec![];
+
+    for (line_number, line) in source.lines().enumerate() {
+        let line_number = line_number + 1;
+
+        // the `}` closing a selector may be written at the start of the line
+        if line.starts_with(char::is_whitespace) || line.starts_with('}') {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let Some((_, value)) = entries.last_mut() else {
+                return Err(format!(
+                    "line {line_number}: indented text outside of a message"
+                ));
+            };
+
+            if !value.is_empty() {
+                value.push('\n');
+            }
+            value.push_str(trimmed);
+            continue;
+        }
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((id, value)) = line.split_once('=') else {
+            return Err(format!("line {line_number}: expected `identifier = value`"));
+        };
+
+        let id = id.trim();
+        let name = id.strip_prefix('-').unwrap_or(id);
+        if !is_identifier(name) {
+            return Err(format!("line {line_number}: invalid identifier `{id}`"));
+        }
+
+        entries.push((id.to_string(), value.trim().to_string()));
+    }
+
+    let mut terms: HashMap<String, Vec<Part>> = HashMap::new();
+    let mut messages: Vec<Message> = vec![];
+
+    for (id, value) in entries {
+        let parts = PatternParser::new(&value, &terms)
+            .parse()
+            .map_err(|err| format!("in `{id}`: {err}"))?;
+
+        if let Some(term) = id.strip_prefix('-') {
+            terms.insert(term.to_string(), parts);
+        } else if messages.iter().any(|message| message.id == id) {
+            return Err(format!("`{id}` is defined more than once"));
+        } else {
+            messages.push(Message { id, parts });
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Every character which can be displayed by the messages, for subsetting a font to a language.
+pub(crate) fn characters(messages: &[Message]) -> String {
+    fn collect(parts: &[Part], characters: &mut String) {
+        for part in parts {
+            match part {
+                Part::Text(text) => characters.push_str(text),
+                Part::Select { variants, .. } => {
+                    for (_, parts) in variants {
+                        collect(parts, characters);
+                    }
+                }
+                Part::Variable(_) | Part::Colour(_) => {}
+            }
+        }
+    }
+
+    let mut characters = String::new();
+    for message in messages {
+        collect(&message.parts, &mut characters);
+    }
+
+    characters
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+struct PatternParser<'a> {
+    chars: Vec<char>,
+    position: usize,
+    terms: &'a HashMap<String, Vec<Part>>,
+}
+
+impl<'a> PatternParser<'a> {
+    fn new(pattern: &str, terms: &'a HashMap<String, Vec<Part>>) -> Self {
+        Self {
+            chars: pattern.chars().collect(),
+            position: 0,
+            terms,
+        }
+    }
+
+    fn parse(mut self) -> Result<Vec<Part>, String> {
+        self.pattern(false)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{expected}` but found `{c}`")),
+            None => Err(format!("expected `{expected}` but the message ended")),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn skip_blank_inline(&mut self) {
+        while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+            self.position += 1;
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            let is_arrow = c == '-' && self.chars.get(self.position + 1) == Some(&'>');
+            if is_arrow || !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                break;
+            }
+            self.position += 1;
+        }
+
+        if start == self.position {
+            return Err("expected an identifier".to_string());
+        }
+
+        Ok(self.chars[start..self.position].iter().collect())
+    }
+
+    /// Parses text and placeables. A variant's pattern ends at the end of its line or at the `}`
+    /// which closes the selector.
+    fn pattern(&mut self, in_variant: bool) -> Result<Vec<Part>, String> {
+        let mut parts = vec![];
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '\n' | '}' if in_variant => break,
+                '{' => {
+                    self.position += 1;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.extend(self.placeable()?);
+                }
+                '}' => return Err("unexpected `}`".to_string()),
+                _ => {
+                    self.position += 1;
+                    text.push(c);
+                }
+            }
+        }
+
+        if in_variant {
+            text.truncate(text.trim_end().len());
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(merge_text(parts))
+    }
+
+    fn placeable(&mut self) -> Result<Vec<Part>, String> {
+        self.skip_whitespace();
+
+        let parts = match self.peek() {
+            Some('$') => {
+                self.position += 1;
+                let variable = self.identifier()?;
+                self.skip_whitespace();
+
+                if self.peek() == Some('-') {
+                    self.position += 1;
+                    self.expect('>')?;
+                    return self.select(variable);
+                }
+
+                vec![Part::Variable(variable)]
+            }
+            Some('"') => {
+                self.position += 1;
+                vec![Part::Text(self.string_literal()?)]
+            }
+            Some('-') => {
+                self.position += 1;
+                let term = self.identifier()?;
+                self.terms
+                    .get(&term)
+                    .ok_or_else(|| format!("unknown term `-{term}`"))?
+                    .clone()
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.position;
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.position += 1;
+                }
+                vec![Part::Text(
+                    self.chars[start..self.position].iter().collect(),
+                )]
+            }
+            Some(c) if c.is_ascii_uppercase() => {
+                let function = self.identifier()?;
+                if function != "COLOUR" && function != "COLOR" {
+                    return Err(format!("unknown function `{function}`"));
+                }
+
+                self.skip_whitespace();
+                self.expect('(')?;
+                self.skip_whitespace();
+                let argument = self.identifier()?;
+                self.skip_whitespace();
+                self.expect(')')?;
+
+                match argument.parse::<u8>() {
+                    Ok(colour) if colour < 16 => vec![Part::Colour(colour)],
+                    _ => return Err(format!("colour must be in the range 0..16, not {argument}")),
+                }
+            }
+            Some(c) => return Err(format!("unexpected `{c}` in placeable")),
+            None => return Err("unterminated placeable".to_string()),
+        };
+
+        self.skip_whitespace();
+        self.expect('}')?;
+
+        Ok(parts)
+    }
+
+    fn string_literal(&mut self) -> Result<String, String> {
+        let mut value = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => match self.next() {
+                    Some(c @ ('"' | '\\')) => value.push(c),
+                    Some(c) => return Err(format!("unknown escape `\\{c}`")),
+                    None => return Err("unterminated string literal".to_string()),
+                },
+                Some('\n') | None => return Err("unterminated string literal".to_string()),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn select(&mut self, variable: String) -> Result<Vec<Part>, String> {
+        let mut variants = vec![];
+        let mut default = None;
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some('}') => {
+                    self.position += 1;
+                    break;
+                }
+                None => return Err(format!("unterminated selector on `${variable}`")),
+                _ => {}
+            }
+
+            if self.peek() == Some('*') {
+                self.position += 1;
+                if default.is_some() {
+                    return Err(format!(
+                        "selector on `${variable}` has two default variants"
+                    ));
+                }
+                default = Some(variants.len());
+            }
+
+            self.expect('[')?;
+            let start = self.position;
+            while self.peek().is_some_and(|c| c != ']' && c != '\n') {
+                self.position += 1;
+            }
+            let key: String = self.chars[start..self.position].iter().collect();
+            self.expect(']')?;
+
+            let key = key.trim();
+            let key = if let Ok(number) = key.parse() {
+                Key::Number(number)
+            } else if let Some(category) = PluralCategory::from_name(key) {
+                Key::Category(category)
+            } else {
+                Key::Text(key.to_string())
+            };
+
+            self.skip_blank_inline();
+            variants.push((key, self.pattern(true)?));
+        }
+
+        let default =
+            default.ok_or_else(|| format!("selector on `${variable}` has no default variant"))?;
+
+        Ok(vec![Part::Select {
+            variable,
+            variants,
+            default,
+        }])
+    }
+}
+
+fn merge_text(parts: Vec<Part>) -> Vec<Part> {
+    let mut merged: Vec<Part> = Vec::with_capacity(parts.len());
+
+    for part in parts {
+        match (merged.last_mut(), part) {
+            (Some(Part::Text(previous)), Part::Text(text)) => previous.push_str(&text),
+            (_, part) => merged.push(part),
+        }
+    }
+
+    merged
+}
+
+/// The plural rule to use for a language, based on the language code in its file name.
+pub(crate) fn plural_rule(language_code: &str) -> &'static str {
+    let language = language_code
+        .split(['-', '_'])
+        .next()
+        .unwrap_or(language_code)
+        .to_ascii_lowercase();
+
+    match language.as_str() {
+        "fr" | "pt" => "ZeroOrOne",
+        "ja" | "zh" | "ko" | "vi" | "th" | "id" | "ms" => "NoPlurals",
+        "ru" | "uk" | "be" | "sr" | "hr" | "bs" => "EastSlavic",
+        "pl" => "Polish",
+        "cs" | "sk" => "Czech",
+        _ => "OneOrOther",
+    }
+}
+
+/// Converts a language code or message id, like `pt-BR` or `items-found`, into `PtBr` or
+/// `ItemsFound`.
+pub(crate) fn camel_case(name: &str) -> String {
+    name.split(['-', '_'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first)
+                .chain(chars.map(|c| c.to_ascii_lowercase()))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+pub(crate) struct Language {
+    pub(crate) code: String,
+    pub(crate) messages: Vec<Message>,
+}
+
+/// Generates the module for `include_strings!`. The first language is the reference language, it
+/// decides which strings exist and provides any strings missing from the other languages.
+pub(crate) fn generate(
+    crate_path: &syn::Path,
+    module_name: &syn::Ident,
+    languages: &[Language],
+    files: &[String],
+) -> Result<TokenStream, String> {
+    let reference = &languages[0];
+
+    for language in &languages[1..] {
+        if let Some(message) = language
+            .messages
+            .iter()
+            .find(|message| !reference.messages.iter().any(|m| m.id == message.id))
+        {
+            return Err(format!(
+                "`{}` is in {} but not in the reference language {}",
+                message.id, language.code, reference.code
+            ));
+        }
+    }
+
+    let localisation = quote!(#crate_path::display::font::localisation);
+
+    let language_variants: Vec<_> = languages
+        .iter()
+        .map(|language| format_ident!("{}", camel_case(&language.code)))
+        .collect();
+    let language_codes: Vec<_> = languages.iter().map(|language| &language.code).collect();
+    let language_indices = 0..languages.len();
+    let plural_rules = languages
+        .iter()
+        .map(|language| format_ident!("{}", plural_rule(&language.code)));
+
+    let id_variants: Vec<_> = reference
+        .messages
+        .iter()
+        .map(|message| format_ident!("{}", camel_case(&message.id)))
+        .collect();
+    let id_names = reference.messages.iter().map(|message| &message.id);
+    let id_indices = 0..reference.messages.len();
+
+    let mut statics = vec![];
+    let mut table = vec![];
+
+    for (language_index, language) in languages.iter().enumerate() {
+        let mut row = vec![];
+
+        for (message_index, message) in reference.messages.iter().enumerate() {
+            let (owner, translated) = match language.messages.iter().find(|m| m.id == message.id) {
+                Some(translated) => (language_index, translated),
+                None => (0, message),
+            };
+
+            let name = format_ident!("MESSAGE_{}_{}", owner, message_index);
+
+            if owner == language_index {
+                let parts = parts_tokens(&localisation, &translated.parts);
+                statics.push(quote! {
+                    static #name: #localisation::Message = #localisation::Message::new(&[#(#parts),*]);
+                });
+            }
+
+            row.push(quote!(&#name));
+        }
+
+        table.push(quote!(&[#(#row),*]));
+    }
+
+    Ok(quote! {
+        pub mod #module_name {
+            const _: &[&[u8]] = &[#(include_bytes!(#files)),*];
+
+            /// The languages which have translations.
+            #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+            pub enum Language {
+                #(#[doc = #language_codes] #language_variants,)*
+            }
+
+            impl Language {
+                /// Every language, in the order they were included.
+                pub const ALL: &'static [Language] = &[#(Language::#language_variants),*];
+
+                /// The language code, taken from the name of the translation file.
+                #[must_use]
+                pub const fn code(self) -> &'static str {
+                    match self {
+                        #(Language::#language_variants => #language_codes,)*
+                    }
+                }
+            }
+
+            impl #localisation::Language for Language {
+                fn index(self) -> usize {
+                    match self {
+                        #(Language::#language_variants => #language_indices,)*
+                    }
+                }
+
+                fn plural_rule(self) -> #localisation::PluralRule {
+                    match self {
+                        #(Language::#language_variants => #localisation::PluralRule::#plural_rules,)*
+                    }
+                }
+            }
+
+            /// The strings which can be translated.
+            #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+            pub enum StringId {
+                #(#[doc = #id_names] #id_variants,)*
+            }
+
+            impl #localisation::StringId for StringId {
+                fn index(self) -> usize {
+                    match self {
+                        #(StringId::#id_variants => #id_indices,)*
+                    }
+                }
+            }
+
+            #(#statics)*
+
+            static TABLE: &[&[&#localisation::Message]] = &[#(#table),*];
+
+            /// The translated strings in one of the languages.
+            pub type Strings = #localisation::Strings<Language, StringId>;
+
+            /// The translated strings, starting in the given language.
+            #[must_use]
+            pub const fn strings(language: Language) -> Strings {
+                #localisation::Strings::new(TABLE, language)
+            }
+        }
+    })
+}
+
+fn parts_tokens(localisation: &TokenStream, parts: &[Part]) -> Vec<TokenStream> {
+    parts
+        .iter()
+        .map(|part| match part {
+            Part::Text(text) => quote!(#localisation::Part::Text(#text)),
+            Part::Variable(variable) => quote!(#localisation::Part::Variable(#variable)),
+            Part::Colour(colour) => quote!(#localisation::Part::Colour(#colour)),
+            Part::Select {
+                variable,
+                variants,
+                default,
+            } => {
+                let variants = variants.iter().map(|(key, parts)| {
+                    let key = match key {
+                        Key::Category(category) => {
+                            let category = format_ident!("{}", format!("{category:?}"));
+                            quote!(#localisation::Key::Category(#localisation::PluralCategory::#category))
+                        }
+                        Key::Number(number) => quote!(#localisation::Key::Number(#number)),
+                        Key::Text(text) => quote!(#localisation::Key::Text(#text)),
+                    };
+                    let parts = parts_tokens(localisation, parts);
+                    quote!((#key, &[#(#parts),*]))
+                });
+
+                quote!(#localisation::Part::Select {
+                    variable: #variable,
+                    variants: &[#(#variants),*],
+                    default: #default,
+                })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_messages_with_placeables() {
+        let messages = parse_ftl(
+            "# A comment\n\
+             -hero = Agb\n\
+             greeting = Hello, { $name }!\n\
+             intro =\n    Welcome to\n    { -hero }'s { COLOUR(2) }house{ COLOUR(1) }.\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            messages,
+            vec![
+                Message {
+                    id: "greeting".to_string(),
+                    parts: vec![
+                        Part::Text("Hello, ".to_string()),
+                        Part::Variable("name".to_string()),
+                        Part::Text("!".to_string()),
+                    ],
+                },
+                Message {
+                    id: "intro".to_string(),
+                    parts: vec![
+                        Part::Text("Welcome to\nAgb's ".to_string()),
+                        Part::Colour(2),
+                        Part::Text("house".to_string()),
+                        Part::Colour(1),
+                        Part::Text(".".to_string()),
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_plural_selectors() {
+        let messages = parse_ftl(
+            "items = { $count ->\n    [0] No items\n    [one] One item\n   *[other] { $count } items\n}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            messages[0].parts,
+            vec![Part::Select {
+                variable: "count".to_string(),
+                variants: vec![
+                    (Key::Number(0), vec![Part::Text("No items".to_string())]),
+                    (
+                        Key::Category(PluralCategory::One),
+                        vec![Part::Text("One item".to_string())]
+                    ),
+                    (
+                        Key::Category(PluralCategory::Other),
+                        vec![
+                            Part::Variable("count".to_string()),
+                            Part::Text(" items".to_string())
+                        ]
+                    ),
+                ],
+                default: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_messages() {
+        assert!(parse_ftl("items = { $count ->\n    [one] One item\n}\n").is_err());
+        assert!(parse_ftl("colour = { COLOUR(16) }\n").is_err());
+        assert!(parse_ftl("missing = { -unknown }\n").is_err());
+        assert!(parse_ftl("twice = a\ntwice = b\n").is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Encodes sounds into the IMA ADPCM format which `agb::sound::mixer` decodes at
+//! runtime. This must be kept in sync with the decoder in `agb/src/sound/mixer/adpcm.rs`.
+//!
+//! The sound is split into blocks of [`BLOCK_SAMPLES`] samples for each channel,
+//! each starting with the decoder state at that point so that playback can
+//! start from anywhere. Stereo sounds store the left block then the right block.
+
+/// The number of samples in each block
+const BLOCK_SAMPLES: usize = 256;
+
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+const INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+#[derive(Clone, Copy, Default)]
+struct ChannelState {
+    predictor: i32,
+    step_index: i32,
+}
+
+impl ChannelState {
+    /// Applies `nibble` exactly as the decoder would
+    fn step(&mut self, nibble: u8) {
+        let step = STEP_TABLE[self.step_index as usize];
+
+        let mut difference = step >> 3;
+        if nibble & 1 != 0 {
+            difference += step >> 2;
+        }
+        if nibble & 2 != 0 {
+            difference += step >> 1;
+        }
+        if nibble & 4 != 0 {
+            difference += step;
+        }
+        if nibble & 8 != 0 {
+            difference = -difference;
+        }
+
+        self.predictor = (self.predictor + difference).clamp(i16::MIN.into(), i16::MAX.into());
+        self.step_index = (self.step_index + INDEX_TABLE[usize::from(nibble)]).clamp(0, 88);
+    }
+
+    /// Picks the nibble which gets closest to `sample` and moves on to it
+    fn encode(&mut self, sample: i32) -> u8 {
+        let step = STEP_TABLE[self.step_index as usize];
+        let mut difference = sample - self.predictor;
+
+        let mut nibble = 0;
+        if difference < 0 {
+            nibble = 8;
+            difference = -difference;
+        }
+
+        // the decoder always adds step / 8, so the remaining bits need to
+        // account for the rest of the difference
+        let mut threshold = step;
+        for bit in [4, 2, 1] {
+            if difference >= threshold {
+                nibble |= bit;
+                difference -= threshold;
+            }
+            threshold >>= 1;
+        }
+
+        self.step(nibble);
+        nibble
+    }
+}
+
+/// Encodes the channels into blocks, returning the data and the number of
+/// bytes the mixer will see once it has been decoded.
+pub(crate) fn encode(channels: &[Vec<f64>]) -> (Vec<u8>, usize) {
+    let length = channels.first().map_or(0, Vec::len);
+    let mut states = vec![ChannelState::default(); channels.len()];
+    let mut data = vec![];
+
+    for block_start in (0..length).step_by(BLOCK_SAMPLES) {
+        for (channel, state) in channels.iter().zip(&mut states) {
+            data.extend((state.predictor as i16).to_le_bytes());
+            data.push(state.step_index as u8);
+            data.push(0);
+
+            // the last block is padded with silence
+            let nibbles = (block_start..block_start + BLOCK_SAMPLES)
+                .map(|i| {
+                    let sample = channel.get(i).copied().unwrap_or(0.0);
+                    state.encode((sample * 32768.0).round().clamp(-32768.0, 32767.0) as i32)
+                })
+                .collect::<Vec<_>>();
+
+            data.extend(nibbles.chunks(2).map(|pair| pair[0] | (pair[1] << 4)));
+        }
+    }
+
+    (data, length * channels.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    // A straightforward decoder for checking the encoder against
+    fn decode(data: &[u8], channel_count: usize, length: usize) -> Vec<Vec<i8>> {
+        let block_bytes = 4 + BLOCK_SAMPLES / 2;
+        let mut channels = vec![vec![]; channel_count];
+
+        for (i, block) in data.chunks(block_bytes).enumerate() {
+            let channel = &mut channels[i % channel_count];
+            let mut state = ChannelState {
+                predictor: i16::from_le_bytes([block[0], block[1]]).into(),
+                step_index: block[2].into(),
+            };
+
+            for byte in &block[4..] {
+                for nibble in [byte & 0xf, byte >> 4] {
+                    state.step(nibble);
+                    channel.push((state.predictor >> 8) as i8);
+                }
+            }
+        }
+
+        for channel in &mut channels {
+            channel.truncate(length);
+        }
+        channels
+    }
+
+    #[test]
+    fn encoded_sound_is_close_to_the_original() {
+        let left = (0..1000)
+            .map(|i| (2.0 * PI * f64::from(i) / 50.0).sin() * 0.8)
+            .collect::<Vec<_>>();
+        let right = (0..1000)
+            .map(|i| (2.0 * PI * f64::from(i) / 120.0).sin() * 0.3)
+            .collect::<Vec<_>>();
+
+        let (data, length) = encode(&[left.clone(), right.clone()]);
+        assert_eq!(length, 2000);
+        // 4 blocks of 256 samples for each channel
+        assert_eq!(data.len(), 4 * 2 * (4 + 128));
+
+        let decoded = decode(&data, 2, 1000);
+        for (original, decoded) in [left, right].iter().zip(&decoded) {
+            assert_eq!(decoded.len(), 1000);
+            // the step size starts off small, so it takes a few samples to catch up
+            for (original, decoded) in original.iter().zip(decoded).skip(20) {
+                let expected = original * 128.0;
+                assert!(
+                    (expected - f64::from(*decoded)).abs() < 4.0,
+                    "{expected} vs {decoded}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn block_headers_match_the_decoder_state() {
+        let samples = (0..600)
+            .map(|i| (f64::from(i) / 37.0).sin() * 0.5)
+            .collect::<Vec<_>>();
+        let (data, _) = encode(&[samples]);
+
+        // decoding a later block on its own gives the same result as decoding
+        // everything up to it
+        let everything = decode(&data, 1, 600);
+        let block_bytes = 4 + BLOCK_SAMPLES / 2;
+        let second_block = decode(&data[block_bytes..], 1, 600 - BLOCK_SAMPLES);
+        assert_eq!(everything[0][BLOCK_SAMPLES..], second_block[0]);
+    }
+}
@@ -1,10 +1,19 @@
 #![deny(clippy::all)]
 
 use proc_macro::TokenStream;
-use proc_macro2::Literal;
+use proc_macro2::{Literal, Span};
 use quote::{ToTokens, quote};
-use std::path::Path;
-use syn::parse_macro_input;
+use std::{io::Read, path::Path};
+use syn::{
+    Token,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+};
+
+mod adpcm;
+mod loop_points;
+mod resample;
+mod sfxr;
 
 use quote::TokenStreamExt;
 struct ByteString<'a>(&'a [u8]);
@@ -14,36 +23,442 @@ impl ToTokens for ByteString<'_> {
     }
 }
 
+/// The frequencies the mixer can run at, see `agb::sound::mixer::Frequency`
+const SUPPORTED_FREQUENCIES: &[u32] = &[10512, 13379, 18157, 21024, 26758, 31536, 32768];
+
+/// The environment variable to read the frequency from if it isn't passed to the macro
+const FREQUENCY_ENV_VAR: &str = "AGB_SOUND_FREQUENCY";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Channels {
+    Mono,
+    Stereo,
+}
+
+struct IncludeWavInput {
+    filename: syn::LitStr,
+    frequency: Option<(u32, Span)>,
+    resample: Option<Span>,
+    channels: Option<(Channels, Span)>,
+    adpcm: bool,
+}
+
+impl Parse for IncludeWavInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let filename = input.parse()?;
+
+        let mut result = Self {
+            filename,
+            frequency: None,
+            resample: None,
+            channels: None,
+            adpcm: false,
+        };
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+
+            // accept both `Hz18157` and `Frequency::Hz18157`
+            let path: syn::Path = input.parse()?;
+            let option = path
+                .segments
+                .last()
+                .expect("paths always have at least one segment")
+                .ident
+                .clone();
+            let span = option.span();
+
+            match option.to_string().as_str() {
+                "resample" => result.resample = Some(span),
+                "adpcm" => result.adpcm = true,
+                "mono" | "stereo" => {
+                    if result.channels.is_some() {
+                        return Err(syn::Error::new(
+                            span,
+                            "Only one of `mono` or `stereo` can be given",
+                        ));
+                    }
+
+                    let channels = if option == "mono" {
+                        Channels::Mono
+                    } else {
+                        Channels::Stereo
+                    };
+                    result.channels = Some((channels, span));
+                }
+                name => {
+                    let frequency = parse_frequency(name).ok_or_else(|| {
+                        let message = if name.starts_with("Hz") {
+                            format!(
+                                "The mixer can't run at `{name}`, it supports {}",
+                                SUPPORTED_FREQUENCIES
+                                    .iter()
+                                    .map(|frequency| format!("`Hz{frequency}`"))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            )
+                        } else {
+                            format!(
+                                "Unknown option `{name}`, expected a frequency like `Hz18157`, `resample`, `mono`, `stereo` or `adpcm`"
+                            )
+                        };
+                        syn::Error::new(span, message)
+                    })?;
+                    result.frequency = Some((frequency, span));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Parses `Hz18157` or `18157` into 18157 if it is a frequency the mixer supports
+fn parse_frequency(name: &str) -> Option<u32> {
+    let frequency = name.strip_prefix("Hz").unwrap_or(name).parse().ok()?;
+    SUPPORTED_FREQUENCIES
+        .contains(&frequency)
+        .then_some(frequency)
+}
+
+/// The frequency passed to the macro, or from [`FREQUENCY_ENV_VAR`] if there wasn't one
+fn mixer_frequency(frequency: Option<(u32, Span)>, span: Span) -> syn::Result<Option<(u32, Span)>> {
+    if frequency.is_some() {
+        return Ok(frequency);
+    }
+
+    match std::env::var(FREQUENCY_ENV_VAR) {
+        Ok(value) => Ok(Some((
+            parse_frequency(value.trim()).ok_or_else(|| {
+                syn::Error::new(
+                    span,
+                    format!("{FREQUENCY_ENV_VAR} is set to an unsupported frequency `{value}`"),
+                )
+            })?,
+            span,
+        ))),
+        Err(_) => Ok(None),
+    }
+}
+
 #[proc_macro]
 pub fn include_wav(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as syn::LitStr);
+    let input = parse_macro_input!(input as IncludeWavInput);
 
-    let filename = input.value();
+    match include_wav_inner(&input) {
+        Ok(result) => result.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn include_wav_inner(input: &IncludeWavInput) -> syn::Result<proc_macro2::TokenStream> {
+    let filename = input.filename.value();
 
     let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
     let path = Path::new(&root).join(&*filename);
 
     let include_path = path.to_string_lossy();
 
-    let wav_reader = hound::WavReader::open(&path)
-        .unwrap_or_else(|_| panic!("Failed to load file {include_path}"));
+    let frequency = mixer_frequency(input.frequency, input.filename.span())?;
+
+    let source = Source::open(&path).map_err(|err| {
+        syn::Error::new(
+            input.filename.span(),
+            format!("Failed to load file {include_path}: {err}"),
+        )
+    })?;
+
+    let (sample_rate, channel_count) = source.format();
+
+    if let Some((frequency, span)) = frequency
+        && input.resample.is_none()
+        && frequency != sample_rate
+    {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "{filename} has a sample rate of {sample_rate}Hz but the mixer runs at {frequency}Hz, so it would play at the wrong speed. Add `resample` to convert it while compiling"
+            ),
+        ));
+    }
+
+    if let (Some(span), None) = (input.resample, frequency) {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "`resample` needs to know the mixer frequency. Pass it as an argument like `Hz18157` or set {FREQUENCY_ENV_VAR}"
+            ),
+        ));
+    }
 
-    let samples: Vec<u8> = samples_from_reader(wav_reader).collect();
-    let samples = ByteString(&samples);
+    // only wav files have loop points
+    let mut loop_points = match source {
+        Source::Wav(_) => std::fs::read(&path)
+            .ok()
+            .and_then(|file| loop_points::read(&file)),
+        Source::Decoded(_) => None,
+    };
 
-    let result = quote! {
+    let (constructor, output_channels) = match source {
+        // the mixer can play these as they are
+        Source::Wav(wav_reader)
+            if input.resample.is_none()
+                && input.channels.is_none()
+                && !input.adpcm
+                && channel_count <= 2 =>
+        {
+            let samples: Vec<u8> = samples_from_reader(wav_reader).collect();
+            (SoundDataConstructor::Pcm(samples), channel_count)
+        }
+        source => {
+            let audio = source.into_audio();
+            let mut audio = convert_channels(audio, input.channels.map(|(channels, _)| channels))
+                .map_err(|message| syn::Error::new(input.filename.span(), message))?;
+
+            if let (Some(_), Some((frequency, _))) = (input.resample, frequency) {
+                audio = audio.resample(frequency);
+                loop_points = loop_points.map(|points| points.resample(sample_rate, frequency));
+            }
+
+            let constructor = if input.adpcm {
+                let (data, length) = adpcm::encode(&audio.channels);
+                SoundDataConstructor::Adpcm(data, length)
+            } else {
+                SoundDataConstructor::Pcm(audio.to_samples())
+            };
+
+            (constructor, audio.channels.len())
+        }
+    };
+
+    let (data, length, constructor) = match &constructor {
+        SoundDataConstructor::Pcm(samples) => {
+            (samples, samples.len(), quote! { SoundData::new(DATA) })
+        }
+        SoundDataConstructor::Adpcm(data, length) => (
+            data,
+            *length,
+            quote! { SoundData::new_adpcm(DATA, #length) },
+        ),
+    };
+
+    let constructor = match loop_points {
+        Some(points) => {
+            let frames = length / output_channels.max(1);
+            let start = points.start as usize;
+            let end = points.end.map_or(frames, |end| (end as usize).min(frames));
+
+            if start >= end {
+                return Err(syn::Error::new(
+                    input.filename.span(),
+                    format!(
+                        "{filename} has a loop starting at frame {start}, which isn't before the end of the loop at frame {end}"
+                    ),
+                ));
+            }
+
+            quote! { #constructor.with_loop(#start, #end) }
+        }
+        None => constructor,
+    };
+
+    let data = ByteString(data);
+
+    Ok(quote! {
         {
             #[repr(align(4))]
             struct AlignmentWrapper<const N: usize>([u8; N]);
 
             const _: &[u8] = include_bytes!(#include_path);
-            static DATA: &[u8] = &AlignmentWrapper(*#samples).0;
+            // rebuild if the configured frequency changes
+            const _: Option<&str> = option_env!(#FREQUENCY_ENV_VAR);
+            static DATA: &[u8] = &AlignmentWrapper(*#data).0;
 
-            unsafe { SoundData::new(DATA) }
+            unsafe { #constructor }
         }
+    })
+}
+
+struct IncludeSfxrInput {
+    filename: syn::LitStr,
+    frequency: Option<(u32, Span)>,
+}
+
+impl Parse for IncludeSfxrInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let filename = input.parse()?;
+        let mut frequency = None;
+
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let path: syn::Path = input.parse()?;
+            let name = &path
+                .segments
+                .last()
+                .expect("paths always have at least one segment")
+                .ident;
+
+            frequency = Some((
+                parse_frequency(&name.to_string()).ok_or_else(|| {
+                    syn::Error::new(
+                        name.span(),
+                        format!(
+                            "Expected a mixer frequency like `Hz18157`, it supports {}",
+                            SUPPORTED_FREQUENCIES
+                                .iter()
+                                .map(|frequency| format!("`Hz{frequency}`"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    )
+                })?,
+                name.span(),
+            ));
+
+            input.parse::<Option<Token![,]>>()?;
+        }
+
+        Ok(Self {
+            filename,
+            frequency,
+        })
+    }
+}
+
+#[proc_macro]
+pub fn include_sfxr(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as IncludeSfxrInput);
+
+    match include_sfxr_inner(&input) {
+        Ok(result) => result.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn include_sfxr_inner(input: &IncludeSfxrInput) -> syn::Result<proc_macro2::TokenStream> {
+    let filename = input.filename.value();
+
+    let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
+    let path = Path::new(&root).join(&*filename);
+
+    let include_path = path.to_string_lossy();
+
+    let Some((frequency, _)) = mixer_frequency(input.frequency, input.filename.span())? else {
+        return Err(syn::Error::new(
+            input.filename.span(),
+            format!(
+                "`include_sfxr!` needs to know the mixer frequency. Pass it as an argument like `Hz18157` or set {FREQUENCY_ENV_VAR}"
+            ),
+        ));
     };
 
-    TokenStream::from(result)
+    let error = |err: String| {
+        syn::Error::new(
+            input.filename.span(),
+            format!("Failed to load file {include_path}: {err}"),
+        )
+    };
+
+    let file = std::fs::read(&path).map_err(|err| error(err.to_string()))?;
+    let parameters = if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("sfs"))
+    {
+        sfxr::Parameters::from_sfs(&file)
+    } else {
+        sfxr::Parameters::from_json(&file)
+    }
+    .map_err(error)?;
+
+    let audio = Audio {
+        sample_rate: sfxr::SAMPLE_RATE,
+        channels: vec![sfxr::synthesise(&parameters).map_err(error)?],
+    }
+    .resample(frequency);
+
+    let samples = audio.to_samples();
+    let data = ByteString(&samples);
+
+    Ok(quote! {
+        {
+            #[repr(align(4))]
+            struct AlignmentWrapper<const N: usize>([u8; N]);
+
+            const _: &[u8] = include_bytes!(#include_path);
+            // rebuild if the configured frequency changes
+            const _: Option<&str> = option_env!(#FREQUENCY_ENV_VAR);
+            static DATA: &[u8] = &AlignmentWrapper(*#data).0;
+
+            unsafe { SoundData::new(DATA) }
+        }
+    })
+}
+
+enum SoundDataConstructor {
+    Pcm(Vec<u8>),
+    /// The encoded data along with how many samples it decodes to
+    Adpcm(Vec<u8>, usize),
+}
+
+/// A sound file which has been opened, but not necessarily decoded yet. WAV files
+/// are read with `hound` which lets us skip decoding into floats for the common case
+/// where the mixer can play them as they are. Everything else is decoded with
+/// `symphonia` up front.
+enum Source {
+    Wav(hound::WavReader<std::io::BufReader<std::fs::File>>),
+    Decoded(Audio),
+}
+
+impl Source {
+    fn open(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "wav" => Ok(Self::Wav(
+                hound::WavReader::open(path).map_err(|err| err.to_string())?,
+            )),
+            "ogg" | "flac" | "mp3" => {
+                let file = std::fs::File::open(path).map_err(|err| err.to_string())?;
+                Ok(Self::Decoded(Audio::decode(Box::new(file), &extension)?))
+            }
+            // wav files don't always use the `.wav` extension, such as `.wave` or `.bwf`
+            _ if is_wav(path) => Ok(Self::Wav(
+                hound::WavReader::open(path).map_err(|err| err.to_string())?,
+            )),
+            _ => Err(format!(
+                "Unsupported file type `{extension}`, expected wav, ogg, flac or mp3"
+            )),
+        }
+    }
+
+    /// The sample rate and number of channels
+    fn format(&self) -> (u32, usize) {
+        match self {
+            Self::Wav(reader) => (reader.spec().sample_rate, reader.spec().channels.into()),
+            Self::Decoded(audio) => (audio.sample_rate, audio.channels.len()),
+        }
+    }
+
+    fn into_audio(self) -> Audio {
+        match self {
+            Self::Wav(reader) => Audio::from_reader(reader),
+            Self::Decoded(audio) => audio,
+        }
+    }
+}
+
+/// Whether the file starts with the header of a wav file
+fn is_wav(path: &Path) -> bool {
+    let mut header = [0; 12];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE")
 }
 
 fn samples_from_reader<'a, R>(reader: hound::WavReader<R>) -> Box<dyn Iterator<Item = u8> + 'a>
@@ -57,7 +472,7 @@ where
         hound::SampleFormat::Float => Box::new(
             reader
                 .into_samples::<f32>()
-                .map(|sample| (sample.unwrap() * (i8::MAX as f32)) as u8),
+                .map(|sample| quantise(f64::from(sample.unwrap()))),
         ),
         hound::SampleFormat::Int => Box::new(
             reader
@@ -66,3 +481,345 @@ where
         ),
     }
 }
+
+/// Converts a sample between -1 and 1 into the signed 8-bit format the mixer uses
+fn quantise(sample: f64) -> u8 {
+    (sample * 128.0).floor().clamp(-128.0, 127.0) as i8 as u8
+}
+
+/// Decoded audio with each channel stored separately and samples between -1 and 1
+struct Audio {
+    sample_rate: u32,
+    channels: Vec<Vec<f64>>,
+}
+
+impl Audio {
+    fn from_reader<R: std::io::Read>(reader: hound::WavReader<R>) -> Self {
+        let spec = reader.spec();
+        let channel_count = usize::from(spec.channels);
+
+        let samples: Vec<f64> = match spec.sample_format {
+            hound::SampleFormat::Float => reader
+                .into_samples::<f32>()
+                .map(|sample| f64::from(sample.unwrap()))
+                .collect(),
+            hound::SampleFormat::Int => {
+                let scale = f64::from(1u32 << (spec.bits_per_sample - 1));
+                reader
+                    .into_samples::<i32>()
+                    .map(|sample| f64::from(sample.unwrap()) / scale)
+                    .collect()
+            }
+        };
+
+        Self::from_interleaved(spec.sample_rate, channel_count, &samples)
+    }
+
+    /// Decodes a compressed file such as an ogg, flac or mp3 where `extension`
+    /// gives a hint as to which one it is
+    fn decode(
+        source: Box<dyn symphonia::core::io::MediaSource>,
+        extension: &str,
+    ) -> Result<Self, String> {
+        use symphonia::core::{
+            audio::SampleBuffer, codecs::DecoderOptions, errors::Error, formats::FormatOptions,
+            io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+        };
+
+        let stream = MediaSourceStream::new(source, Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension(extension);
+
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|err| err.to_string())?
+            .format;
+
+        let track = format
+            .default_track()
+            .ok_or_else(|| "File doesn't contain any audio".to_string())?;
+        let track_id = track.id;
+
+        let mut sample_rate = track.codec_params.sample_rate;
+        let mut channel_count = track.codec_params.channels.map(|channels| channels.count());
+
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|err| err.to_string())?;
+
+        let mut samples = vec![];
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    break;
+                }
+                Err(err) => return Err(err.to_string()),
+            };
+
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // a corrupt packet, which we can skip over
+                Err(Error::DecodeError(_)) => continue,
+                Err(err) => return Err(err.to_string()),
+            };
+            let spec = *decoded.spec();
+            sample_rate = Some(spec.rate);
+            channel_count = Some(spec.channels.count());
+
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            buffer.copy_interleaved_ref(decoded);
+            samples.extend(buffer.samples().iter().map(|&sample| f64::from(sample)));
+        }
+
+        match (sample_rate, channel_count) {
+            (Some(sample_rate), Some(channel_count)) if channel_count > 0 => {
+                Ok(Self::from_interleaved(sample_rate, channel_count, &samples))
+            }
+            _ => Err("Couldn't work out the sample rate or channels".to_string()),
+        }
+    }
+
+    fn from_interleaved(sample_rate: u32, channel_count: usize, samples: &[f64]) -> Self {
+        let channels = (0..channel_count)
+            .map(|channel| {
+                samples
+                    .iter()
+                    .skip(channel)
+                    .step_by(channel_count)
+                    .copied()
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            sample_rate,
+            channels,
+        }
+    }
+
+    fn resample(self, frequency: u32) -> Self {
+        Self {
+            channels: self
+                .channels
+                .iter()
+                .map(|channel| resample::resample(channel, self.sample_rate, frequency))
+                .collect(),
+            sample_rate: frequency,
+        }
+    }
+
+    /// Interleaves the channels into the format the mixer expects
+    fn to_samples(&self) -> Vec<u8> {
+        let length = self.channels.first().map_or(0, Vec::len);
+
+        (0..length)
+            .flat_map(|i| {
+                self.channels
+                    .iter()
+                    .map(move |channel| quantise(channel[i]))
+            })
+            .collect()
+    }
+}
+
+/// Downmixes or duplicates channels to get the requested layout. Without a
+/// requested layout, the mixer can play mono or stereo sounds as they are.
+fn convert_channels(audio: Audio, target: Option<Channels>) -> Result<Audio, String> {
+    let channel_count = audio.channels.len();
+
+    let channels = match (target, channel_count) {
+        (None, 1 | 2) | (Some(Channels::Mono), 1) | (Some(Channels::Stereo), 2) => audio.channels,
+        (Some(Channels::Mono), _) => {
+            let length = audio.channels[0].len();
+            vec![
+                (0..length)
+                    .map(|i| {
+                        audio.channels.iter().map(|channel| channel[i]).sum::<f64>()
+                            / channel_count as f64
+                    })
+                    .collect(),
+            ]
+        }
+        (Some(Channels::Stereo), 1) => vec![audio.channels[0].clone(), audio.channels[0].clone()],
+        (_, _) => {
+            return Err(format!(
+                "The mixer can only play mono or stereo sounds, but this has {channel_count} channels. Add `mono` to mix them down"
+            ));
+        }
+    };
+
+    Ok(Audio {
+        sample_rate: audio.sample_rate,
+        channels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantises_like_shifting_integer_samples() {
+        for sample in [i16::MIN, -300, -256, -1, 0, 1, 255, 256, i16::MAX] {
+            let expected = (i32::from(sample) >> 8) as u8;
+            assert_eq!(quantise(f64::from(sample) / 32768.0), expected, "{sample}");
+        }
+
+        assert_eq!(quantise(1.0), 127);
+        assert_eq!(quantise(-1.5), 128);
+    }
+
+    #[test]
+    fn converts_channel_layouts() {
+        let stereo = Audio {
+            sample_rate: 10512,
+            channels: vec![vec![0.5, -0.5], vec![0.25, 0.5]],
+        };
+
+        let mono = convert_channels(stereo, Some(Channels::Mono)).unwrap();
+        assert_eq!(mono.channels, [vec![0.375, 0.0]]);
+
+        let stereo = convert_channels(mono, Some(Channels::Stereo)).unwrap();
+        assert_eq!(stereo.to_samples(), [48, 48, 0, 0]);
+
+        let surround = Audio {
+            sample_rate: 10512,
+            channels: vec![vec![0.0]; 6],
+        };
+        assert!(convert_channels(surround, None).is_err());
+    }
+
+    // A flac file with a single frame of uncompressed 16-bit samples, which is
+    // the simplest valid flac file we can write by hand
+    fn flac(sample_rate: u32, channels: &[Vec<i16>]) -> Vec<u8> {
+        fn crc(data: &[u8], polynomial: u16, width: u32) -> u16 {
+            let top_bit = 1 << (width - 1);
+            let mask = ((1u32 << width) - 1) as u16;
+            let mut crc = 0u16;
+            for &byte in data {
+                crc ^= u16::from(byte) << (width - 8);
+                for _ in 0..8 {
+                    crc = if crc & top_bit != 0 {
+                        (crc << 1) ^ polynomial
+                    } else {
+                        crc << 1
+                    } & mask;
+                }
+            }
+            crc
+        }
+
+        let length = channels[0].len();
+        let mut file = b"fLaC".to_vec();
+
+        // STREAMINFO, which is also the last metadata block
+        file.extend([0x80, 0, 0, 34]);
+        file.extend((length as u16).to_be_bytes());
+        file.extend((length as u16).to_be_bytes());
+        file.extend([0; 6]);
+        let info = (u64::from(sample_rate) << 44)
+            | ((channels.len() as u64 - 1) << 41)
+            | (15 << 36)
+            | length as u64;
+        file.extend(info.to_be_bytes());
+        file.extend([0; 16]);
+
+        // frame header with a 16-bit block size, the sample rate from STREAMINFO,
+        // independent channels and 16-bit samples
+        let mut frame = vec![
+            0xff,
+            0xf8,
+            0x70,
+            ((channels.len() as u8 - 1) << 4) | 0x08,
+            0,
+        ];
+        frame.extend((length as u16 - 1).to_be_bytes());
+        frame.push(crc(&frame, 0x07, 8) as u8);
+
+        for channel in channels {
+            // verbatim subframe
+            frame.push(0x02);
+            frame.extend(channel.iter().flat_map(|sample| sample.to_be_bytes()));
+        }
+
+        frame.extend(crc(&frame, 0x8005, 16).to_be_bytes());
+        file.extend(frame);
+        file
+    }
+
+    #[test]
+    fn decodes_flac() {
+        let left = (0..100).map(|i| i * 300 - 15000).collect::<Vec<i16>>();
+        let right = (0..100).map(|i| 10000 - i * 200).collect::<Vec<i16>>();
+        let file = flac(18157, &[left.clone(), right.clone()]);
+
+        let audio = Audio::decode(Box::new(std::io::Cursor::new(file)), "flac").unwrap();
+
+        assert_eq!(audio.sample_rate, 18157);
+        assert_eq!(audio.channels.len(), 2);
+        for (decoded, original) in audio.channels.iter().zip([left, right]) {
+            let original = original
+                .iter()
+                .map(|&sample| f64::from(sample) / 32768.0)
+                .collect::<Vec<_>>();
+            assert_eq!(decoded, &original);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_file_types() {
+        assert!(Source::open(Path::new("music.mod")).is_err());
+
+        let path = std::env::temp_dir().join(format!("agb-not-a-wav-{}.mod", std::process::id()));
+        std::fs::write(&path, b"M.K. this is not a wav file").unwrap();
+        let result = Source::open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn opens_wav_files_with_other_extensions() {
+        for name in ["sound.wave", "sound.bwf", "sound"] {
+            let path = std::env::temp_dir().join(format!("agb-{}-{name}", std::process::id()));
+            let spec = hound::WavSpec {
+                channels: 1,
+                sample_rate: 18157,
+                bits_per_sample: 8,
+                sample_format: hound::SampleFormat::Int,
+            };
+            let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+            for sample in [0i8, 10, -10] {
+                writer.write_sample(sample).unwrap();
+            }
+            writer.finalize().unwrap();
+
+            let source = Source::open(&path);
+            std::fs::remove_file(&path).unwrap();
+
+            let source = source.unwrap_or_else(|err| panic!("{name}: {err}"));
+            assert!(matches!(source, Source::Wav(_)), "{name}");
+            assert_eq!(source.format(), (18157, 1));
+        }
+    }
+
+    #[test]
+    fn parses_frequencies() {
+        assert_eq!(parse_frequency("Hz18157"), Some(18157));
+        assert_eq!(parse_frequency("32768"), Some(32768));
+        assert_eq!(parse_frequency("Hz21024"), Some(21024));
+        assert_eq!(parse_frequency("Hz44100"), None);
+        assert_eq!(parse_frequency("resample"), None);
+    }
+}
@@ -0,0 +1,195 @@
+//! Reads loop points from the `smpl` and `cue ` chunks of a wav file. `hound`
+//! skips over any chunks it doesn't need, so we find them ourselves.
+
+/// The region of a sound to repeat, in frames
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LoopPoints {
+    pub start: u32,
+    /// The first frame after the loop, or `None` to loop at the end of the file
+    pub end: Option<u32>,
+}
+
+impl LoopPoints {
+    /// Moves the loop points to where they'd be after resampling from `from` Hz
+    /// to `to` Hz
+    pub(crate) fn resample(self, from: u32, to: u32) -> Self {
+        let scale = |frame: u32| {
+            ((u64::from(frame) * u64::from(to) + u64::from(from) / 2) / u64::from(from)) as u32
+        };
+
+        Self {
+            start: scale(self.start),
+            end: self.end.map(scale),
+        }
+    }
+}
+
+/// Finds the loop points in the wav file `data`. The first loop in the `smpl`
+/// chunk is used if there is one, otherwise the first cue marker is used as the
+/// start of the loop and the second one, if present, as the end.
+pub(crate) fn read(data: &[u8]) -> Option<LoopPoints> {
+    if data.get(0..4)? != b"RIFF" || data.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut smpl = None;
+    let mut cue = None;
+
+    let mut chunks = &data[12..];
+    while chunks.len() >= 8 {
+        let id = &chunks[0..4];
+        let size = u32::from_le_bytes(chunks[4..8].try_into().unwrap()) as usize;
+        // some writers don't fill in the size of the last chunk
+        let Some(body) = chunks.get(8..8 + size) else {
+            break;
+        };
+
+        match id {
+            b"smpl" => smpl = Some(body),
+            b"cue " => cue = Some(body),
+            _ => {}
+        }
+
+        // chunks are padded to an even length
+        chunks = chunks.get(8 + size + (size & 1)..).unwrap_or_default();
+    }
+
+    smpl.and_then(read_smpl).or_else(|| cue.and_then(read_cue))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().unwrap(),
+    ))
+}
+
+fn read_smpl(smpl: &[u8]) -> Option<LoopPoints> {
+    let loop_count = read_u32(smpl, 28)?;
+    if loop_count == 0 {
+        return None;
+    }
+
+    // each loop is a cue point id, type, start, end, fraction and play count,
+    // where the end is the last frame that is part of the loop
+    let start = read_u32(smpl, 36 + 8)?;
+    let end = read_u32(smpl, 36 + 12)?;
+
+    Some(LoopPoints {
+        start,
+        end: Some(end.saturating_add(1)),
+    })
+}
+
+fn read_cue(cue: &[u8]) -> Option<LoopPoints> {
+    let cue_count = read_u32(cue, 0)? as usize;
+
+    // each cue point is an id, position, chunk id, chunk start, block start and
+    // sample offset, where the sample offset is the frame the marker is at
+    let mut markers = (0..cue_count)
+        .map(|i| read_u32(cue, 4 + i * 24 + 20))
+        .collect::<Option<Vec<_>>>()?;
+    markers.sort_unstable();
+
+    Some(LoopPoints {
+        start: *markers.first()?,
+        end: markers.get(1).copied(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut body = b"WAVE".to_vec();
+        for (id, data) in chunks {
+            body.extend(*id);
+            body.extend((data.len() as u32).to_le_bytes());
+            body.extend(data);
+            if data.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+
+        let mut file = b"RIFF".to_vec();
+        file.extend((body.len() as u32).to_le_bytes());
+        file.extend(body);
+        file
+    }
+
+    fn words(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn reads_smpl_loops() {
+        let mut smpl = words(&[0; 7]);
+        smpl.extend(words(&[1, 0]));
+        smpl.extend(words(&[0, 0, 1000, 4999, 0, 0]));
+
+        let file = wav(&[
+            (b"fmt ", vec![0; 16]),
+            (b"odd ", vec![1, 2, 3]),
+            (b"data", vec![0; 10]),
+            (b"smpl", smpl),
+        ]);
+
+        assert_eq!(
+            read(&file),
+            Some(LoopPoints {
+                start: 1000,
+                end: Some(5000)
+            })
+        );
+    }
+
+    #[test]
+    fn reads_cue_markers() {
+        let mut cue = words(&[2]);
+        cue.extend(words(&[1, 0, u32::from_le_bytes(*b"data"), 0, 0, 800]));
+        cue.extend(words(&[2, 0, u32::from_le_bytes(*b"data"), 0, 0, 300]));
+
+        let file = wav(&[(b"data", vec![0; 10]), (b"cue ", cue.clone())]);
+        assert_eq!(
+            read(&file),
+            Some(LoopPoints {
+                start: 300,
+                end: Some(800)
+            })
+        );
+
+        // a single marker loops to the end of the file
+        let mut single = words(&[1]);
+        single.extend(&cue[4..28]);
+        let file = wav(&[(b"cue ", single)]);
+        assert_eq!(
+            read(&file),
+            Some(LoopPoints {
+                start: 800,
+                end: None
+            })
+        );
+    }
+
+    #[test]
+    fn no_loop_points() {
+        assert_eq!(read(&wav(&[(b"data", vec![0; 10])])), None);
+        assert_eq!(read(b"not a wav file"), None);
+    }
+
+    #[test]
+    fn resamples_loop_points() {
+        let points = LoopPoints {
+            start: 44100,
+            end: Some(88200),
+        };
+
+        assert_eq!(
+            points.resample(44100, 18157),
+            LoopPoints {
+                start: 18157,
+                end: Some(36314)
+            }
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! A windowed sinc resampler. This is slow compared to what you'd want to use
+//! at runtime, but we only run it once at compile time so we can afford to do
+//! it properly and avoid aliasing.
+
+use std::f64::consts::PI;
+
+// How many zero crossings of the sinc function to use either side of each
+// output sample. More gives a sharper cutoff at the cost of compile time.
+const ZERO_CROSSINGS: f64 = 32.0;
+// The shape parameter for the Kaiser window. 8.6 gives about 90dB of stopband
+// attenuation which is well below what 8-bit output can represent anyway.
+const KAISER_BETA: f64 = 8.6;
+// How many points of the Kaiser window to calculate between its centre and
+// its edge. Evaluating the Bessel function for every tap is far too slow for
+// long sounds, so we look the window up in a table and interpolate instead.
+const WINDOW_TABLE_SIZE: usize = 4096;
+// How much of the lower of the two Nyquist frequencies to keep. Leaving a bit
+// of room lets the filter roll off before the frequencies would alias.
+const CUTOFF: f64 = 0.95;
+
+/// Resamples `samples` from `from` Hz to `to` Hz
+pub(crate) fn resample(samples: &[f64], from: u32, to: u32) -> Vec<f64> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = f64::from(to) / f64::from(from);
+    // when making the sound slower, we need to remove the frequencies that
+    // can't be represented at the new rate
+    let cutoff = ratio.min(1.0) * CUTOFF;
+    let half_width = ZERO_CROSSINGS / cutoff;
+    let window = KaiserWindow::new();
+
+    let output_length = (samples.len() as f64 * ratio).ceil() as usize;
+
+    (0..output_length)
+        .map(|n| {
+            let centre = n as f64 / ratio;
+
+            let first = (centre - half_width).ceil().max(0.0) as usize;
+            let last = ((centre + half_width).floor() as usize).min(samples.len() - 1);
+
+            (first..=last)
+                .map(|k| {
+                    let x = centre - k as f64;
+                    samples[k] * cutoff * sinc(cutoff * x) * window.at(x / half_width)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// The Kaiser window from its centre to its edge, which is symmetric so
+/// covers both sides.
+struct KaiserWindow(Vec<f64>);
+
+impl KaiserWindow {
+    fn new() -> Self {
+        let scale = bessel_i0(KAISER_BETA);
+
+        Self(
+            (0..=WINDOW_TABLE_SIZE)
+                .map(|i| {
+                    let x = i as f64 / WINDOW_TABLE_SIZE as f64;
+                    bessel_i0(KAISER_BETA * (1.0 - x * x).sqrt()) / scale
+                })
+                .collect(),
+        )
+    }
+
+    /// The window at `x`, where the window covers -1 to 1
+    fn at(&self, x: f64) -> f64 {
+        let position = x.abs() * WINDOW_TABLE_SIZE as f64;
+        let index = position as usize;
+        if index >= WINDOW_TABLE_SIZE {
+            return self.0[WINDOW_TABLE_SIZE];
+        }
+
+        let fraction = position - index as f64;
+        self.0[index] * (1.0 - fraction) + self.0[index + 1] * fraction
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+// The zeroth order modified Bessel function of the first kind, needed for the
+// Kaiser window. The series converges quickly for the values we use.
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half_x = x / 2.0;
+
+    for k in 1..50 {
+        term *= (half_x / k as f64).powi(2);
+        sum += term;
+
+        if term < sum * 1e-12 {
+            break;
+        }
+    }
+
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f64, sample_rate: u32, length: usize) -> Vec<f64> {
+        (0..length)
+            .map(|n| (2.0 * PI * frequency * n as f64 / f64::from(sample_rate)).sin() * 0.5)
+            .collect()
+    }
+
+    fn amplitude(samples: &[f64]) -> f64 {
+        // ignore the ends where the filter runs off the edge of the sound
+        let middle = &samples[samples.len() / 4..samples.len() * 3 / 4];
+        middle.iter().fold(0.0, |max, x| x.abs().max(max))
+    }
+
+    #[test]
+    fn keeps_frequencies_both_rates_can_represent() {
+        let input = sine(440.0, 44100, 44100);
+        let output = resample(&input, 44100, 18157);
+
+        assert_eq!(output.len(), 18157);
+        assert!((amplitude(&output) - 0.5).abs() < 0.01);
+
+        let expected = sine(440.0, 18157, 18157);
+        let middle = 4000..14000;
+        for (actual, expected) in output[middle.clone()].iter().zip(&expected[middle]) {
+            assert!((actual - expected).abs() < 0.01, "{actual} != {expected}");
+        }
+    }
+
+    #[test]
+    fn removes_frequencies_which_would_alias() {
+        // above the 9078Hz nyquist frequency of the output
+        let input = sine(12000.0, 44100, 44100);
+        let output = resample(&input, 44100, 18157);
+
+        assert!(amplitude(&output) < 0.01);
+    }
+
+    #[test]
+    fn window_table_matches_kaiser_window() {
+        let window = KaiserWindow::new();
+        let scale = bessel_i0(KAISER_BETA);
+
+        for i in 0..=1000 {
+            let x = f64::from(i) / 1000.0 * 2.0 - 1.0;
+            let expected = bessel_i0(KAISER_BETA * (1.0 - x * x).sqrt()) / scale;
+
+            assert!((window.at(x) - expected).abs() < 1e-6, "{x}");
+        }
+    }
+
+    #[test]
+    fn upsamples() {
+        let input = sine(1000.0, 10512, 10512);
+        let output = resample(&input, 10512, 32768);
+
+        assert_eq!(output.len(), 32768);
+        assert!((amplitude(&output) - 0.5).abs() < 0.01);
+    }
+}
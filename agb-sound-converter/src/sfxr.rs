@@ -0,0 +1,563 @@
+//! Synthesises sound effects from sfxr parameter files. This follows the
+//! original sfxr synthesiser so that sounds match what the editors play.
+//!
+//! Two formats are supported: the JSON files which jsfxr saves, and the binary
+//! `.sfs` files which the original sfxr saves.
+
+use std::f64::consts::PI;
+
+use serde::Deserialize;
+
+/// The sample rate sfxr generates sounds at. All the time based parameters are
+/// in terms of samples at this rate.
+pub(crate) const SAMPLE_RATE: u32 = 44100;
+
+// sfxr steps the waveform 8 times for each sample it outputs
+const OVERSAMPLING: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WaveType {
+    Square,
+    Sawtooth,
+    Sine,
+    Noise,
+}
+
+impl WaveType {
+    fn from_index(index: i32) -> Result<Self, String> {
+        match index {
+            0 => Ok(Self::Square),
+            1 => Ok(Self::Sawtooth),
+            2 => Ok(Self::Sine),
+            3 => Ok(Self::Noise),
+            _ => Err(format!(
+                "Unsupported wave type {index}, expected square (0), sawtooth (1), sine (2) or noise (3)"
+            )),
+        }
+    }
+}
+
+/// The sfxr parameters. These use the same names as the jsfxr JSON files, and
+/// default to what jsfxr uses when a parameter is missing.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub(crate) struct Parameters {
+    wave_type: i32,
+
+    p_env_attack: f64,
+    p_env_sustain: f64,
+    p_env_punch: f64,
+    p_env_decay: f64,
+
+    p_base_freq: f64,
+    p_freq_limit: f64,
+    p_freq_ramp: f64,
+    p_freq_dramp: f64,
+
+    p_vib_strength: f64,
+    p_vib_speed: f64,
+
+    p_arp_mod: f64,
+    p_arp_speed: f64,
+
+    p_duty: f64,
+    p_duty_ramp: f64,
+
+    p_repeat_speed: f64,
+
+    p_pha_offset: f64,
+    p_pha_ramp: f64,
+
+    p_lpf_freq: f64,
+    p_lpf_ramp: f64,
+    p_lpf_resonance: f64,
+    p_hpf_freq: f64,
+    p_hpf_ramp: f64,
+
+    sound_vol: f64,
+
+    /// Whether this came from the original sfxr, which is quieter than jsfxr
+    #[serde(skip)]
+    original: bool,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self {
+            wave_type: 0,
+
+            p_env_attack: 0.0,
+            p_env_sustain: 0.3,
+            p_env_punch: 0.0,
+            p_env_decay: 0.4,
+
+            p_base_freq: 0.3,
+            p_freq_limit: 0.0,
+            p_freq_ramp: 0.0,
+            p_freq_dramp: 0.0,
+
+            p_vib_strength: 0.0,
+            p_vib_speed: 0.0,
+
+            p_arp_mod: 0.0,
+            p_arp_speed: 0.0,
+
+            p_duty: 0.0,
+            p_duty_ramp: 0.0,
+
+            p_repeat_speed: 0.0,
+
+            p_pha_offset: 0.0,
+            p_pha_ramp: 0.0,
+
+            p_lpf_freq: 1.0,
+            p_lpf_ramp: 0.0,
+            p_lpf_resonance: 0.0,
+            p_hpf_freq: 0.0,
+            p_hpf_ramp: 0.0,
+
+            sound_vol: 0.5,
+
+            original: false,
+        }
+    }
+}
+
+impl Parameters {
+    /// Reads a jsfxr JSON file
+    pub(crate) fn from_json(data: &[u8]) -> Result<Self, String> {
+        let parameters: Self = serde_json::from_slice(data).map_err(|err| err.to_string())?;
+        WaveType::from_index(parameters.wave_type)?;
+
+        Ok(parameters)
+    }
+
+    /// Reads a `.sfs` file saved by the original sfxr
+    pub(crate) fn from_sfs(data: &[u8]) -> Result<Self, String> {
+        let mut reader = SfsReader { data };
+        let truncated = || "The file ends too early".to_string();
+
+        let version = reader.i32().ok_or_else(truncated)?;
+        if !(100..=102).contains(&version) {
+            return Err(format!(
+                "Unsupported sfxr file version {version}, expected 100, 101 or 102"
+            ));
+        }
+
+        let mut parameters = Self {
+            original: true,
+            ..Self::default()
+        };
+
+        let mut read = || -> Option<()> {
+            parameters.wave_type = reader.i32()?;
+            if version >= 102 {
+                parameters.sound_vol = reader.f32()?;
+            }
+
+            parameters.p_base_freq = reader.f32()?;
+            parameters.p_freq_limit = reader.f32()?;
+            parameters.p_freq_ramp = reader.f32()?;
+            if version >= 101 {
+                parameters.p_freq_dramp = reader.f32()?;
+            }
+            parameters.p_duty = reader.f32()?;
+            parameters.p_duty_ramp = reader.f32()?;
+
+            parameters.p_vib_strength = reader.f32()?;
+            parameters.p_vib_speed = reader.f32()?;
+            let _vibrato_delay = reader.f32()?;
+
+            parameters.p_env_attack = reader.f32()?;
+            parameters.p_env_sustain = reader.f32()?;
+            parameters.p_env_decay = reader.f32()?;
+            parameters.p_env_punch = reader.f32()?;
+
+            let _filter_on = reader.bytes(1)?;
+            parameters.p_lpf_resonance = reader.f32()?;
+            parameters.p_lpf_freq = reader.f32()?;
+            parameters.p_lpf_ramp = reader.f32()?;
+            parameters.p_hpf_freq = reader.f32()?;
+            parameters.p_hpf_ramp = reader.f32()?;
+
+            parameters.p_pha_offset = reader.f32()?;
+            parameters.p_pha_ramp = reader.f32()?;
+
+            parameters.p_repeat_speed = reader.f32()?;
+
+            if version >= 101 {
+                parameters.p_arp_speed = reader.f32()?;
+                parameters.p_arp_mod = reader.f32()?;
+            }
+
+            Some(())
+        };
+
+        read().ok_or_else(truncated)?;
+        WaveType::from_index(parameters.wave_type)?;
+
+        Ok(parameters)
+    }
+
+    /// How much to scale the output by. The original sfxr scales its output
+    /// linearly and exports it 4 times louder than it plays, jsfxr uses a
+    /// curve instead.
+    fn gain(&self) -> f64 {
+        if self.original {
+            0.05 * 2.0 * self.sound_vol * 4.0
+        } else {
+            self.sound_vol.exp() - 1.0
+        }
+    }
+}
+
+struct SfsReader<'a> {
+    data: &'a [u8],
+}
+
+impl SfsReader<'_> {
+    fn bytes(&mut self, count: usize) -> Option<&[u8]> {
+        let (bytes, rest) = self.data.split_at_checked(count)?;
+        self.data = rest;
+        Some(bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Option<f64> {
+        Some(f64::from(f32::from_le_bytes(
+            self.bytes(4)?.try_into().unwrap(),
+        )))
+    }
+}
+
+/// The noise needs to be the same every time the sound is built, so rather than
+/// using a random number generator we use a fixed xorshift sequence
+struct Noise(u32);
+
+impl Noise {
+    /// A random number between -1 and 1
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+
+        f64::from(self.0) / f64::from(u32::MAX) * 2.0 - 1.0
+    }
+}
+
+/// The state of the synthesiser which gets reset when the sound repeats
+struct Oscillator {
+    period: f64,
+    max_period: f64,
+    slide: f64,
+    delta_slide: f64,
+    square_duty: f64,
+    square_slide: f64,
+    arpeggio_multiplier: f64,
+    arpeggio_time: usize,
+    arpeggio_limit: usize,
+}
+
+impl Oscillator {
+    fn new(parameters: &Parameters) -> Self {
+        let arpeggio_multiplier = if parameters.p_arp_mod >= 0.0 {
+            1.0 - parameters.p_arp_mod.powi(2) * 0.9
+        } else {
+            1.0 + parameters.p_arp_mod.powi(2) * 10.0
+        };
+
+        let arpeggio_limit = if parameters.p_arp_speed == 1.0 {
+            0
+        } else {
+            ((1.0 - parameters.p_arp_speed).powi(2) * 20000.0 + 32.0) as usize
+        };
+
+        Self {
+            period: 100.0 / (parameters.p_base_freq.powi(2) + 0.001),
+            max_period: 100.0 / (parameters.p_freq_limit.powi(2) + 0.001),
+            slide: 1.0 - parameters.p_freq_ramp.powi(3) * 0.01,
+            delta_slide: -parameters.p_freq_dramp.powi(3) * 0.000_001,
+            square_duty: 0.5 - parameters.p_duty * 0.5,
+            square_slide: -parameters.p_duty_ramp * 0.000_05,
+            arpeggio_multiplier,
+            arpeggio_time: 0,
+            arpeggio_limit,
+        }
+    }
+}
+
+/// Generates the sound described by `parameters` at [`SAMPLE_RATE`], with
+/// samples between -1 and 1
+pub(crate) fn synthesise(parameters: &Parameters) -> Result<Vec<f64>, String> {
+    let p = parameters;
+    let wave_type = WaveType::from_index(p.wave_type)?;
+
+    let mut oscillator = Oscillator::new(p);
+    let mut noise = Noise(0x2545_f491);
+
+    let mut phase = 0;
+    let mut noise_buffer = [0.0; 32];
+    noise_buffer.fill_with(|| noise.next());
+
+    // the low pass filter
+    let mut lpf_position: f64 = 0.0;
+    let mut lpf_delta: f64 = 0.0;
+    let mut lpf_cutoff = p.p_lpf_freq.powi(3) * 0.1;
+    let lpf_cutoff_ramp = 1.0 + p.p_lpf_ramp * 0.0001;
+    let lpf_damping =
+        (5.0 / (1.0 + p.p_lpf_resonance.powi(2) * 20.0) * (0.01 + lpf_cutoff)).min(0.8);
+
+    // the high pass filter
+    let mut hpf_position: f64 = 0.0;
+    let mut hpf_cutoff: f64 = p.p_hpf_freq.powi(2) * 0.1;
+    let hpf_cutoff_ramp = 1.0 + p.p_hpf_ramp * 0.0003;
+
+    let mut vibrato_phase: f64 = 0.0;
+    let vibrato_speed = p.p_vib_speed.powi(2) * 0.01;
+    let vibrato_amplitude = p.p_vib_strength * 0.5;
+
+    // sfxr divides by these, so make sure none of them are 0
+    let envelope_lengths = [p.p_env_attack, p.p_env_sustain, p.p_env_decay]
+        .map(|length| ((length * length * 100_000.0) as usize).max(1));
+    let mut envelope_stage = 0;
+    let mut envelope_time = 0;
+
+    let mut phaser_offset = p.p_pha_offset.powi(2).copysign(p.p_pha_offset) * 1020.0;
+    let phaser_ramp = p.p_pha_ramp.powi(2).copysign(p.p_pha_ramp);
+    let mut phaser_buffer = [0.0; 1024];
+    let mut phaser_position = 0;
+
+    let repeat_limit = if p.p_repeat_speed == 0.0 {
+        0
+    } else {
+        ((1.0 - p.p_repeat_speed).powi(2) * 20000.0 + 32.0) as usize
+    };
+    let mut repeat_time = 0;
+
+    let gain = p.gain();
+    let mut output = vec![];
+
+    loop {
+        repeat_time += 1;
+        if repeat_limit != 0 && repeat_time >= repeat_limit {
+            repeat_time = 0;
+            oscillator = Oscillator::new(p);
+        }
+
+        oscillator.arpeggio_time += 1;
+        if oscillator.arpeggio_limit != 0 && oscillator.arpeggio_time >= oscillator.arpeggio_limit {
+            oscillator.arpeggio_limit = 0;
+            oscillator.period *= oscillator.arpeggio_multiplier;
+        }
+
+        oscillator.slide += oscillator.delta_slide;
+        oscillator.period *= oscillator.slide;
+        if oscillator.period > oscillator.max_period {
+            oscillator.period = oscillator.max_period;
+            if p.p_freq_limit > 0.0 {
+                break;
+            }
+        }
+
+        let mut period = oscillator.period;
+        if vibrato_amplitude > 0.0 {
+            vibrato_phase += vibrato_speed;
+            period *= 1.0 + vibrato_phase.sin() * vibrato_amplitude;
+        }
+        let period = (period as usize).max(8);
+
+        oscillator.square_duty = (oscillator.square_duty + oscillator.square_slide).clamp(0.0, 0.5);
+
+        envelope_time += 1;
+        if envelope_time > envelope_lengths[envelope_stage] {
+            envelope_time = 0;
+            envelope_stage += 1;
+            if envelope_stage == 3 {
+                break;
+            }
+        }
+
+        let envelope_progress = envelope_time as f64 / envelope_lengths[envelope_stage] as f64;
+        let envelope_volume = match envelope_stage {
+            0 => envelope_progress,
+            1 => 1.0 + (1.0 - envelope_progress) * 2.0 * p.p_env_punch,
+            _ => 1.0 - envelope_progress,
+        };
+
+        phaser_offset += phaser_ramp;
+        let phaser_delay = (phaser_offset as i32).unsigned_abs().min(1023) as usize;
+
+        hpf_cutoff = (hpf_cutoff * hpf_cutoff_ramp).clamp(0.00001, 0.1);
+
+        let mut total = 0.0;
+        for _ in 0..OVERSAMPLING {
+            phase += 1;
+            if phase >= period {
+                phase %= period;
+                if wave_type == WaveType::Noise {
+                    noise_buffer.fill_with(|| noise.next());
+                }
+            }
+
+            let position = phase as f64 / period as f64;
+            let sample = match wave_type {
+                WaveType::Square => {
+                    if position < oscillator.square_duty {
+                        0.5
+                    } else {
+                        -0.5
+                    }
+                }
+                WaveType::Sawtooth => 1.0 - position * 2.0,
+                WaveType::Sine => (position * 2.0 * PI).sin(),
+                WaveType::Noise => noise_buffer[phase * 32 / period],
+            };
+
+            let previous_lpf_position = lpf_position;
+            lpf_cutoff = (lpf_cutoff * lpf_cutoff_ramp).clamp(0.0, 0.1);
+            if p.p_lpf_freq == 1.0 {
+                lpf_position = sample;
+                lpf_delta = 0.0;
+            } else {
+                lpf_delta += (sample - lpf_position) * lpf_cutoff;
+                lpf_delta -= lpf_delta * lpf_damping;
+            }
+            lpf_position += lpf_delta;
+
+            hpf_position += lpf_position - previous_lpf_position;
+            hpf_position -= hpf_position * hpf_cutoff;
+            let mut sample = hpf_position;
+
+            phaser_buffer[phaser_position & 1023] = sample;
+            sample += phaser_buffer[(phaser_position + 1024 - phaser_delay) & 1023];
+            phaser_position = (phaser_position + 1) & 1023;
+
+            total += sample * envelope_volume;
+        }
+
+        output.push((total / OVERSAMPLING as f64 * gain).clamp(-1.0, 1.0));
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_jsfxr_json() {
+        let parameters = Parameters::from_json(
+            br#"{
+                "oldParams": true,
+                "wave_type": 2,
+                "p_env_sustain": 0.1,
+                "p_base_freq": 0.5,
+                "sound_vol": 0.25,
+                "sample_rate": 44100,
+                "sample_size": 8
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parameters,
+            Parameters {
+                wave_type: 2,
+                p_env_sustain: 0.1,
+                p_base_freq: 0.5,
+                sound_vol: 0.25,
+                ..Parameters::default()
+            }
+        );
+
+        assert!(Parameters::from_json(br#"{ "wave_type": 7 }"#).is_err());
+        assert!(Parameters::from_json(b"not json").is_err());
+    }
+
+    #[test]
+    fn reads_sfs_files() {
+        let mut file = vec![];
+        file.extend(102i32.to_le_bytes());
+        file.extend(3i32.to_le_bytes());
+        // volume, then the frequency parameters
+        for value in [0.4f32, 0.6, 0.0, -0.25, 0.0] {
+            file.extend(value.to_le_bytes());
+        }
+        // duty, vibrato and the envelope
+        for value in [0.0f32, 0.0, 0.0, 0.0, 0.0, 0.0, 0.25, 0.375, 0.5] {
+            file.extend(value.to_le_bytes());
+        }
+        file.push(0);
+        // filters, phaser, repeat and arpeggio
+        for value in [0.0f32, 1.0, 0.0, 0.125, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0] {
+            file.extend(value.to_le_bytes());
+        }
+
+        let parameters = Parameters::from_sfs(&file).unwrap();
+        assert_eq!(parameters.wave_type, 3);
+        assert_eq!(parameters.p_freq_ramp, -0.25);
+        assert_eq!(parameters.p_env_sustain, 0.25);
+        assert_eq!(parameters.p_env_punch, 0.5);
+        assert_eq!(parameters.p_hpf_freq, 0.125);
+        assert!(parameters.original);
+
+        assert!(Parameters::from_sfs(&file[..file.len() - 1]).is_err());
+        assert!(Parameters::from_sfs(&99i32.to_le_bytes()).is_err());
+    }
+
+    #[test]
+    fn lasts_as_long_as_the_envelope() {
+        let parameters = Parameters {
+            p_env_attack: 0.125,
+            p_env_sustain: 0.25,
+            p_env_decay: 0.5,
+            ..Parameters::default()
+        };
+
+        let samples = synthesise(&parameters).unwrap();
+        // each stage lasts length^2 * 100000 samples, plus one for moving on
+        assert_eq!(samples.len(), 1562 + 6250 + 25000 + 2);
+
+        // the attack fades in
+        assert!(samples[..20].iter().all(|sample| sample.abs() < 0.01));
+        assert!(samples.iter().any(|sample| sample.abs() > 0.1));
+        assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+    }
+
+    #[test]
+    fn square_waves_have_the_base_frequency() {
+        let parameters = Parameters {
+            p_env_sustain: 0.5,
+            p_env_decay: 0.0,
+            ..Parameters::default()
+        };
+
+        let samples = synthesise(&parameters).unwrap();
+
+        // the period is in oversampled steps
+        let period = (100.0 / (0.3f64.powi(2) + 0.001)) as usize;
+        let expected = (samples.len() * OVERSAMPLING / period) as i32;
+
+        let rising_edges = samples
+            .windows(2)
+            .filter(|pair| pair[0] <= 0.0 && pair[1] > 0.0)
+            .count() as i32;
+        assert!((rising_edges - expected).abs() <= 1, "{rising_edges}");
+    }
+
+    #[test]
+    fn noise_is_the_same_every_time() {
+        let parameters = Parameters {
+            wave_type: 3,
+            ..Parameters::default()
+        };
+
+        let first = synthesise(&parameters).unwrap();
+        assert_eq!(first, synthesise(&parameters).unwrap());
+        assert!(first.iter().any(|sample| sample.abs() > 0.05));
+    }
+}
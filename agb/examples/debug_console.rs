@@ -0,0 +1,47 @@
+//! Shows a log of the buttons you press on top of the game. Press L and R
+//! together to show or hide it.
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+
+use agb::{
+    display::{DebugConsole, Priority, Rgb15, tiled::VRAM_MANAGER},
+    input::{Button, ButtonController},
+};
+
+#[agb::entry]
+fn main(mut gba: agb::Gba) -> ! {
+    let mut gfx = gba.graphics.get();
+    let mut input = ButtonController::new();
+
+    VRAM_MANAGER.set_background_palette_colour(0, 0, Rgb15(0x4a_52));
+
+    let mut console = DebugConsole::new(Priority::P0, 15).with_toggle(Button::L | Button::R);
+    // consoles with a toggle start hidden
+    console.set_visible(true);
+    writeln!(console, "Press L and R together to hide this").unwrap();
+
+    let mut frame_count: u32 = 0;
+
+    loop {
+        input.update();
+        console.update(&input);
+        frame_count += 1;
+
+        for (button, name) in [
+            (Button::A, "A"),
+            (Button::B, "B"),
+            (Button::Start, "start"),
+            (Button::Select, "select"),
+        ] {
+            if input.is_just_pressed(button) {
+                writeln!(console, "frame {frame_count}: pressed {name}").unwrap();
+            }
+        }
+
+        let mut frame = gfx.frame();
+        console.show(&mut frame);
+        frame.commit();
+    }
+}
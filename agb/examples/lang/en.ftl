@@ -0,0 +1,12 @@
+# English is the reference language, so every string must be translated here.
+
+-game-name = Crab Quest
+
+title = { -game-name }
+greeting = Hello, { $name }!
+coins = { $count ->
+    [0] You have no coins.
+    [one] You have { COLOUR(2) }one{ COLOUR(1) } coin.
+   *[other] You have { COLOUR(2) }{ $count }{ COLOUR(1) } coins.
+}
+quit = Quit
@@ -0,0 +1,9 @@
+-game-name = La Quête du Crabe
+
+title = { -game-name }
+greeting = Bonjour, { $name } !
+coins = { $count ->
+    [0] Vous n'avez aucune pièce.
+    [one] Vous avez { COLOUR(2) }{ $count }{ COLOUR(1) } pièce.
+   *[other] Vous avez { COLOUR(2) }{ $count }{ COLOUR(1) } pièces.
+}
@@ -0,0 +1,9 @@
+-game-name = カニクエスト
+
+title = { -game-name }
+greeting = こんにちは、{ $name }さん！
+coins = { $count ->
+    [0] コインがありません。
+   *[other] コインが{ COLOUR(2) }{ $count }{ COLOUR(1) }こあります。
+}
+quit = やめる
@@ -0,0 +1,107 @@
+//! A dialogue box which types out its text, waits for you to press A and
+//! finishes with a choice.
+#![no_std]
+#![no_main]
+
+use agb::{
+    display::{
+        Palette16, Priority, Rgb, Rgb15,
+        font::{
+            BackgroundTextBoxRenderer, Font, Layout, LayoutSettings, ObjectTextRenderer,
+            RegularBackgroundTextRenderer, TextBox, TextBoxSettings, TextBoxState,
+        },
+        object::{Object, Size},
+        tiled::{RegularBackground, RegularBackgroundSize, TileFormat, VRAM_MANAGER},
+    },
+    fixnum::vec2,
+    include_font,
+    input::ButtonController,
+};
+
+static FONT: Font = include_font!("examples/font/ark-pixel-10px-proportional-ja.ttf", 10);
+
+static PALETTE: Palette16 = const {
+    let mut palette = [Rgb15::BLACK; 16];
+    palette[1] = Rgb15::WHITE;
+    palette[2] = Rgb15(0x10_7C);
+    Palette16::new(palette)
+};
+
+const TEXT_BOX_POSITION: (i32, i32) = (16, 104);
+
+#[agb::entry]
+fn main(mut gba: agb::Gba) -> ! {
+    let mut gfx = gba.graphics.get();
+    let mut input = ButtonController::new();
+
+    VRAM_MANAGER.set_background_palette(0, &PALETTE);
+    VRAM_MANAGER.set_background_palette_colour(0, 0, Rgb::new(0, 97, 132).into());
+
+    let background = RegularBackground::new(
+        Priority::P0,
+        RegularBackgroundSize::Background32x32,
+        TileFormat::FourBpp,
+    );
+    let renderer = BackgroundTextBoxRenderer::new(
+        background,
+        RegularBackgroundTextRenderer::new(TEXT_BOX_POSITION, 0),
+    );
+
+    let settings = TextBoxSettings::new(208, 3)
+        .with_layout_settings(LayoutSettings::new().with_max_group_width(8));
+    let mut text_box = TextBox::new(renderer, &FONT, settings);
+
+    // the prompt and cursor are shown with objects on top of the text box
+    let symbols = ObjectTextRenderer::new((&PALETTE).into(), Size::S8x16);
+    let symbol = |text: &str| -> Object {
+        let group = Layout::new(text, &FONT, &LayoutSettings::new())
+            .next()
+            .expect("symbol should have letters");
+        symbols.show(&group, (0, 0))
+    };
+    let mut prompt = symbol("▼");
+    let mut cursor = symbol(">");
+
+    text_box.ask(
+        "Welcome, traveller! This box reveals its text a little at a time, and pauses at punctuation. \
+        When it is full, it waits for you to press A before continuing on the next page.\n\
+        Did you enjoy the demonstration?",
+        &["Yes, very much", "Show me again"],
+    );
+
+    let mut frame_count = 0;
+
+    loop {
+        input.update();
+        text_box.update(&input);
+        frame_count += 1;
+
+        if text_box.state() == TextBoxState::Finished {
+            match text_box.chosen() {
+                Some(0) => text_box.say("Thank you for watching!"),
+                _ => text_box.ask(
+                    "Here it is again, one more time. Did you enjoy it?",
+                    &["Yes, very much", "Show me again"],
+                ),
+            }
+        }
+
+        let mut frame = gfx.frame();
+        let offset = vec2(TEXT_BOX_POSITION.0, TEXT_BOX_POSITION.1);
+
+        if let Some(position) = text_box.prompt_position()
+            && (frame_count / 16) % 2 == 0
+        {
+            prompt.set_pos(offset + position + vec2(2, 0));
+            prompt.show(&mut frame);
+        }
+
+        if let Some(position) = text_box.cursor_position() {
+            cursor.set_pos(offset + position);
+            cursor.show(&mut frame);
+        }
+
+        text_box.renderer().show(&mut frame);
+        frame.commit();
+    }
+}
@@ -1,6 +1,8 @@
 #![warn(missing_docs)]
 use core::fmt::Debug;
 
+use crate::fixnum::Num;
+
 /// Represents a pixel on the GBA.
 ///
 /// This is stored as a 15 bit number as `0b0bbbbbgggggrrrrr`. You can see what would happen to your true-colour
@@ -21,6 +23,249 @@ impl Rgb15 {
     pub const BLACK: Rgb15 = Rgb::new(0, 0, 0).to_rgb15();
     /// A white Rgb15 value
     pub const WHITE: Rgb15 = Rgb::new(255, 255, 255).to_rgb15();
+
+    /// Creates a colour from its red, green and blue components, each of which
+    /// must be between 0 and 31 inclusive.
+    ///
+    /// # Panics
+    ///
+    /// If any of the components are larger than 31.
+    #[must_use]
+    pub const fn from_components(r: u8, g: u8, b: u8) -> Self {
+        assert!(
+            r < 32 && g < 32 && b < 32,
+            "components must be less than 32"
+        );
+        Self(r as u16 | ((g as u16) << 5) | ((b as u16) << 10))
+    }
+
+    /// The red component, between 0 and 31 inclusive
+    #[must_use]
+    pub const fn r(self) -> u8 {
+        (self.0 & 31) as u8
+    }
+
+    /// The green component, between 0 and 31 inclusive
+    #[must_use]
+    pub const fn g(self) -> u8 {
+        ((self.0 >> 5) & 31) as u8
+    }
+
+    /// The blue component, between 0 and 31 inclusive
+    #[must_use]
+    pub const fn b(self) -> u8 {
+        ((self.0 >> 10) & 31) as u8
+    }
+
+    /// Linearly interpolates between this colour and `other`. An `amount` of 0
+    /// gives this colour and 1 gives `other`, values outside that range are
+    /// clamped.
+    ///
+    /// ```rust
+    /// # #![no_std]
+    /// # #![no_main]
+    /// use agb::{display::Rgb15, fixnum::num};
+    ///
+    /// # #[agb::doctest]
+    /// # fn test(_gba: agb::Gba) {
+    /// let grey = Rgb15::BLACK.lerp(Rgb15::WHITE, num!(0.5));
+    /// assert_eq!(grey, Rgb15::from_components(16, 16, 16));
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn lerp(self, other: Self, amount: Num<i32, 8>) -> Self {
+        let amount = clamp_amount(amount);
+
+        Self::from_components(
+            lerp_component(self.r(), other.r(), amount),
+            lerp_component(self.g(), other.g(), amount),
+            lerp_component(self.b(), other.b(), amount),
+        )
+    }
+
+    /// Adds each component of `other` to this colour, saturating at white.
+    /// This is the same as additive blending.
+    #[must_use]
+    pub const fn blend_add(self, other: Self) -> Self {
+        Self::from_components(
+            add_component(self.r(), other.r()),
+            add_component(self.g(), other.g()),
+            add_component(self.b(), other.b()),
+        )
+    }
+
+    /// Multiplies each component of this colour by the component of `other`,
+    /// where 31 counts as 1. Multiplying by white leaves the colour unchanged
+    /// and multiplying by black gives black.
+    #[must_use]
+    pub const fn blend_multiply(self, other: Self) -> Self {
+        Self::from_components(
+            multiply_component(self.r(), other.r()),
+            multiply_component(self.g(), other.g()),
+            multiply_component(self.b(), other.b()),
+        )
+    }
+
+    /// Converts this colour to grey, weighting each component by how bright it
+    /// appears.
+    #[must_use]
+    pub const fn greyscale(self) -> Self {
+        let luma = (self.r() as u32 * 77 + self.g() as u32 * 150 + self.b() as u32 * 29 + 128) >> 8;
+        let luma = luma as u8;
+
+        Self::from_components(luma, luma, luma)
+    }
+
+    /// Moves this colour towards white by `amount`, where 1 gives white. See
+    /// [`lerp`](Self::lerp).
+    #[must_use]
+    pub const fn lighten(self, amount: Num<i32, 8>) -> Self {
+        self.lerp(Self::WHITE, amount)
+    }
+
+    /// Moves this colour towards black by `amount`, where 1 gives black. See
+    /// [`lerp`](Self::lerp).
+    #[must_use]
+    pub const fn darken(self, amount: Num<i32, 8>) -> Self {
+        self.lerp(Self::BLACK, amount)
+    }
+
+    /// Converts this colour to hue, saturation and value
+    #[must_use]
+    pub const fn to_hsv(self) -> Hsv {
+        let (r, g, b) = (self.r() as i32, self.g() as i32, self.b() as i32);
+
+        let max = max(r, max(g, b));
+        let min = min(r, min(g, b));
+        let delta = max - min;
+
+        let v = (max * 255 + 15) / 31;
+        if delta == 0 {
+            return Hsv::new(0, 0, v as u8);
+        }
+
+        let s = (delta * 255 + max / 2) / max;
+
+        // each sixth of the hue circle covers 256 / 6 values
+        let sector = if max == r {
+            (g - b) * 256 / (6 * delta)
+        } else if max == g {
+            256 / 3 + (b - r) * 256 / (6 * delta)
+        } else {
+            512 / 3 + (r - g) * 256 / (6 * delta)
+        };
+
+        Hsv::new(sector.rem_euclid(256) as u8, s as u8, v as u8)
+    }
+
+    /// Creates a colour from hue, saturation and value
+    #[must_use]
+    pub const fn from_hsv(hsv: Hsv) -> Self {
+        let v = hsv.v as i32;
+        let s = hsv.s as i32;
+
+        // position within the hue circle in units of 1/6th, with 8 fractional bits
+        let h = hsv.h as i32 * 6;
+        let sector = h >> 8;
+        let fraction = h & 0xff;
+
+        let p = v * (255 - s) / 255;
+        let q = v * (255 * 256 - s * fraction) / (255 * 256);
+        let t = v * (255 * 256 - s * (256 - fraction)) / (255 * 256);
+
+        let (r, g, b) = match sector {
+            0 => (v, t, p),
+            1 => (q, v, p),
+            2 => (p, v, t),
+            3 => (p, q, v),
+            4 => (t, p, v),
+            _ => (v, p, q),
+        };
+
+        Self::from_components(to_component(r), to_component(g), to_component(b))
+    }
+
+    /// Rotates the hue of this colour by `amount`, where 256 is a full turn
+    #[must_use]
+    pub const fn hue_shift(self, amount: u8) -> Self {
+        let hsv = self.to_hsv();
+        Self::from_hsv(Hsv::new(hsv.h.wrapping_add(amount), hsv.s, hsv.v))
+    }
+}
+
+const fn clamp_amount(amount: Num<i32, 8>) -> i32 {
+    let amount = amount.to_raw();
+    if amount < 0 {
+        0
+    } else if amount > 256 {
+        256
+    } else {
+        amount
+    }
+}
+
+const fn lerp_component(from: u8, to: u8, amount: i32) -> u8 {
+    let (from, to) = (from as i32, to as i32);
+    (from + ((to - from) * amount + 128).div_euclid(256)) as u8
+}
+
+const fn add_component(a: u8, b: u8) -> u8 {
+    let sum = a + b;
+    if sum > 31 { 31 } else { sum }
+}
+
+const fn multiply_component(a: u8, b: u8) -> u8 {
+    ((a as u32 * b as u32 + 15) / 31) as u8
+}
+
+const fn to_component(value: i32) -> u8 {
+    ((value * 31 + 127) / 255) as u8
+}
+
+const fn max(a: i32, b: i32) -> i32 {
+    if a > b { a } else { b }
+}
+
+const fn min(a: i32, b: i32) -> i32 {
+    if a < b { a } else { b }
+}
+
+/// A colour as hue, saturation and value, each stored as a `u8`.
+///
+/// The hue goes once round the colour wheel from 0 to 256, starting at red,
+/// so it wraps around naturally when you add to it. Saturation and value go
+/// from 0 to 255.
+///
+/// Converting between [`Rgb15`] and [`Hsv`] is lossy, since the GBA only has
+/// 32 levels for each of red, green and blue.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Hsv {
+    /// The hue, where 0 is red, 85 is green and 171 is blue
+    pub h: u8,
+    /// The saturation, where 0 is grey and 255 is fully saturated
+    pub s: u8,
+    /// The value, where 0 is black and 255 is fully bright
+    pub v: u8,
+}
+
+impl Hsv {
+    /// Creates a new Hsv value with given hue, saturation and value
+    #[must_use]
+    pub const fn new(h: u8, s: u8, v: u8) -> Self {
+        Self { h, s, v }
+    }
+}
+
+impl From<Rgb15> for Hsv {
+    fn from(value: Rgb15) -> Self {
+        value.to_hsv()
+    }
+}
+
+impl From<Hsv> for Rgb15 {
+    fn from(value: Hsv) -> Self {
+        Rgb15::from_hsv(value)
+    }
 }
 
 impl From<Rgb> for Rgb15 {
@@ -131,4 +376,59 @@ mod tests {
         let debug = format!("{:?}", Rgb::new(0x05, 0x02, 0x0b));
         assert_eq!(debug, "#05020b");
     }
+
+    #[test_case]
+    fn components_round_trip(_: &mut crate::Gba) {
+        let colour = Rgb15::from_components(3, 17, 31);
+        assert_eq!((colour.r(), colour.g(), colour.b()), (3, 17, 31));
+        assert_eq!(colour, Rgb::new(3 << 3, 17 << 3, 31 << 3).to_rgb15());
+    }
+
+    #[test_case]
+    fn lerp_end_points(_: &mut crate::Gba) {
+        let a = Rgb15::from_components(31, 0, 10);
+        let b = Rgb15::from_components(0, 31, 20);
+
+        assert_eq!(a.lerp(b, Num::new(0)), a);
+        assert_eq!(a.lerp(b, Num::new(1)), b);
+        assert_eq!(a.lerp(b, Num::new(2)), b);
+        assert_eq!(
+            a.lerp(b, Num::from_raw(128)),
+            Rgb15::from_components(16, 16, 15)
+        );
+    }
+
+    #[test_case]
+    fn blending(_: &mut crate::Gba) {
+        let colour = Rgb15::from_components(20, 10, 0);
+
+        assert_eq!(colour.blend_add(colour), Rgb15::from_components(31, 20, 0));
+        assert_eq!(colour.blend_multiply(Rgb15::WHITE), colour);
+        assert_eq!(colour.blend_multiply(Rgb15::BLACK), Rgb15::BLACK);
+        assert_eq!(Rgb15::WHITE.greyscale(), Rgb15::WHITE);
+    }
+
+    #[test_case]
+    fn hsv_round_trip(_: &mut crate::Gba) {
+        for value in (0..0x8000).step_by(37) {
+            let colour = Rgb15(value);
+            let round_tripped = Rgb15::from_hsv(colour.to_hsv());
+
+            assert!(
+                colour.r().abs_diff(round_tripped.r()) <= 1
+                    && colour.g().abs_diff(round_tripped.g()) <= 1
+                    && colour.b().abs_diff(round_tripped.b()) <= 1,
+                "{colour:?} became {round_tripped:?}"
+            );
+        }
+
+        assert_eq!(
+            Rgb15::from_components(31, 0, 0).to_hsv(),
+            Hsv::new(0, 255, 255)
+        );
+        assert_eq!(
+            Rgb15::from_components(31, 0, 0).hue_shift(85),
+            Rgb15::from_components(0, 31, 0)
+        );
+    }
 }
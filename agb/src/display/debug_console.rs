@@ -0,0 +1,310 @@
+use alloc::{collections::VecDeque, string::String};
+
+use crate::{
+    display::{
+        GraphicsFrame, HEIGHT, Palette16, Priority, Rgb15, WIDTH,
+        font::{Font, Layout, LayoutSettings, RegularBackgroundTextRenderer},
+        tiled::{RegularBackground, RegularBackgroundSize, TileFormat, VRAM_MANAGER},
+    },
+    input::{ButtonController, ButtonState},
+};
+
+static FONT: Font = include_font!("fnt/ark-pixel-10px-proportional-latin.ttf", 10);
+
+static PALETTE: Palette16 = const {
+    let mut palette = [Rgb15::BLACK; 16];
+    palette[1] = Rgb15::WHITE;
+    Palette16::new(palette)
+};
+
+// Space left around the edge of the screen
+const MARGIN: i32 = 4;
+
+const LAYOUT_SETTINGS: LayoutSettings = LayoutSettings::new()
+    .with_max_line_length(WIDTH - 2 * MARGIN)
+    .with_max_group_width(32)
+    .with_drop_shadow(2);
+
+/// A scrolling log drawn on its own background with a built-in font, so that
+/// you can see debug output on real hardware where
+/// [`println!`](crate::println!) goes nowhere.
+///
+/// Write to it with [`write!`] and [`writeln!`]. Once the screen is full, the
+/// oldest lines scroll off the top.
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use core::fmt::Write;
+///
+/// use agb::{
+///     display::{DebugConsole, Priority},
+///     input::{Button, ButtonController},
+/// };
+///
+/// # #[agb::doctest]
+/// # fn test(mut gba: agb::Gba) {
+/// let mut gfx = gba.graphics.get();
+/// let mut input = ButtonController::new();
+///
+/// // shown and hidden by pressing L, R and select together
+/// let mut console = DebugConsole::new(Priority::P0, 15)
+///     .with_toggle(Button::L | Button::R | Button::Select);
+///
+/// let health = 3;
+/// writeln!(console, "health is {health}").unwrap();
+///
+/// input.update();
+/// console.update(&input);
+///
+/// let mut frame = gfx.frame();
+/// console.show(&mut frame);
+/// frame.commit();
+/// # }
+/// ```
+pub struct DebugConsole {
+    background: RegularBackground,
+    renderer: RegularBackgroundTextRenderer,
+    lines: VecDeque<ConsoleLine>,
+    max_rows: usize,
+    toggle: Option<ButtonState>,
+    visible: bool,
+    dirty: bool,
+}
+
+struct ConsoleLine {
+    text: String,
+    // how many rows this line takes up once it is wrapped
+    rows: usize,
+}
+
+impl DebugConsole {
+    /// Creates a new console which draws on its own 16 colour background at
+    /// the given priority. The background palette at index `palette_id` is
+    /// replaced with the colours the console uses.
+    ///
+    /// The console takes up one of the backgrounds while it is shown, so you
+    /// can have at most 3 other regular backgrounds with it.
+    #[must_use]
+    pub fn new(priority: Priority, palette_id: u8) -> Self {
+        VRAM_MANAGER.set_background_palette(palette_id, &PALETTE);
+
+        let background = RegularBackground::new(
+            priority,
+            RegularBackgroundSize::Background32x32,
+            TileFormat::FourBpp,
+        );
+
+        let mut lines = VecDeque::new();
+        lines.push_back(ConsoleLine {
+            text: String::new(),
+            rows: 1,
+        });
+
+        Self {
+            background,
+            renderer: RegularBackgroundTextRenderer::new((MARGIN, MARGIN), palette_id),
+            lines,
+            max_rows: ((HEIGHT - 2 * MARGIN) / FONT.line_height()) as usize,
+            toggle: None,
+            visible: true,
+            dirty: false,
+        }
+    }
+
+    /// Only show the console once all the buttons in `combo` are pressed
+    /// together, and hide it again the next time they are. You need to call
+    /// [`update()`](Self::update) every frame for this to work.
+    ///
+    /// The console starts hidden, but still records everything written to it.
+    #[must_use]
+    pub fn with_toggle(mut self, combo: impl Into<ButtonState>) -> Self {
+        self.toggle = Some(combo.into());
+        self.visible = false;
+        self
+    }
+
+    /// Checks whether the toggle combo set with [`with_toggle()`](Self::with_toggle)
+    /// was just pressed, and if so shows or hides the console.
+    pub fn update(&mut self, input: &ButtonController) {
+        if let Some(combo) = self.toggle
+            && input.is_just_pressed(combo)
+            && !input.is_released(combo)
+        {
+            self.visible = !self.visible;
+        }
+    }
+
+    /// Whether the console will be drawn by [`show()`](Self::show)
+    #[must_use]
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the console
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Removes everything written to the console
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.lines.push_back(ConsoleLine {
+            text: String::new(),
+            rows: 1,
+        });
+        self.dirty = true;
+    }
+
+    /// Shows the console on the given frame if it is visible. Any text written
+    /// since the last call is drawn first, which redraws the whole console, so
+    /// avoid writing to it every frame if you can.
+    pub fn show(&mut self, frame: &mut GraphicsFrame<'_>) {
+        if !self.visible {
+            return;
+        }
+
+        if self.dirty {
+            self.redraw();
+        }
+
+        self.background.show(frame);
+    }
+
+    fn redraw(&mut self) {
+        self.dirty = false;
+        self.renderer.clear();
+
+        let mut text = String::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            text.push_str(&line.text);
+        }
+
+        for group in Layout::new(&text, &FONT, &LAYOUT_SETTINGS) {
+            self.renderer.show(&mut self.background, &group);
+        }
+    }
+
+    fn current_line(&mut self) -> &mut ConsoleLine {
+        self.lines
+            .back_mut()
+            .expect("console always has a line being written to")
+    }
+
+    // Removes lines from the top until everything fits on the screen
+    fn scroll(&mut self) {
+        let mut rows: usize = self.lines.iter().map(|line| line.rows).sum();
+
+        while rows > self.max_rows && self.lines.len() > 1 {
+            let removed = self.lines.pop_front().expect("checked there is a line");
+            rows -= removed.rows;
+        }
+    }
+}
+
+impl core::fmt::Write for DebugConsole {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for (i, part) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.lines.push_back(ConsoleLine {
+                    text: String::new(),
+                    rows: 1,
+                });
+            }
+
+            if !part.is_empty() {
+                let line = self.current_line();
+                line.text.push_str(part);
+                line.rows = Layout::measure(&line.text, &FONT, &LAYOUT_SETTINGS)
+                    .line_count()
+                    .max(1);
+            }
+        }
+
+        self.scroll();
+        self.dirty = true;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::fmt::Write;
+
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+    use crate::{Gba, input::Button};
+
+    fn lines(console: &DebugConsole) -> Vec<&str> {
+        console
+            .lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect()
+    }
+
+    #[test_case]
+    fn console_splits_writes_into_lines(_: &mut Gba) {
+        let mut console = DebugConsole::new(Priority::P0, 15);
+
+        write!(console, "Hello, ").unwrap();
+        writeln!(console, "world!").unwrap();
+        write!(console, "second\nthird").unwrap();
+
+        assert_eq!(lines(&console), ["Hello, world!", "second", "third"]);
+    }
+
+    #[test_case]
+    fn console_scrolls_old_lines_away(_: &mut Gba) {
+        let mut console = DebugConsole::new(Priority::P0, 15);
+        let max_rows = console.max_rows;
+
+        for i in 0..max_rows + 5 {
+            writeln!(console, "line {i}").unwrap();
+        }
+
+        let lines = lines(&console);
+        // the final empty line is the one being written to next
+        assert_eq!(lines.len(), max_rows);
+        assert_eq!(lines[0], alloc::format!("line {}", 6).as_str());
+        assert_eq!(lines[max_rows - 1], "");
+
+        // a long line wraps over multiple rows, pushing more lines away
+        writeln!(
+            console,
+            "this line is long enough that it has to be wrapped over more than one row of the screen"
+        )
+        .unwrap();
+        let rows: usize = console.lines.iter().map(|line| line.rows).sum();
+        assert!(rows <= max_rows);
+        assert!(console.lines.iter().any(|line| line.rows > 1));
+    }
+
+    #[test_case]
+    fn console_toggles_with_combo(_: &mut Gba) {
+        let mut console = DebugConsole::new(Priority::P0, 15).with_toggle(Button::L | Button::R);
+        let mut input = ButtonController::new();
+
+        let mut visibility = vec![];
+        for state in [
+            ButtonState::empty(),
+            Button::L.into(),
+            Button::L | Button::R,
+            Button::L | Button::R,
+            ButtonState::empty(),
+            Button::R.into(),
+            Button::R | Button::L,
+        ] {
+            input.update_with_state(state);
+            console.update(&input);
+            visibility.push(console.is_visible());
+        }
+
+        assert_eq!(visibility, [false, false, true, true, true, true, false]);
+    }
+}
@@ -10,8 +10,10 @@
 //! It is designed such that there are two phases to the rendering:
 //! * the [`Layout`] system which decides where groups of letters should be
 //!   rendered.
-//! * the [`ObjectTextRenderer`] and [`RegularBackgroundTextRenderer`] which
-//!   take those groups of letters and display them to their relevant targets.
+//! * the [`ObjectTextRenderer`], [`RegularBackgroundTextRenderer`],
+//!   [`RegularBackgroundTextRenderer256`] and [`AffineBackgroundTextRenderer`]
+//!   which take those groups of letters and display them to their relevant
+//!   targets.
 //!
 //! These two phases interact through the [`LetterGroup`] that the [`Layout`]
 //! generates.
@@ -149,16 +151,29 @@
 #![warn(missing_docs)]
 mod align;
 mod layout;
+pub mod localisation;
+mod markup;
 mod object;
 mod special;
+mod textbox;
 mod tiled;
 
 pub use align::AlignmentKind;
-pub use layout::{Layout, LayoutSettings, LetterGroup};
+pub use layout::{Layout, LayoutSettings, LetterGroup, TextMetrics};
 pub use object::ObjectTextRenderer;
-pub use tiled::RegularBackgroundTextRenderer;
+pub use textbox::{
+    BackgroundTextBoxRenderer, ObjectTextBoxRenderer, TextBox, TextBoxRenderer, TextBoxSettings,
+    TextBoxState,
+};
+pub use tiled::{
+    AffineBackgroundTextRenderer, RegularBackgroundTextRenderer, RegularBackgroundTextRenderer256,
+};
 
-pub use special::{ChangeColour, Tag};
+pub use special::{ChangeColour, ChangeSpeed, Icon, Tag, Wait};
+
+pub use markup::{MarkupError, parse_markup};
+#[doc(hidden)]
+pub use markup::{markup_bytes, markup_length};
 
 /// A single letter's data required to render it.
 pub struct FontLetter {
@@ -260,6 +275,22 @@ impl Font {
         }
     }
 
+    /// Gets the letter for the given character, or `None` if this font doesn't have it
+    pub(crate) fn try_letter(&self, letter: char) -> Option<&'static FontLetter> {
+        let found = if (0x21..0x7F).contains(&(letter as u32)) {
+            &self.ascii_letters[letter as usize - 0x21]
+        } else {
+            let index = self
+                .letters
+                .binary_search_by_key(&letter, |letter| letter.character)
+                .ok()?;
+            &self.letters[index]
+        };
+
+        // missing ascii letters are filled in with a different letter
+        (found.character == letter).then_some(found)
+    }
+
     pub(crate) fn ascent(&self) -> i32 {
         self.ascent
     }
@@ -269,4 +300,61 @@ impl Font {
     pub fn line_height(&self) -> i32 {
         self.line_height
     }
+
+    #[must_use]
+    /// Measures the given text in this font without any wrapping, so only
+    /// newlines start a new line. Use [`Layout::measure`] to measure text
+    /// with other [`LayoutSettings`].
+    pub fn measure(&'static self, text: &str) -> TextMetrics {
+        Layout::measure(text, self, &LayoutSettings::new())
+    }
+}
+
+/// A font along with the fonts to use for any letters it doesn't have, see
+/// [`LayoutSettings::with_fallback_fonts`].
+#[derive(Clone, Copy)]
+pub(crate) struct FontChain {
+    font: &'static Font,
+    fallbacks: &'static [&'static Font],
+}
+
+impl FontChain {
+    pub(crate) const fn new(font: &'static Font, fallbacks: &'static [&'static Font]) -> Self {
+        Self { font, fallbacks }
+    }
+
+    /// Gets the letter from the first font which has it, or the main font's
+    /// replacement letter if none of them do
+    pub(crate) fn letter(&self, letter: char) -> &'static FontLetter {
+        self.font
+            .try_letter(letter)
+            .or_else(|| {
+                self.fallbacks
+                    .iter()
+                    .find_map(|font| font.try_letter(letter))
+            })
+            .unwrap_or_else(|| self.font.letter(letter))
+    }
+
+    /// The largest ascent of any of the fonts, so every letter fits above the
+    /// shared baseline
+    pub(crate) fn ascent(&self) -> i32 {
+        self.fallbacks
+            .iter()
+            .map(|font| font.ascent())
+            .fold(self.font.ascent(), i32::max)
+    }
+
+    pub(crate) fn line_height(&self) -> i32 {
+        self.fallbacks
+            .iter()
+            .map(|font| font.line_height())
+            .fold(self.font.line_height(), i32::max)
+    }
+}
+
+impl From<&'static Font> for FontChain {
+    fn from(font: &'static Font) -> Self {
+        Self::new(font, &[])
+    }
 }
@@ -1,4 +1,4 @@
-use super::{Font, special::AGB_PRIVATE_USE_RANGE};
+use super::{FontChain, special::AGB_PRIVATE_USE_RANGE};
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
 /// Ways of aligning text
@@ -34,7 +34,7 @@ pub struct Line {
 }
 
 impl Align {
-    pub fn new(alignment: AlignmentKind, max_line_length: i32, font: &Font) -> Self {
+    pub fn new(alignment: AlignmentKind, max_line_length: i32, font: &FontChain) -> Self {
         let default_space_width = font.letter(' ').advance_width as i32;
 
         Self {
@@ -45,7 +45,7 @@ impl Align {
         }
     }
 
-    pub fn next(&mut self, text: &str, font: &Font) -> Option<Line> {
+    pub fn next(&mut self, text: &str, font: &FontChain) -> Option<Line> {
         if self.processed + 1 >= text.len() {
             return None;
         }
@@ -214,18 +214,18 @@ mod tests {
 
     #[test_case]
     fn align_benchmark_short(_: &mut crate::Gba) {
-        let mut align = Align::new(AlignmentKind::Left, 200, &FONT);
+        let mut align = Align::new(AlignmentKind::Left, 200, &FontChain::from(&FONT));
         let text = "Hello, world!";
-        while let Some(line) = align.next(text, &FONT) {
+        while let Some(line) = align.next(text, &FontChain::from(&FONT)) {
             core::hint::black_box(line);
         }
     }
 
     #[test_case]
     fn align_benchmark_long(_: &mut crate::Gba) {
-        let mut align = Align::new(AlignmentKind::Left, 200, &FONT);
+        let mut align = Align::new(AlignmentKind::Left, 200, &FontChain::from(&FONT));
         let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
-        if let Some(line) = align.next(text, &FONT) {
+        if let Some(line) = align.next(text, &FontChain::from(&FONT)) {
             core::hint::black_box(line);
         }
     }
@@ -246,8 +246,8 @@ mod tests {
     fn benchmark_japanese_text(_: &mut crate::Gba) {
         let text = "現代社会において、情報技術の進化は目覚ましい。それは、私たちの生活様式だけでなく、思考様式にも大きな影響を与えている。例えば、スマートフォンやタブレット端末の普及により、いつでもどこでも情報にアクセスできるようになった。これにより、知識の共有やコミュニケーションが容易になり、新しい文化や価値観が生まれている。しかし、一方で、情報過多やプライバシーの問題など、新たな課題も浮上している。私たちは、これらの課題にどのように向き合い、情報技術をどのように活用していくべきだろうか。それは、私たち一人ひとりが真剣に考えるべき重要なテーマである。";
 
-        let mut align = Align::new(AlignmentKind::Left, 200, &FONT);
-        while let Some(line) = align.next(text, &FONT) {
+        let mut align = Align::new(AlignmentKind::Left, 200, &FontChain::from(&FONT));
+        while let Some(line) = align.next(text, &FontChain::from(&FONT)) {
             core::hint::black_box(line);
         }
     }
@@ -1,11 +1,11 @@
 use core::ops::Range;
 
-use alloc::rc::Rc;
+use alloc::{rc::Rc, vec::Vec};
 
 use crate::fixnum::{Vector2D, vec2};
 
 use super::{
-    ChangeColour, Font, FontLetter, Tag,
+    ChangeColour, ChangeSpeed, Font, FontChain, FontLetter, Tag, Wait,
     align::{Align, AlignmentKind, Line},
 };
 
@@ -40,7 +40,7 @@ use super::{
 /// ```
 pub struct Layout {
     text: Rc<str>,
-    font: &'static Font,
+    font: FontChain,
     align: Align,
     line: Option<Line>,
     line_number: i32,
@@ -75,6 +75,7 @@ pub struct LayoutSettings {
     drop_shadow_palette_index: Option<u8>,
     max_group_width: i32,
     max_line_length: i32,
+    fallback_fonts: &'static [&'static Font],
 }
 
 impl Default for LayoutSettings {
@@ -100,6 +101,7 @@ impl LayoutSettings {
             drop_shadow_palette_index: None,
             max_group_width: 16,
             max_line_length: 0,
+            fallback_fonts: &[],
         }
     }
 
@@ -155,6 +157,44 @@ impl LayoutSettings {
         self.max_line_length = max_line_length;
         self
     }
+
+    /// Sets the fonts to use, in order, for any letters which aren't in the
+    /// main font. This lets you keep a small main font and only include the
+    /// letters you need from larger fonts, see the `subset` option of
+    /// [`include_font`](crate::include_font).
+    ///
+    /// Letters from every font share the same baseline, and lines are tall
+    /// enough for the tallest font.
+    ///
+    /// ```rust
+    /// # #![no_std]
+    /// # #![no_main]
+    /// use agb::display::font::{Font, Layout, LayoutSettings};
+    ///
+    /// static FONT: Font = agb::include_font!("examples/font/pixelated.ttf", 8);
+    /// static JAPANESE: Font = agb::include_font!(
+    ///     "examples/font/ark-pixel-10px-proportional-ja.ttf",
+    ///     10,
+    ///     subset = "こんにちは",
+    /// );
+    /// static FALLBACKS: &[&Font] = &[&JAPANESE];
+    ///
+    /// # #[agb::doctest]
+    /// # fn test(_: agb::Gba) {
+    /// let layout = Layout::new(
+    ///     "Hello, こんにちは",
+    ///     &FONT,
+    ///     &LayoutSettings::new().with_fallback_fonts(FALLBACKS),
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// Defaults to no fallback fonts.
+    #[must_use]
+    pub const fn with_fallback_fonts(mut self, fonts: &'static [&'static Font]) -> Self {
+        self.fallback_fonts = fonts;
+        self
+    }
 }
 
 impl Layout {
@@ -162,11 +202,13 @@ impl Layout {
     /// Creates a new layout for the given text, font, and alignment. Generates
     /// [`LetterGroup`]s of width up to the `max_group_width`.
     pub fn new(text: &str, font: &'static Font, settings: &LayoutSettings) -> Self {
+        let font = FontChain::new(font, settings.fallback_fonts);
+
         let mut grouper = Grouper::default();
-        grouper.pos.y = -font.line_height;
+        grouper.pos.y = -font.line_height();
 
         Self {
-            align: Align::new(settings.alignment, settings.max_line_length, font),
+            align: Align::new(settings.alignment, settings.max_line_length, &font),
             text: text.into(),
             font,
             line: None,
@@ -179,6 +221,99 @@ impl Layout {
             max_group_width: settings.max_group_width,
         }
     }
+
+    #[must_use]
+    /// Measures how much space the given text takes up when laid out with the
+    /// given settings, without drawing anything. Use this to size boxes or
+    /// position text before rendering it.
+    ///
+    /// ```rust
+    /// # #![no_std]
+    /// # #![no_main]
+    /// use agb::display::font::{AlignmentKind, Font, Layout, LayoutSettings};
+    ///
+    /// static FONT: Font = agb::include_font!("examples/font/pixelated.ttf", 8);
+    ///
+    /// # #[agb::doctest]
+    /// # fn test(_: agb::Gba) {
+    /// let metrics = Layout::measure("Hello,\nworld!", &FONT, &LayoutSettings::new());
+    ///
+    /// assert_eq!(metrics.line_count(), 2);
+    /// assert_eq!(metrics.height(), 2 * FONT.line_height());
+    ///
+    /// // centre the text on the screen
+    /// let left = (240 - metrics.width()) / 2;
+    /// # }
+    /// ```
+    pub fn measure(text: &str, font: &'static Font, settings: &LayoutSettings) -> TextMetrics {
+        // the group width doesn't change where letters go, so use as few groups as possible
+        let layout = Layout::new(text, font, &settings.clone().with_max_group_width(i32::MAX));
+        let line_height = layout.line_height();
+        let drop_shadow_width = i32::from(settings.drop_shadow_palette_index.is_some());
+
+        let mut extents: Vec<Option<(i32, i32)>> = Vec::new();
+        for group in layout {
+            let line = group.line() as usize;
+            if extents.len() <= line {
+                extents.resize(line + 1, None);
+            }
+
+            let left = group.position.x;
+            let right = group.position.x + group.width + drop_shadow_width;
+            extents[line] = Some(match extents[line] {
+                Some((line_left, line_right)) => (line_left.min(left), line_right.max(right)),
+                None => (left, right),
+            });
+        }
+
+        TextMetrics {
+            line_widths: extents
+                .into_iter()
+                .map(|extent| extent.map_or(0, |(left, right)| right - left))
+                .collect(),
+            line_height,
+        }
+    }
+
+    pub(crate) fn line_height(&self) -> i32 {
+        self.font.line_height()
+    }
+}
+
+/// The size of some text once it has been laid out, as returned by
+/// [`Layout::measure`] and [`Font::measure`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextMetrics {
+    line_widths: Vec<i32>,
+    line_height: i32,
+}
+
+impl TextMetrics {
+    #[must_use]
+    /// The width of the widest line in pixels
+    pub fn width(&self) -> i32 {
+        self.line_widths.iter().copied().max().unwrap_or(0)
+    }
+
+    #[must_use]
+    /// The height of all the lines in pixels
+    pub fn height(&self) -> i32 {
+        self.line_count() as i32 * self.line_height
+    }
+
+    #[must_use]
+    /// The number of lines the text is split over. Lines with no letters in
+    /// them are only counted if there are more letters after them.
+    pub fn line_count(&self) -> usize {
+        self.line_widths.len()
+    }
+
+    #[must_use]
+    /// The width of each line in pixels. This is the width of the letters
+    /// themselves, so it doesn't include the space added by alignment.
+    pub fn line_widths(&self) -> &[i32] {
+        &self.line_widths
+    }
 }
 
 /// A collection of letters and a position for them
@@ -191,7 +326,9 @@ pub struct LetterGroup {
     width: i32,
     position: Vector2D<i32>,
     line: i32,
-    font: &'static Font,
+    font: FontChain,
+    wait: u32,
+    speed_change: Option<ChangeSpeed>,
 }
 
 impl core::fmt::Debug for LetterGroup {
@@ -234,8 +371,8 @@ impl LetterGroup {
         (self.tag >> tag.0) & 1 == 1
     }
 
-    pub(crate) fn font(&self) -> &Font {
-        self.font
+    pub(crate) fn font(&self) -> &FontChain {
+        &self.font
     }
 
     #[must_use]
@@ -244,6 +381,23 @@ impl LetterGroup {
         self.position
     }
 
+    #[must_use]
+    /// The number of frames to pause for before showing this group, from the
+    /// [`Wait`]s just before it.
+    pub fn wait(&self) -> u32 {
+        self.wait
+    }
+
+    #[must_use]
+    /// The [`ChangeSpeed`] just before this group, if there was one.
+    pub fn speed_change(&self) -> Option<ChangeSpeed> {
+        self.speed_change
+    }
+
+    pub(crate) fn offset_by(&mut self, offset: Vector2D<i32>) {
+        self.position += offset;
+    }
+
     #[must_use]
     /// The line count of the text
     pub fn line(&self) -> i32 {
@@ -436,9 +590,9 @@ impl Iterator for Layout {
         let line = match &self.line {
             Some(line) => line,
             None => {
-                let line = self.align.next(&self.text, self.font)?;
+                let line = self.align.next(&self.text, &self.font)?;
                 self.line_number += 1;
-                self.grouper.pos = vec2(line.left, self.grouper.pos.y + self.font.line_height);
+                self.grouper.pos = vec2(line.left, self.grouper.pos.y + self.font.line_height());
                 self.grouper.previous_char = None;
                 self.grouper.current_idx = line.start_index;
 
@@ -461,6 +615,8 @@ impl Iterator for Layout {
             line: self.line_number,
             font: self.font,
             width: 0,
+            wait: 0,
+            speed_change: None,
         };
 
         for (char_index, char) in self.text[self.grouper.current_idx..].char_indices() {
@@ -509,6 +665,28 @@ impl Iterator for Layout {
                 }
             }
 
+            if let Some(wait) = Wait::try_from_char(char) {
+                if letter_group.range.is_empty() {
+                    self.grouper.current_idx += char.len_utf8();
+                    letter_group.range = self.grouper.current_idx..self.grouper.current_idx;
+                    letter_group.wait += wait.frames();
+                    continue;
+                } else {
+                    break;
+                }
+            }
+
+            if let Some(speed_change) = ChangeSpeed::try_from_char(char) {
+                if letter_group.range.is_empty() {
+                    self.grouper.current_idx += char.len_utf8();
+                    letter_group.range = self.grouper.current_idx..self.grouper.current_idx;
+                    letter_group.speed_change = Some(speed_change);
+                    continue;
+                } else {
+                    break;
+                }
+            }
+
             if char == ' ' {
                 self.grouper.add_space(line);
 
@@ -657,4 +835,116 @@ mod test {
 
         assert_eq!(&letter_group_lines, &[0, 1, 2, 2, 2, 2, 2, 3, 3, 3]);
     }
+
+    #[test_case]
+    fn measures_text_without_drawing(_: &mut Gba) {
+        let text = "Hello\n\nSome text that should break over multiple lines";
+        let settings = LayoutSettings::new().with_max_line_length(100);
+
+        let metrics = Layout::measure(text, &FONT, &settings);
+
+        let mut expected = Vec::new();
+        for group in Layout::new(text, &FONT, &settings) {
+            let line = group.line() as usize;
+            expected.resize(expected.len().max(line + 1), None);
+            let (left, right) = expected[line].unwrap_or((i32::MAX, i32::MIN));
+            expected[line] = Some((
+                left.min(group.position().x),
+                right.max(group.position().x + group.bounds().x),
+            ));
+        }
+        let expected = expected
+            .into_iter()
+            .map(|extent| extent.map_or(0, |(left, right)| right - left))
+            .collect::<Vec<_>>();
+
+        assert_eq!(metrics.line_widths(), &expected);
+        assert_eq!(metrics.line_widths()[1], 0);
+        assert!(metrics.line_count() > 3);
+        assert!(metrics.width() <= 100);
+        assert_eq!(
+            metrics.height(),
+            metrics.line_count() as i32 * FONT.line_height()
+        );
+
+        let centred = Layout::measure(
+            text,
+            &FONT,
+            &settings.clone().with_alignment(AlignmentKind::Centre),
+        );
+        assert_eq!(centred, metrics);
+
+        assert_eq!(
+            FONT.measure("Hello"),
+            Layout::measure("Hello", &FONT, &LayoutSettings::new())
+        );
+        assert_eq!(FONT.measure("").line_count(), 0);
+    }
+
+    #[test_case]
+    fn bitmap_fonts_match_the_font_they_were_made_from(_: &mut Gba) {
+        static BDF_FONT: Font = include_font!("fnt/ark-pixel-10px-proportional-latin.bdf");
+        static PNG_FONT: Font = include_font!(
+            "fnt/ark-pixel-10px-proportional-latin-grid.png",
+            10,
+            12,
+            " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~"
+        );
+
+        fn pixels(text: &str, font: &'static Font, y_offset: i32) -> Vec<Vector2D<i32>> {
+            let mut layout = Layout::new(text, font, &LayoutSettings::new());
+            let group = layout.next().unwrap();
+            group
+                .pixels()
+                .map(|(position, _)| position + vec2(0, y_offset))
+                .collect()
+        }
+
+        // the bitmap fonts have a different ascent to the ttf, so everything is shifted vertically
+        let ascent_difference = FONT.ascent() - BDF_FONT.ascent();
+        assert_eq!(
+            pixels("Hello", &BDF_FONT, ascent_difference),
+            pixels("Hello", &FONT, 0)
+        );
+        assert_eq!(pixels("g", &PNG_FONT, 0), pixels("g", &BDF_FONT, 0));
+    }
+
+    #[test_case]
+    fn fallback_fonts_fill_in_missing_letters(_: &mut Gba) {
+        static SUBSET: Font = include_font!(
+            "fnt/ark-pixel-10px-proportional-latin.ttf",
+            10,
+            subset = "H",
+            subset_files = ["fnt/subset-test.txt"],
+        );
+        static FALLBACK: Font = include_font!("fnt/ark-pixel-10px-proportional-latin.bdf");
+        static FALLBACKS: &[&Font] = &[&FALLBACK];
+
+        assert!(SUBSET.try_letter('w').is_some());
+        assert!(SUBSET.try_letter('z').is_none());
+        assert!(SUBSET.letters.len() < FONT.letters.len());
+
+        let chain = FontChain::new(&SUBSET, FALLBACKS);
+        assert!(core::ptr::eq(
+            chain.letter('z'),
+            FALLBACK.try_letter('z').unwrap()
+        ));
+        assert!(core::ptr::eq(
+            chain.letter('H'),
+            SUBSET.try_letter('H').unwrap()
+        ));
+
+        let layout = Layout::new(
+            "zebra",
+            &SUBSET,
+            &LayoutSettings::new().with_fallback_fonts(FALLBACKS),
+        );
+        let group_widths: Vec<_> = layout.map(|group| group.bounds().x).collect();
+
+        let expected_width: i32 = "zebra"
+            .chars()
+            .map(|c| FALLBACK.try_letter(c).unwrap().advance_width as i32)
+            .sum();
+        assert_eq!(group_widths.iter().sum::<i32>(), expected_width);
+    }
 }
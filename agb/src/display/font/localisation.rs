@@ -0,0 +1,412 @@
+//! Translated text for games which support more than one language.
+//!
+//! The [`include_strings!`](crate::include_strings) macro loads a
+//! [Fluent](https://projectfluent.org) translation file for each language and
+//! generates a module containing a `Language` enum, a `StringId` enum with a
+//! variant for each string, and a `strings` function to create the [`Strings`]
+//! for a language. The language can then be changed at any time with
+//! [`Strings::set_language`].
+//!
+//! Formatting a string gives a [`String`] which can be passed straight to
+//! [`Layout::new`](super::Layout::new). Fluent's `{ $variable }` placeholders
+//! are filled in from the arguments, selectors pick between plural forms using
+//! the rules for the language, and `{ COLOUR(n) }` becomes a [`ChangeColour`]
+//! to palette index `n`.
+//!
+//! ```ftl
+//! greeting = Hello, { $name }!
+//! coins = { $count ->
+//!     [0] You have no coins.
+//!     [one] You have { COLOUR(2) }one{ COLOUR(1) } coin.
+//!    *[other] You have { COLOUR(2) }{ $count }{ COLOUR(1) } coins.
+//! }
+//! ```
+//!
+//! ```rust
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::display::font::{Font, Layout, LayoutSettings};
+//!
+//! agb::include_strings!(mod text, "examples/lang/en.ftl", "examples/lang/fr.ftl");
+//!
+//! static FONT: Font = agb::include_font!("fnt/ark-pixel-10px-proportional-latin.ttf", 10);
+//!
+//! # #[agb::doctest]
+//! # fn test(_: agb::Gba) {
+//! use text::{Language, StringId};
+//!
+//! let mut strings = text::strings(Language::En);
+//! assert_eq!(strings.format(StringId::Greeting, &[("name", "Ferris".into())]), "Hello, Ferris!");
+//!
+//! strings.set_language(Language::Fr);
+//! let coins = strings.format(StringId::Coins, &[("count", 0.into())]);
+//! assert_eq!(coins, "Vous n'avez aucune pièce.");
+//!
+//! let layout = Layout::new(&coins, &FONT, &LayoutSettings::new().with_max_line_length(200));
+//! # }
+//! ```
+//!
+//! The first file is the reference language. Every string must be in it, and
+//! any string missing from another language uses the reference translation.
+//! The language code for each file, which decides its plural rules, is the
+//! name of the file.
+//!
+//! Only the letters a language uses need to be in its font. Passing the
+//! translation file to [`include_font!`](crate::include_font)'s
+//! `subset_files` keeps just the letters in its translated text, along with
+//! the digits and `-` for any numbers filled in to its placeholders.
+//!
+//! ```rust
+//! # #![no_std]
+//! # #![no_main]
+//! # use agb::display::font::Font;
+//! static JAPANESE_FONT: Font = agb::include_font!(
+//!     "examples/font/ark-pixel-10px-proportional-ja.ttf",
+//!     10,
+//!     subset_files = ["examples/lang/ja.ftl"],
+//! );
+//! # #[agb::doctest]
+//! # fn test(_: agb::Gba) {}
+//! ```
+
+use alloc::string::String;
+use core::{fmt::Write, marker::PhantomData};
+
+use super::ChangeColour;
+
+/// A language with translations, implemented by the `Language` enum generated
+/// by [`include_strings!`](crate::include_strings).
+pub trait Language: Copy {
+    /// The position of the language in the translation table.
+    fn index(self) -> usize;
+    /// How numbers choose between the plural forms of the language.
+    fn plural_rule(self) -> PluralRule;
+}
+
+/// A translatable string, implemented by the `StringId` enum generated by
+/// [`include_strings!`](crate::include_strings).
+pub trait StringId: Copy {
+    /// The position of the string in the translation table.
+    fn index(self) -> usize;
+}
+
+/// The strings for every language, formatted in the current language.
+pub struct Strings<L, S> {
+    table: &'static [&'static [&'static Message]],
+    language: L,
+    _ids: PhantomData<S>,
+}
+
+impl<L: Language, S: StringId> Strings<L, S> {
+    #[must_use]
+    #[doc(hidden)]
+    /// Unstable interface for creating strings, should only be used by the [`crate::include_strings`] macro
+    pub const fn new(table: &'static [&'static [&'static Message]], language: L) -> Self {
+        Self {
+            table,
+            language,
+            _ids: PhantomData,
+        }
+    }
+
+    /// Changes the language of all future formatted strings.
+    pub fn set_language(&mut self, language: L) {
+        self.language = language;
+    }
+
+    #[must_use]
+    /// The current language.
+    pub fn language(&self) -> L {
+        self.language
+    }
+
+    #[must_use]
+    /// The translation of the string in the current language.
+    pub fn message(&self, id: S) -> &'static Message {
+        self.table[self.language.index()][id.index()]
+    }
+
+    #[must_use]
+    /// Formats a string which has no placeholders.
+    pub fn get(&self, id: S) -> String {
+        self.format(id, &[])
+    }
+
+    #[must_use]
+    /// Formats a string, filling in its placeholders from the named arguments.
+    pub fn format(&self, id: S, arguments: &[(&str, Value)]) -> String {
+        self.message(id)
+            .format(self.language.plural_rule(), arguments)
+    }
+}
+
+/// A translated string.
+pub struct Message {
+    parts: &'static [Part],
+}
+
+impl Message {
+    #[must_use]
+    #[doc(hidden)]
+    /// Unstable interface for creating a message, should only be used by the [`crate::include_strings`] macro
+    pub const fn new(parts: &'static [Part]) -> Self {
+        Self { parts }
+    }
+
+    #[must_use]
+    /// Formats the message, using `plural_rule` to choose between plural
+    /// forms. Placeholders without an argument are left as `{$name}`.
+    pub fn format(&self, plural_rule: PluralRule, arguments: &[(&str, Value)]) -> String {
+        let mut output = String::new();
+        write_parts(&mut output, self.parts, plural_rule, arguments);
+        output
+    }
+}
+
+fn write_parts(
+    output: &mut String,
+    parts: &[Part],
+    plural_rule: PluralRule,
+    arguments: &[(&str, Value)],
+) {
+    let argument = |name: &str| {
+        arguments
+            .iter()
+            .find(|(argument, _)| *argument == name)
+            .map(|(_, value)| *value)
+    };
+
+    for part in parts {
+        match *part {
+            Part::Text(text) => output.push_str(text),
+            Part::Variable(name) => match argument(name) {
+                Some(Value::Text(text)) => output.push_str(text),
+                Some(Value::Number(number)) => {
+                    let _ = write!(output, "{number}");
+                }
+                None => {
+                    let _ = write!(output, "{{${name}}}");
+                }
+            },
+            Part::Colour(colour) => output.push(ChangeColour::new(colour as u32).to_char()),
+            Part::Select {
+                variable,
+                variants,
+                default,
+            } => {
+                let chosen = match argument(variable) {
+                    Some(Value::Number(number)) => {
+                        let category = plural_rule.category(number);
+                        variants
+                            .iter()
+                            .find(|(key, _)| *key == Key::Number(number))
+                            .or_else(|| {
+                                variants
+                                    .iter()
+                                    .find(|(key, _)| *key == Key::Category(category))
+                            })
+                    }
+                    Some(Value::Text(text)) => variants
+                        .iter()
+                        .find(|(key, _)| matches!(key, Key::Text(key) if *key == text)),
+                    None => None,
+                };
+
+                let (_, parts) = chosen.unwrap_or(&variants[default]);
+                write_parts(output, parts, plural_rule, arguments);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// An argument to fill in a placeholder.
+pub enum Value<'a> {
+    /// Text, which is displayed as it is.
+    Text(&'a str),
+    /// A number, which also chooses the plural form in selectors.
+    Number(i32),
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(value: &'a str) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<i32> for Value<'_> {
+    fn from(value: i32) -> Self {
+        Value::Number(value)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[doc(hidden)]
+/// Unstable interface for the contents of a message, should only be used by the [`crate::include_strings`] macro
+pub enum Part {
+    Text(&'static str),
+    Variable(&'static str),
+    Colour(u8),
+    Select {
+        variable: &'static str,
+        variants: &'static [(Key, &'static [Part])],
+        default: usize,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[doc(hidden)]
+/// Unstable interface for the keys of a selector, should only be used by the [`crate::include_strings`] macro
+pub enum Key {
+    Category(PluralCategory),
+    Number(i32),
+    Text(&'static str),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// The [CLDR plural categories](https://cldr.unicode.org/index/cldr-spec/plural-rules)
+/// which a number can fall in to.
+#[allow(missing_docs)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// How a language chooses the plural form for a number. The rule for a
+/// translation file is chosen from its language code.
+pub enum PluralRule {
+    /// One for 1, other for everything else. Used for English, German, Spanish
+    /// and any language without a more specific rule.
+    OneOrOther,
+    /// One for 0 and 1, other for everything else. Used for French and
+    /// Portuguese.
+    ZeroOrOne,
+    /// Always other. Used for Japanese, Chinese, Korean, Vietnamese, Thai,
+    /// Indonesian and Malay.
+    NoPlurals,
+    /// One for 1, 21, 31..., few for 2-4, 22-24..., many for everything else.
+    /// Used for Russian, Ukrainian, Belarusian, Serbian, Croatian and Bosnian.
+    EastSlavic,
+    /// One for 1, few for 2-4, 22-24..., many for everything else. Used for
+    /// Polish.
+    Polish,
+    /// One for 1, few for 2-4, other for everything else. Used for Czech and
+    /// Slovak.
+    Czech,
+}
+
+impl PluralRule {
+    #[must_use]
+    /// The plural category of `number`.
+    pub const fn category(self, number: i32) -> PluralCategory {
+        let n = number.unsigned_abs();
+        let few = matches!(n % 10, 2..=4) && !matches!(n % 100, 12..=14);
+
+        match self {
+            PluralRule::OneOrOther if n == 1 => PluralCategory::One,
+            PluralRule::ZeroOrOne if n <= 1 => PluralCategory::One,
+            PluralRule::EastSlavic if n % 10 == 1 && n % 100 != 11 => PluralCategory::One,
+            PluralRule::EastSlavic if few => PluralCategory::Few,
+            PluralRule::EastSlavic => PluralCategory::Many,
+            PluralRule::Polish if n == 1 => PluralCategory::One,
+            PluralRule::Polish if few => PluralCategory::Few,
+            PluralRule::Polish => PluralCategory::Many,
+            PluralRule::Czech if n == 1 => PluralCategory::One,
+            PluralRule::Czech if matches!(n, 2..=4) => PluralCategory::Few,
+            _ => PluralCategory::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::font::{Font, Layout, LayoutSettings};
+
+    crate::include_strings!(
+        mod text,
+        "examples/lang/en.ftl",
+        "examples/lang/fr.ftl",
+        "examples/lang/ja.ftl"
+    );
+
+    static JAPANESE_FONT: Font = crate::include_font!(
+        "examples/font/ark-pixel-10px-proportional-ja.ttf",
+        10,
+        subset_files = ["examples/lang/ja.ftl"],
+    );
+
+    use text::{Language, StringId};
+
+    #[test_case]
+    fn strings_switch_language(_gba: &mut crate::Gba) {
+        let mut strings = text::strings(Language::En);
+        assert_eq!(strings.get(StringId::Title), "Crab Quest");
+
+        strings.set_language(Language::Fr);
+        assert_eq!(strings.language(), Language::Fr);
+        assert_eq!(strings.get(StringId::Title), "La Quête du Crabe");
+        assert_eq!(
+            strings.format(StringId::Greeting, &[("name", "Ferris".into())]),
+            "Bonjour, Ferris !"
+        );
+
+        // missing translations use the reference language
+        assert_eq!(strings.get(StringId::Quit), "Quit");
+
+        assert_eq!(Language::ALL, &[Language::En, Language::Fr, Language::Ja]);
+        assert_eq!(Language::Ja.code(), "ja");
+    }
+
+    #[test_case]
+    fn plurals_use_the_rules_of_the_language(_gba: &mut crate::Gba) {
+        let colour = |index| ChangeColour::new(index).to_char();
+
+        let mut strings = text::strings(Language::En);
+        let coins = |strings: &text::Strings, count: i32| {
+            strings.format(StringId::Coins, &[("count", count.into())])
+        };
+
+        assert_eq!(coins(&strings, 0), "You have no coins.");
+        assert_eq!(
+            coins(&strings, 1),
+            alloc::format!("You have {}one{} coin.", colour(2), colour(1))
+        );
+        assert_eq!(
+            coins(&strings, 12),
+            alloc::format!("You have {}12{} coins.", colour(2), colour(1))
+        );
+
+        strings.set_language(Language::Fr);
+        assert_eq!(coins(&strings, 0), "Vous n'avez aucune pièce.");
+        assert_eq!(
+            coins(&strings, -1),
+            alloc::format!("Vous avez {}-1{} pièce.", colour(2), colour(1))
+        );
+
+        assert_eq!(PluralRule::EastSlavic.category(21), PluralCategory::One);
+        assert_eq!(PluralRule::EastSlavic.category(13), PluralCategory::Many);
+        assert_eq!(PluralRule::Polish.category(23), PluralCategory::Few);
+        assert_eq!(PluralRule::NoPlurals.category(1), PluralCategory::Other);
+    }
+
+    #[test_case]
+    fn translations_lay_out_with_their_language_font(_gba: &mut crate::Gba) {
+        let strings = text::strings(Language::Ja);
+        let coins = strings.format(StringId::Coins, &[("count", 3.into())]);
+
+        let layout = Layout::new(
+            &coins,
+            &JAPANESE_FONT,
+            &LayoutSettings::new().with_max_line_length(200),
+        );
+        let text: String = layout.map(|group| String::from(group.text())).collect();
+
+        assert_eq!(text, "コインが3こあります。");
+    }
+}
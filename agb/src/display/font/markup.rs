@@ -0,0 +1,455 @@
+use alloc::{string::String, vec};
+use core::fmt::Display;
+
+use super::{ChangeColour, ChangeSpeed, Icon, Tag, Wait};
+
+/// How many `{c:n}` can be open at once.
+const MAX_COLOUR_DEPTH: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// A mistake in text written with markup, see [`markup!`](crate::markup).
+pub enum MarkupError {
+    /// A `{` without a matching `}`. Use `{{` for a literal `{`.
+    UnclosedCommand,
+    /// A `}` which doesn't close a command. Use `}}` for a literal `}`.
+    UnexpectedClose,
+    /// A command which isn't one of the known commands.
+    UnknownCommand,
+    /// A command's number isn't a number.
+    InvalidNumber,
+    /// A command's number is too big.
+    NumberOutOfRange,
+    /// A `{/c}` without a matching `{c:n}`.
+    UnmatchedColourEnd,
+    /// More than 16 `{c:n}` are open at once.
+    ColoursTooDeep,
+}
+
+impl MarkupError {
+    const fn message(self) -> &'static str {
+        match self {
+            MarkupError::UnclosedCommand => "`{` is never closed, use `{{` for a literal `{`",
+            MarkupError::UnexpectedClose => "unexpected `}`, use `}}` for a literal `}`",
+            MarkupError::UnknownCommand => {
+                "unknown command, expected c, /c, tag, /tag, wait, speed, /speed or icon"
+            }
+            MarkupError::InvalidNumber => "expected a number",
+            MarkupError::NumberOutOfRange => "number is too big for this command",
+            MarkupError::UnmatchedColourEnd => "`{/c}` without a matching `{c:n}`",
+            MarkupError::ColoursTooDeep => "too many `{c:n}` open at once",
+        }
+    }
+
+    #[doc(hidden)]
+    /// Used by [`crate::markup`] to report the error at compile time
+    pub const fn panic(self) -> ! {
+        panic!("{}", self.message())
+    }
+}
+
+impl Display for MarkupError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl core::error::Error for MarkupError {}
+
+/// Converts text written with markup into the special characters understood
+/// by [`Layout`](super::Layout) and [`TextBox`](super::TextBox). For text which
+/// is known at compile time, use [`markup!`](crate::markup) instead.
+///
+/// # Errors
+///
+/// If the markup is not valid.
+pub fn parse_markup(text: &str) -> Result<String, MarkupError> {
+    let length = encode(text.as_bytes(), &mut [])?;
+
+    let mut bytes = vec![0; length];
+    encode(text.as_bytes(), &mut bytes)?;
+
+    Ok(String::from_utf8(bytes).expect("markup should produce valid utf-8"))
+}
+
+#[must_use]
+#[doc(hidden)]
+/// Used by [`crate::markup`] to size the encoded text
+pub const fn markup_length(text: &str) -> usize {
+    match encode(text.as_bytes(), &mut []) {
+        Ok(length) => length,
+        Err(err) => err.panic(),
+    }
+}
+
+#[must_use]
+#[doc(hidden)]
+/// Used by [`crate::markup`] to encode the text
+pub const fn markup_bytes<const N: usize>(text: &str) -> [u8; N] {
+    let mut bytes = [0; N];
+    match encode(text.as_bytes(), &mut bytes) {
+        Ok(_) => bytes,
+        Err(err) => err.panic(),
+    }
+}
+
+enum Command {
+    Colour(u32),
+    EndColour,
+    Tag(u32),
+    EndTag(u32),
+    Wait(u32),
+    Speed(u32),
+    EndSpeed,
+    Icon(u32),
+}
+
+/// Encodes the markup into `output`, returning the length of the encoded text.
+/// If `output` is too short, only the length is calculated.
+const fn encode(text: &[u8], output: &mut [u8]) -> Result<usize, MarkupError> {
+    let mut length = 0;
+    let mut index = 0;
+
+    let mut colours = [1; MAX_COLOUR_DEPTH];
+    let mut colour_depth = 0;
+
+    while index < text.len() {
+        let byte = text[index];
+
+        if byte == b'}' {
+            if index + 1 < text.len() && text[index + 1] == b'}' {
+                length = push_byte(output, length, b'}');
+                index += 2;
+                continue;
+            }
+
+            return Err(MarkupError::UnexpectedClose);
+        }
+
+        if byte != b'{' {
+            length = push_byte(output, length, byte);
+            index += 1;
+            continue;
+        }
+
+        if index + 1 < text.len() && text[index + 1] == b'{' {
+            length = push_byte(output, length, b'{');
+            index += 2;
+            continue;
+        }
+
+        let start = index + 1;
+        let mut end = start;
+        while end < text.len() && text[end] != b'}' {
+            if text[end] == b'{' {
+                return Err(MarkupError::UnclosedCommand);
+            }
+            end += 1;
+        }
+
+        if end == text.len() {
+            return Err(MarkupError::UnclosedCommand);
+        }
+
+        let (_, rest) = text.split_at(start);
+        let (command, _) = rest.split_at(end - start);
+
+        let command = match parse_command(command) {
+            Ok(command) => command,
+            Err(err) => return Err(err),
+        };
+
+        length = match command {
+            Command::Colour(colour) => {
+                if colour >= 16 {
+                    return Err(MarkupError::NumberOutOfRange);
+                }
+                if colour_depth + 1 >= MAX_COLOUR_DEPTH {
+                    return Err(MarkupError::ColoursTooDeep);
+                }
+
+                colour_depth += 1;
+                colours[colour_depth] = colour;
+                push_char(output, length, ChangeColour::new(colour).to_char())
+            }
+            Command::EndColour => {
+                if colour_depth == 0 {
+                    return Err(MarkupError::UnmatchedColourEnd);
+                }
+
+                colour_depth -= 1;
+                push_char(
+                    output,
+                    length,
+                    ChangeColour::new(colours[colour_depth]).to_char(),
+                )
+            }
+            Command::Tag(tag) | Command::EndTag(tag) if tag >= 16 => {
+                return Err(MarkupError::NumberOutOfRange);
+            }
+            Command::Tag(tag) => push_char(output, length, Tag::new(tag).set()),
+            Command::EndTag(tag) => push_char(output, length, Tag::new(tag).unset()),
+            Command::Wait(mut frames) => {
+                // longer waits are split up into several waits
+                while frames > 0 {
+                    let this_wait = if frames > 255 { 255 } else { frames };
+                    length = push_char(output, length, Wait::new(this_wait).to_char());
+                    frames -= this_wait;
+                }
+                length
+            }
+            Command::Speed(speed) => {
+                if speed >= 16 {
+                    return Err(MarkupError::NumberOutOfRange);
+                }
+                push_char(output, length, ChangeSpeed::new(speed).to_char())
+            }
+            Command::EndSpeed => push_char(output, length, ChangeSpeed::reset().to_char()),
+            Command::Icon(icon) => {
+                if icon >= 256 {
+                    return Err(MarkupError::NumberOutOfRange);
+                }
+                push_char(output, length, Icon::new(icon).to_char())
+            }
+        };
+
+        index = end + 1;
+    }
+
+    Ok(length)
+}
+
+const fn parse_command(command: &[u8]) -> Result<Command, MarkupError> {
+    if bytes_eq(command, b"/c") {
+        return Ok(Command::EndColour);
+    }
+    if bytes_eq(command, b"/speed") {
+        return Ok(Command::EndSpeed);
+    }
+
+    let mut colon = 0;
+    while colon < command.len() && command[colon] != b':' {
+        colon += 1;
+    }
+
+    if colon == command.len() {
+        return Err(MarkupError::UnknownCommand);
+    }
+
+    let (name, argument) = command.split_at(colon);
+    let (_, argument) = argument.split_at(1);
+
+    let argument = match parse_number(argument) {
+        Ok(argument) => argument,
+        Err(err) => return Err(err),
+    };
+
+    if bytes_eq(name, b"c") {
+        Ok(Command::Colour(argument))
+    } else if bytes_eq(name, b"tag") {
+        Ok(Command::Tag(argument))
+    } else if bytes_eq(name, b"/tag") {
+        Ok(Command::EndTag(argument))
+    } else if bytes_eq(name, b"wait") {
+        Ok(Command::Wait(argument))
+    } else if bytes_eq(name, b"speed") {
+        Ok(Command::Speed(argument))
+    } else if bytes_eq(name, b"icon") {
+        Ok(Command::Icon(argument))
+    } else {
+        Err(MarkupError::UnknownCommand)
+    }
+}
+
+const fn parse_number(digits: &[u8]) -> Result<u32, MarkupError> {
+    if digits.is_empty() {
+        return Err(MarkupError::InvalidNumber);
+    }
+
+    let mut number: u32 = 0;
+    let mut index = 0;
+    while index < digits.len() {
+        let digit = digits[index];
+        if !digit.is_ascii_digit() {
+            return Err(MarkupError::InvalidNumber);
+        }
+
+        number = match number.checked_mul(10) {
+            Some(number) => match number.checked_add((digit - b'0') as u32) {
+                Some(number) => number,
+                None => return Err(MarkupError::NumberOutOfRange),
+            },
+            None => return Err(MarkupError::NumberOutOfRange),
+        };
+        index += 1;
+    }
+
+    Ok(number)
+}
+
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut index = 0;
+    while index < a.len() {
+        if a[index] != b[index] {
+            return false;
+        }
+        index += 1;
+    }
+
+    true
+}
+
+const fn push_byte(output: &mut [u8], length: usize, byte: u8) -> usize {
+    if length < output.len() {
+        output[length] = byte;
+    }
+    length + 1
+}
+
+const fn push_char(output: &mut [u8], length: usize, c: char) -> usize {
+    let mut buffer = [0; 4];
+    let encoded = c.encode_utf8(&mut buffer).as_bytes();
+
+    let mut length = length;
+    let mut index = 0;
+    while index < encoded.len() {
+        length = push_byte(output, length, encoded[index]);
+        index += 1;
+    }
+
+    length
+}
+
+/// Writes text with inline markup, checked and converted at compile time.
+/// Gives a `&'static str` ready for [`Layout`](crate::display::font::Layout)
+/// or [`TextBox`](crate::display::font::TextBox).
+///
+/// | Markup        | Meaning                                                                                |
+/// |---------------|----------------------------------------------------------------------------------------|
+/// | `{c:n}`       | Changes to colour `n` with a [`ChangeColour`](crate::display::font::ChangeColour)     |
+/// | `{/c}`        | Goes back to the colour before the matching `{c:n}`, or colour 1                       |
+/// | `{tag:n}`     | Sets [`Tag`](crate::display::font::Tag) `n`                                            |
+/// | `{/tag:n}`    | Unsets [`Tag`](crate::display::font::Tag) `n`                                          |
+/// | `{wait:n}`    | Pauses for `n` frames with a [`Wait`](crate::display::font::Wait)                      |
+/// | `{speed:n}`   | Reveals letters at `n` frames each with a [`ChangeSpeed`](crate::display::font::ChangeSpeed) |
+/// | `{/speed}`    | Goes back to the starting speed                                                        |
+/// | `{icon:n}`    | Shows [`Icon`](crate::display::font::Icon) `n`                                         |
+/// | `{{` and `}}` | A literal `{` or `}`                                                                   |
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::display::font::{ChangeColour, Wait};
+///
+/// # #[agb::doctest]
+/// # fn test(_: agb::Gba) {
+/// const GREETING: &str = agb::markup!("Hello {c:2}world{/c}!{wait:30}");
+///
+/// assert_eq!(
+///     GREETING,
+///     alloc::format!("Hello {}world{}!{}", ChangeColour::new(2), ChangeColour::new(1), Wait::new(30))
+/// );
+/// # }
+/// # extern crate alloc;
+/// ```
+///
+/// Mistakes in the markup are compile errors. For text which is only known at
+/// runtime, such as text loaded from save data, use
+/// [`parse_markup`](crate::display::font::parse_markup).
+#[macro_export]
+macro_rules! markup {
+    ($text: expr) => {{
+        const TEXT: &str = $text;
+        const LENGTH: usize = $crate::display::font::markup_length(TEXT);
+        const BYTES: [u8; LENGTH] = $crate::display::font::markup_bytes::<LENGTH>(TEXT);
+        const MARKUP: &str = match ::core::str::from_utf8(&BYTES) {
+            Ok(text) => text,
+            Err(_) => panic!("markup should produce valid utf-8"),
+        };
+        MARKUP
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use super::*;
+    use crate::display::font::{Font, Layout, LayoutSettings};
+
+    static FONT: Font = include_font!("fnt/ark-pixel-10px-proportional-latin.ttf", 10);
+
+    #[test_case]
+    fn markup_converts_to_special_characters(_gba: &mut crate::Gba) {
+        let red = ChangeColour::new(2);
+        let blue = ChangeColour::new(3);
+        let white = ChangeColour::new(1);
+        let tag = Tag::new(4);
+
+        assert_eq!(
+            parse_markup("{c:2}a{c:3}b{/c}c{/c} {{d}} {tag:4}e{/tag:4}").unwrap(),
+            format!(
+                "{red}a{blue}b{red}c{white} {{d}} {}e{}",
+                tag.set(),
+                tag.unset()
+            )
+        );
+        assert_eq!(
+            parse_markup("{speed:4}slow{/speed}{wait:300}{icon:7}").unwrap(),
+            format!(
+                "{}slow{}{}{}{}",
+                ChangeSpeed::new(4),
+                ChangeSpeed::reset(),
+                Wait::new(255),
+                Wait::new(45),
+                Icon::new(7)
+            )
+        );
+
+        const COMPILE_TIME: &str = crate::markup!("日本語 {c:2}テキスト{/c}");
+        assert_eq!(
+            COMPILE_TIME,
+            parse_markup("日本語 {c:2}テキスト{/c}").unwrap()
+        );
+    }
+
+    #[test_case]
+    fn invalid_markup_is_an_error(_gba: &mut crate::Gba) {
+        assert_eq!(parse_markup("{c:2"), Err(MarkupError::UnclosedCommand));
+        assert_eq!(parse_markup("a } b"), Err(MarkupError::UnexpectedClose));
+        assert_eq!(parse_markup("{colour:2}"), Err(MarkupError::UnknownCommand));
+        assert_eq!(parse_markup("{c:red}"), Err(MarkupError::InvalidNumber));
+        assert_eq!(parse_markup("{c:16}"), Err(MarkupError::NumberOutOfRange));
+        assert_eq!(parse_markup("{/c}"), Err(MarkupError::UnmatchedColourEnd));
+    }
+
+    #[test_case]
+    fn waits_and_speed_changes_split_letter_groups(_gba: &mut crate::Gba) {
+        let text = crate::markup!("Well{wait:20}{wait:10}... {speed:8}fine");
+        let groups: alloc::vec::Vec<_> = Layout::new(
+            text,
+            &FONT,
+            &LayoutSettings::new().with_max_group_width(100),
+        )
+        .map(|group| {
+            (
+                String::from(group.text()),
+                group.wait(),
+                group.speed_change(),
+            )
+        })
+        .collect();
+
+        assert_eq!(
+            groups,
+            [
+                (String::from("Well"), 0, None),
+                (String::from("..."), 30, None),
+                (String::from("fine"), 0, Some(ChangeSpeed::new(8))),
+            ]
+        );
+    }
+}
@@ -5,8 +5,8 @@ use core::ops::Range;
 
 const UTF8_PRIVATE_USE_START: u32 = 0xE000;
 
-pub(crate) const AGB_PRIVATE_USE_RANGE: Range<u32> =
-    UTF8_PRIVATE_USE_START..UTF8_PRIVATE_USE_START + 48;
+/// Control codes which take up no space when laid out.
+pub(crate) const AGB_PRIVATE_USE_RANGE: Range<u32> = UTF8_PRIVATE_USE_START..ChangeSpeed::RANGE.end;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 /// The way of changing the colour in a palette during text rendering. It
@@ -129,3 +129,195 @@ impl Tag {
         }
     }
 }
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// Pauses the text before the letters which follow it. The pause is given by
+/// [`LetterGroup::wait`](super::LetterGroup::wait), and a
+/// [`TextBox`](super::TextBox) waits this many frames before revealing them.
+///
+/// It is implemented using the unicode private use area, with the 256 code
+/// points from `0xE030` to `0xE130`.
+pub struct Wait {
+    frames: u8,
+}
+
+impl Wait {
+    pub(crate) const RANGE: Range<u32> = UTF8_PRIVATE_USE_START + 48..UTF8_PRIVATE_USE_START + 304;
+
+    #[must_use]
+    /// Creates a pause of the given number of frames.
+    ///
+    /// # Panics
+    /// Panics if frames is 256 or more. Use more than one `Wait` for longer pauses.
+    pub const fn new(frames: u32) -> Self {
+        assert!(frames < 256, "wait must be less than 256 frames");
+        Self {
+            frames: frames as u8,
+        }
+    }
+
+    #[must_use]
+    /// The number of frames to pause for.
+    pub const fn frames(self) -> u32 {
+        self.frames as u32
+    }
+
+    pub(crate) fn try_from_char(c: char) -> Option<Self> {
+        let c = u32::from(c);
+        if Self::RANGE.contains(&c) {
+            Some(Self::new(c - Self::RANGE.start))
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    /// The char representation of the Wait.
+    pub const fn to_char(self) -> char {
+        char::from_u32(self.frames as u32 + Self::RANGE.start).unwrap()
+    }
+}
+
+impl Display for Wait {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_char(self.to_char())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// Changes how quickly the letters which follow it are revealed. The change
+/// is given by [`LetterGroup::speed_change`](super::LetterGroup::speed_change)
+/// and is used by [`TextBox`](super::TextBox).
+///
+/// It is implemented using the unicode private use area, with the 17 code
+/// points from `0xE130` to `0xE141`.
+pub struct ChangeSpeed {
+    frames_per_letter: Option<u8>,
+}
+
+impl ChangeSpeed {
+    pub(crate) const RANGE: Range<u32> = Wait::RANGE.end..Wait::RANGE.end + 17;
+    const RESET: u32 = 16;
+
+    #[must_use]
+    /// Reveals the following letters at the given number of frames per letter.
+    ///
+    /// # Panics
+    /// Panics if frames_per_letter is 16 or more.
+    pub const fn new(frames_per_letter: u32) -> Self {
+        assert!(
+            frames_per_letter < Self::RESET,
+            "frames per letter must be valid (0..=15)"
+        );
+        Self {
+            frames_per_letter: Some(frames_per_letter as u8),
+        }
+    }
+
+    #[must_use]
+    /// Goes back to the speed the text started with.
+    pub const fn reset() -> Self {
+        Self {
+            frames_per_letter: None,
+        }
+    }
+
+    #[must_use]
+    /// The new number of frames per letter, or `None` to go back to the speed
+    /// the text started with.
+    pub const fn frames_per_letter(self) -> Option<u32> {
+        match self.frames_per_letter {
+            Some(frames) => Some(frames as u32),
+            None => None,
+        }
+    }
+
+    pub(crate) fn try_from_char(c: char) -> Option<Self> {
+        let c = u32::from(c);
+        if !Self::RANGE.contains(&c) {
+            None
+        } else if c - Self::RANGE.start == Self::RESET {
+            Some(Self::reset())
+        } else {
+            Some(Self::new(c - Self::RANGE.start))
+        }
+    }
+
+    #[must_use]
+    /// The char representation of the ChangeSpeed.
+    pub const fn to_char(self) -> char {
+        let offset = match self.frames_per_letter {
+            Some(frames) => frames as u32,
+            None => Self::RESET,
+        };
+        char::from_u32(offset + Self::RANGE.start).unwrap()
+    }
+}
+
+impl Display for ChangeSpeed {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_char(self.to_char())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// An inline icon, such as a button or an item, shown in the middle of text.
+///
+/// Unlike the other special characters, an icon is laid out and drawn like
+/// any other letter, so it needs a font containing it. Make one from a png
+/// glyph sheet with [`include_font`](crate::include_font), listing the icon
+/// characters as its letters, and add it to
+/// [`LayoutSettings::with_fallback_fonts`](super::LayoutSettings::with_fallback_fonts).
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// extern crate alloc;
+/// use agb::display::font::{Font, Icon, Layout, LayoutSettings};
+///
+/// static FONT: Font = agb::include_font!("fnt/ark-pixel-10px-proportional-latin.ttf", 10);
+/// // two 10x12 icons, the first two cells of this glyph sheet
+/// static ICONS: Font = agb::include_font!(
+///     "fnt/ark-pixel-10px-proportional-latin-grid.png",
+///     10,
+///     12,
+///     "\u{E200}\u{E201}",
+/// );
+/// static FALLBACKS: &[&Font] = &[&ICONS];
+///
+/// # #[agb::doctest]
+/// # fn test(_: agb::Gba) {
+/// let text = alloc::format!("Press {} to jump", Icon::new(1));
+/// let layout = Layout::new(&text, &FONT, &LayoutSettings::new().with_fallback_fonts(FALLBACKS));
+/// # }
+/// ```
+///
+/// It is implemented using the unicode private use area, with the 256 code
+/// points from `0xE200` to `0xE300`.
+pub struct Icon(u8);
+
+impl Icon {
+    const RANGE: Range<u32> = UTF8_PRIVATE_USE_START + 0x200..UTF8_PRIVATE_USE_START + 0x300;
+
+    #[must_use]
+    /// Creates the icon with the given index.
+    ///
+    /// # Panics
+    /// Panics if the index is 256 or more.
+    pub const fn new(index: u32) -> Self {
+        assert!(index < 256, "icon index must be valid (0..=255)");
+        Self(index as u8)
+    }
+
+    #[must_use]
+    /// The char representation of the Icon.
+    pub const fn to_char(self) -> char {
+        char::from_u32(self.0 as u32 + Self::RANGE.start).unwrap()
+    }
+}
+
+impl Display for Icon {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_char(self.to_char())
+    }
+}
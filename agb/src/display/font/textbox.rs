@@ -0,0 +1,686 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    display::{
+        GraphicsFrame,
+        object::Object,
+        tiled::{RegularBackground, RegularBackgroundId},
+    },
+    fixnum::{Vector2D, vec2},
+    input::{Button, ButtonController, Tri},
+};
+
+use super::{
+    Font, Layout, LayoutSettings, LetterGroup, ObjectTextRenderer, RegularBackgroundTextRenderer,
+};
+
+/// Letters which the text pauses on for [`TextBoxSettings::with_punctuation_pause`].
+const PUNCTUATION: &[char] = &['.', ',', '!', '?', ':', ';', '…', '。', '、', '！', '？'];
+
+/// Somewhere a [`TextBox`] can draw its text.
+///
+/// [`ObjectTextBoxRenderer`] and [`BackgroundTextBoxRenderer`] draw using the
+/// existing text renderers, or you can implement this yourself for any other
+/// way of showing [`LetterGroup`]s.
+pub trait TextBoxRenderer {
+    /// Draws a letter group. Its position is relative to the top left of the
+    /// text box.
+    fn draw(&mut self, group: &LetterGroup);
+    /// Removes all the text which has been drawn, ready for the next page.
+    fn clear(&mut self);
+}
+
+/// Control how a [`TextBox`] lays out and reveals its text.
+///
+/// Uses a builder pattern in the same way as [`LayoutSettings`].
+#[derive(Clone)]
+pub struct TextBoxSettings {
+    layout: LayoutSettings,
+    width: i32,
+    lines_per_page: i32,
+    frames_per_letter: u32,
+    punctuation_pause: u32,
+    choice_indent: i32,
+}
+
+impl TextBoxSettings {
+    /// Creates settings for a text box `width` pixels wide which shows
+    /// `lines_per_page` lines of text at once.
+    ///
+    /// Defaults:
+    /// - `layout_settings`: [`LayoutSettings::new`]
+    /// - `frames_per_letter`: 2
+    /// - `punctuation_pause`: 12
+    /// - `choice_indent`: 8
+    #[must_use]
+    pub const fn new(width: i32, lines_per_page: i32) -> Self {
+        Self {
+            layout: LayoutSettings::new(),
+            width,
+            lines_per_page,
+            frames_per_letter: 2,
+            punctuation_pause: 12,
+            choice_indent: 8,
+        }
+    }
+
+    /// Sets how the text is laid out. The maximum line length is replaced by
+    /// the width of the text box.
+    ///
+    /// The text is revealed one [`LetterGroup`] at a time, so a smaller
+    /// [`max_group_width`](LayoutSettings::with_max_group_width) reveals the
+    /// text more smoothly.
+    #[must_use]
+    pub fn with_layout_settings(mut self, layout: LayoutSettings) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets how many frames it takes to reveal each letter. 0 shows each
+    /// page at once.
+    ///
+    /// Defaults to 2.
+    #[must_use]
+    pub const fn with_frames_per_letter(mut self, frames_per_letter: u32) -> Self {
+        self.frames_per_letter = frames_per_letter;
+        self
+    }
+
+    /// Sets how many extra frames to wait after revealing punctuation which
+    /// ends a sentence or clause.
+    ///
+    /// Defaults to 12.
+    #[must_use]
+    pub const fn with_punctuation_pause(mut self, punctuation_pause: u32) -> Self {
+        self.punctuation_pause = punctuation_pause;
+        self
+    }
+
+    /// Sets how far choices are indented to leave space for the cursor.
+    ///
+    /// Defaults to 8.
+    #[must_use]
+    pub const fn with_choice_indent(mut self, choice_indent: i32) -> Self {
+        self.choice_indent = choice_indent;
+        self
+    }
+
+    fn layout(&self, text: &str, font: &'static Font, indent: i32) -> Layout {
+        Layout::new(
+            text,
+            font,
+            &self
+                .layout
+                .clone()
+                .with_max_line_length(self.width - indent),
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// What a [`TextBox`] is currently doing.
+pub enum TextBoxState {
+    /// The text is being revealed. Pressing A reveals the rest of the page.
+    Writing,
+    /// The page is full. Pressing A continues on the next page.
+    PageFull,
+    /// All the text has been revealed. Pressing A finishes.
+    EndOfText,
+    /// Waiting for a choice. Up and down move the cursor and A chooses.
+    Choosing,
+    /// Finished, any choice is given by [`TextBox::chosen`].
+    Finished,
+}
+
+/// A dialogue box which reveals its text over time, waits for the player to
+/// continue when a page is full and can end with a choice.
+///
+/// Call [`update`](TextBox::update) once a frame. While waiting for the
+/// player, [`prompt_position`](TextBox::prompt_position) gives where to show a
+/// "press A to continue" indicator, and while choosing,
+/// [`cursor_position`](TextBox::cursor_position) gives where to show the
+/// cursor. Both are relative to the top left of the text box.
+///
+/// The text can pause with [`Wait`](super::Wait) and change how quickly it is
+/// revealed with [`ChangeSpeed`](super::ChangeSpeed), which are easiest to
+/// write using [`markup!`](crate::markup).
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{
+///     display::{
+///         Palette16, Rgb15,
+///         font::{Font, ObjectTextBoxRenderer, ObjectTextRenderer, TextBox, TextBoxSettings, TextBoxState},
+///         object::Size,
+///     },
+///     input::ButtonController,
+/// };
+///
+/// static PALETTE: Palette16 = const {
+///     let mut palette = [Rgb15::BLACK; 16];
+///     palette[1] = Rgb15::WHITE;
+///     Palette16::new(palette)
+/// };
+/// static FONT: Font = agb::include_font!("fnt/ark-pixel-10px-proportional-latin.ttf", 10);
+///
+/// # #[agb::doctest]
+/// # fn test(mut gba: agb::Gba) {
+/// let mut gfx = gba.graphics.get();
+/// let mut input = ButtonController::new();
+///
+/// let renderer = ObjectTextBoxRenderer::new(
+///     ObjectTextRenderer::new((&PALETTE).into(), Size::S16x16),
+///     (8, 112),
+/// );
+/// let mut text_box = TextBox::new(renderer, &FONT, TextBoxSettings::new(224, 3));
+/// text_box.ask("Do you want to save your game?", &["Yes", "No"]);
+///
+/// # let mut frames = 0;
+/// while text_box.state() != TextBoxState::Finished {
+///     input.update();
+///     text_box.update(&input);
+///
+///     let mut frame = gfx.frame();
+///     text_box.renderer().show(&mut frame);
+///     frame.commit();
+/// #   frames += 1;
+/// #   if frames > 10 { break; }
+/// }
+///
+/// let save = text_box.chosen() == Some(0);
+/// # }
+/// ```
+pub struct TextBox<R> {
+    renderer: R,
+    font: &'static Font,
+    settings: TextBoxSettings,
+    state: TextBoxState,
+
+    layout: Option<Layout>,
+    line_height: i32,
+    held_group: Option<LetterGroup>,
+    page_first_line: i32,
+    lines_on_page: i32,
+    delay: u32,
+    frames_per_letter: u32,
+    waited: bool,
+    prompt: Vector2D<i32>,
+
+    choices: Vec<String>,
+    choice_tops: Vec<i32>,
+    selected: usize,
+    chosen: Option<usize>,
+}
+
+impl<R: TextBoxRenderer> TextBox<R> {
+    /// Creates an empty text box which draws to `renderer`.
+    #[must_use]
+    pub fn new(renderer: R, font: &'static Font, settings: TextBoxSettings) -> Self {
+        Self {
+            renderer,
+            font,
+            frames_per_letter: settings.frames_per_letter,
+            settings,
+            state: TextBoxState::Finished,
+
+            layout: None,
+            line_height: font.line_height(),
+            held_group: None,
+            page_first_line: 0,
+            lines_on_page: 0,
+            delay: 0,
+            waited: false,
+            prompt: vec2(0, 0),
+
+            choices: Vec::new(),
+            choice_tops: Vec::new(),
+            selected: 0,
+            chosen: None,
+        }
+    }
+
+    /// Clears the text box and starts revealing `text`.
+    pub fn say(&mut self, text: &str) {
+        self.ask(text, &[]);
+    }
+
+    /// Clears the text box and starts revealing `text`, after which the
+    /// player picks one of the `choices`.
+    pub fn ask(&mut self, text: &str, choices: &[&str]) {
+        self.renderer.clear();
+
+        let layout = self.settings.layout(text, self.font, 0);
+        self.line_height = layout.line_height();
+        self.layout = Some(layout);
+        self.held_group = None;
+        self.page_first_line = 0;
+        self.lines_on_page = 0;
+        self.delay = 0;
+        self.frames_per_letter = self.settings.frames_per_letter;
+        self.waited = false;
+        self.prompt = vec2(0, 0);
+
+        self.choices = choices.iter().map(|&choice| String::from(choice)).collect();
+        self.choice_tops.clear();
+        self.selected = 0;
+        self.chosen = None;
+
+        self.state = TextBoxState::Writing;
+    }
+
+    /// Reveals more text and responds to the player. Call this once a frame.
+    pub fn update(&mut self, input: &ButtonController) {
+        let pressed_a = input.is_just_pressed(Button::A);
+
+        match self.state {
+            TextBoxState::Writing => {
+                while self.state == TextBoxState::Writing && (pressed_a || self.delay == 0) {
+                    self.write_next_group(pressed_a);
+                }
+
+                self.delay = self.delay.saturating_sub(1);
+            }
+            TextBoxState::PageFull if pressed_a => self.next_page(),
+            TextBoxState::EndOfText if pressed_a => self.state = TextBoxState::Finished,
+            TextBoxState::Choosing => {
+                let count = self.choices.len();
+                match input.just_pressed_y_tri() {
+                    Tri::Negative => self.selected = (self.selected + count - 1) % count,
+                    Tri::Positive => self.selected = (self.selected + 1) % count,
+                    Tri::Zero => {}
+                }
+
+                if pressed_a {
+                    self.chosen = Some(self.selected);
+                    self.state = TextBoxState::Finished;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[must_use]
+    /// What the text box is currently doing.
+    pub fn state(&self) -> TextBoxState {
+        self.state
+    }
+
+    #[must_use]
+    /// The choice the player made, once the text box has finished.
+    pub fn chosen(&self) -> Option<usize> {
+        self.chosen
+    }
+
+    #[must_use]
+    /// Where to show the "press A to continue" prompt, just after the last
+    /// letters on the page. Only `Some` when waiting for the player to press A.
+    pub fn prompt_position(&self) -> Option<Vector2D<i32>> {
+        matches!(self.state, TextBoxState::PageFull | TextBoxState::EndOfText)
+            .then_some(self.prompt)
+    }
+
+    #[must_use]
+    /// Where to show the cursor, at the start of the line of the selected
+    /// choice. Only `Some` while choosing.
+    pub fn cursor_position(&self) -> Option<Vector2D<i32>> {
+        (self.state == TextBoxState::Choosing).then(|| vec2(0, self.choice_tops[self.selected]))
+    }
+
+    #[must_use]
+    /// The renderer the text is drawn with.
+    pub fn renderer(&self) -> &R {
+        &self.renderer
+    }
+
+    #[must_use]
+    /// The renderer the text is drawn with.
+    pub fn renderer_mut(&mut self) -> &mut R {
+        &mut self.renderer
+    }
+
+    fn write_next_group(&mut self, skipping: bool) {
+        let group = self
+            .held_group
+            .take()
+            .or_else(|| self.layout.as_mut().and_then(Iterator::next));
+
+        let Some(mut group) = group else {
+            self.layout = None;
+            self.show_choices();
+            return;
+        };
+
+        if group.line() - self.page_first_line >= self.settings.lines_per_page {
+            self.held_group = Some(group);
+            self.state = TextBoxState::PageFull;
+            return;
+        }
+
+        // pause before showing the group, unless the player is skipping ahead
+        if group.wait() > 0 && !self.waited && !skipping {
+            self.delay = group.wait();
+            self.waited = true;
+            self.held_group = Some(group);
+            return;
+        }
+        self.waited = false;
+
+        if let Some(speed_change) = group.speed_change() {
+            self.frames_per_letter = speed_change
+                .frames_per_letter()
+                .unwrap_or(self.settings.frames_per_letter);
+        }
+
+        self.lines_on_page = group.line() - self.page_first_line + 1;
+        group.offset_by(vec2(0, -self.page_first_line * self.line_height));
+        self.renderer.draw(&group);
+
+        self.prompt = group.position() + vec2(group.bounds().x, 0);
+        self.delay = self.reveal_time(&group);
+    }
+
+    fn reveal_time(&self, group: &LetterGroup) -> u32 {
+        let text = group.text();
+        let letters = text.chars().filter(|c| !c.is_whitespace()).count() as u32;
+
+        let pause = if text.trim_end().ends_with(PUNCTUATION) {
+            self.settings.punctuation_pause
+        } else {
+            0
+        };
+
+        letters * self.frames_per_letter + pause
+    }
+
+    fn next_page(&mut self) {
+        self.renderer.clear();
+        self.lines_on_page = 0;
+
+        match &self.held_group {
+            Some(group) => {
+                self.page_first_line = group.line();
+                self.delay = 0;
+                self.state = TextBoxState::Writing;
+            }
+            // the choices didn't fit on the last page of text
+            None => self.show_choices(),
+        }
+    }
+
+    fn show_choices(&mut self) {
+        if self.choices.is_empty() {
+            self.state = TextBoxState::EndOfText;
+            return;
+        }
+
+        let indent = self.settings.choice_indent;
+        let choices: Vec<Vec<LetterGroup>> = self
+            .choices
+            .iter()
+            .map(|choice| self.settings.layout(choice, self.font, indent).collect())
+            .collect();
+        let lines = |groups: &[LetterGroup]| groups.last().map_or(1, |group| group.line() + 1);
+
+        let total_lines: i32 = choices.iter().map(|groups| lines(groups)).sum();
+        if self.lines_on_page > 0 && self.lines_on_page + total_lines > self.settings.lines_per_page
+        {
+            self.state = TextBoxState::PageFull;
+            return;
+        }
+
+        let mut top = self.lines_on_page * self.line_height;
+        self.choice_tops.clear();
+
+        for groups in choices {
+            self.choice_tops.push(top);
+            let height = lines(&groups) * self.line_height;
+
+            for mut group in groups {
+                group.offset_by(vec2(indent, top));
+                self.renderer.draw(&group);
+            }
+
+            top += height;
+        }
+
+        self.state = TextBoxState::Choosing;
+    }
+}
+
+/// Draws a [`TextBox`] with objects from an [`ObjectTextRenderer`].
+pub struct ObjectTextBoxRenderer {
+    renderer: ObjectTextRenderer,
+    position: Vector2D<i32>,
+    objects: Vec<Object>,
+}
+
+impl ObjectTextBoxRenderer {
+    #[must_use]
+    /// Creates a renderer for a text box whose top left is at `position` on
+    /// the screen.
+    pub fn new(renderer: ObjectTextRenderer, position: impl Into<Vector2D<i32>>) -> Self {
+        Self {
+            renderer,
+            position: position.into(),
+            objects: Vec::new(),
+        }
+    }
+
+    /// Shows the text on the current frame.
+    pub fn show(&self, frame: &mut GraphicsFrame) {
+        for object in &self.objects {
+            object.show(frame);
+        }
+    }
+}
+
+impl TextBoxRenderer for ObjectTextBoxRenderer {
+    fn draw(&mut self, group: &LetterGroup) {
+        self.objects.push(self.renderer.show(group, self.position));
+    }
+
+    fn clear(&mut self) {
+        self.objects.clear();
+    }
+}
+
+/// Draws a [`TextBox`] on a background with a [`RegularBackgroundTextRenderer`].
+pub struct BackgroundTextBoxRenderer {
+    background: RegularBackground,
+    renderer: RegularBackgroundTextRenderer,
+}
+
+impl BackgroundTextBoxRenderer {
+    #[must_use]
+    /// Creates a renderer which draws on `background`. The origin of the
+    /// `renderer` is the top left of the text box.
+    pub fn new(background: RegularBackground, renderer: RegularBackgroundTextRenderer) -> Self {
+        Self {
+            background,
+            renderer,
+        }
+    }
+
+    /// Shows the background on the current frame.
+    pub fn show(&self, frame: &mut GraphicsFrame) -> RegularBackgroundId {
+        self.background.show(frame)
+    }
+
+    #[must_use]
+    /// The background the text is drawn on.
+    pub fn background(&self) -> &RegularBackground {
+        &self.background
+    }
+
+    #[must_use]
+    /// The background the text is drawn on, for drawing the frame of the
+    /// text box or other tiles around the text.
+    pub fn background_mut(&mut self) -> &mut RegularBackground {
+        &mut self.background
+    }
+}
+
+impl TextBoxRenderer for BackgroundTextBoxRenderer {
+    fn draw(&mut self, group: &LetterGroup) {
+        self.renderer.show(&mut self.background, group);
+    }
+
+    fn clear(&mut self) {
+        self.renderer.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::input::ButtonState;
+
+    static FONT: Font = include_font!("fnt/ark-pixel-10px-proportional-latin.ttf", 10);
+
+    #[derive(Default)]
+    struct Recorder {
+        drawn: Vec<(String, Vector2D<i32>)>,
+    }
+
+    impl TextBoxRenderer for Recorder {
+        fn draw(&mut self, group: &LetterGroup) {
+            self.drawn
+                .push((String::from(group.text()), group.position()));
+        }
+
+        fn clear(&mut self) {
+            self.drawn.clear();
+        }
+    }
+
+    fn texts(text_box: &TextBox<Recorder>) -> Vec<&str> {
+        text_box
+            .renderer()
+            .drawn
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect()
+    }
+
+    fn press(input: &mut ButtonController, text_box: &mut TextBox<Recorder>, state: ButtonState) {
+        input.update_with_state(state);
+        text_box.update(input);
+    }
+
+    #[test_case]
+    fn text_is_revealed_over_time(_gba: &mut crate::Gba) {
+        let mut input = ButtonController::new();
+        input.update_with_state(ButtonState::empty());
+
+        let settings = TextBoxSettings::new(200, 2)
+            .with_frames_per_letter(2)
+            .with_punctuation_pause(10);
+        let mut text_box = TextBox::new(Recorder::default(), &FONT, settings);
+        text_box.say("Hi. Bye now");
+
+        // "Hi." takes 3 letters at 2 frames each plus the punctuation pause
+        for _ in 0..16 {
+            press(&mut input, &mut text_box, ButtonState::empty());
+        }
+        assert_eq!(texts(&text_box), vec!["Hi."]);
+
+        press(&mut input, &mut text_box, ButtonState::empty());
+        assert_eq!(texts(&text_box), vec!["Hi.", "Bye"]);
+
+        // pressing A reveals the rest of the page
+        press(&mut input, &mut text_box, Button::A.into());
+        assert_eq!(texts(&text_box), vec!["Hi.", "Bye", "now"]);
+        assert_eq!(text_box.state(), TextBoxState::EndOfText);
+        assert!(text_box.prompt_position().is_some());
+
+        press(&mut input, &mut text_box, ButtonState::empty());
+        press(&mut input, &mut text_box, Button::A.into());
+        assert_eq!(text_box.state(), TextBoxState::Finished);
+        assert_eq!(text_box.chosen(), None);
+    }
+
+    #[test_case]
+    fn markup_pauses_and_changes_speed(_gba: &mut crate::Gba) {
+        let mut input = ButtonController::new();
+        input.update_with_state(ButtonState::empty());
+
+        let settings = TextBoxSettings::new(200, 2)
+            .with_frames_per_letter(0)
+            .with_punctuation_pause(0);
+        let mut text_box = TextBox::new(Recorder::default(), &FONT, settings);
+        text_box.say(crate::markup!("Wait{wait:3} for{speed:1} it now"));
+
+        press(&mut input, &mut text_box, ButtonState::empty());
+        assert_eq!(texts(&text_box), vec!["Wait"]);
+
+        for _ in 0..2 {
+            press(&mut input, &mut text_box, ButtonState::empty());
+        }
+        assert_eq!(texts(&text_box), vec!["Wait"]);
+
+        // after the wait, "for" is revealed straight away and "it" switches
+        // to taking 1 frame per letter
+        press(&mut input, &mut text_box, ButtonState::empty());
+        assert_eq!(texts(&text_box), vec!["Wait", "for", "it"]);
+
+        press(&mut input, &mut text_box, ButtonState::empty());
+        assert_eq!(texts(&text_box), vec!["Wait", "for", "it"]);
+
+        press(&mut input, &mut text_box, ButtonState::empty());
+        assert_eq!(texts(&text_box), vec!["Wait", "for", "it", "now"]);
+    }
+
+    #[test_case]
+    fn full_pages_wait_for_the_player(_gba: &mut crate::Gba) {
+        let mut input = ButtonController::new();
+        input.update_with_state(ButtonState::empty());
+
+        let settings = TextBoxSettings::new(200, 1).with_frames_per_letter(0);
+        let mut text_box = TextBox::new(Recorder::default(), &FONT, settings);
+        text_box.say("One\nTwo");
+
+        press(&mut input, &mut text_box, ButtonState::empty());
+        assert_eq!(text_box.state(), TextBoxState::PageFull);
+        assert_eq!(texts(&text_box), vec!["One"]);
+
+        press(&mut input, &mut text_box, Button::A.into());
+        press(&mut input, &mut text_box, ButtonState::empty());
+        assert_eq!(texts(&text_box), vec!["Two"]);
+        assert_eq!(text_box.renderer().drawn[0].1.y, 0);
+        assert_eq!(text_box.state(), TextBoxState::EndOfText);
+    }
+
+    #[test_case]
+    fn choices_are_picked_with_a_cursor(_gba: &mut crate::Gba) {
+        let mut input = ButtonController::new();
+        input.update_with_state(ButtonState::empty());
+
+        let settings = TextBoxSettings::new(200, 3)
+            .with_frames_per_letter(0)
+            .with_punctuation_pause(0);
+        let mut text_box = TextBox::new(Recorder::default(), &FONT, settings);
+        text_box.ask("Go?", &["Yes", "No"]);
+
+        press(&mut input, &mut text_box, ButtonState::empty());
+        assert_eq!(text_box.state(), TextBoxState::Choosing);
+        assert_eq!(texts(&text_box), vec!["Go?", "Yes", "No"]);
+        assert_eq!(
+            text_box.cursor_position(),
+            Some(vec2(0, FONT.line_height()))
+        );
+
+        press(&mut input, &mut text_box, Button::Up.into());
+        assert_eq!(
+            text_box.cursor_position(),
+            Some(vec2(0, FONT.line_height() * 2))
+        );
+
+        press(&mut input, &mut text_box, ButtonState::empty());
+        press(&mut input, &mut text_box, Button::A.into());
+        assert_eq!(text_box.state(), TextBoxState::Finished);
+        assert_eq!(text_box.chosen(), Some(1));
+    }
+}
@@ -3,7 +3,7 @@ use alloc::vec::Vec;
 use super::LetterGroup;
 use crate::{
     display::{
-        tiled::{DynamicTile16, RegularBackground, TileEffect},
+        tiled::{AffineBackground, DynamicTile16, DynamicTile256, RegularBackground, TileEffect},
         utils::blit_16_colour,
     },
     fixnum::{Vector2D, vec2},
@@ -107,6 +107,14 @@ impl RegularBackgroundTextRenderer {
         }
     }
 
+    /// Clears all the text drawn by this renderer. The tiles stay on the
+    /// background, so new text can be drawn in the same place.
+    pub fn clear(&mut self) {
+        for tile in self.tiles.iter_mut().flatten().flatten() {
+            tile.data_mut().fill(0);
+        }
+    }
+
     fn ensure_drawing_space(&mut self, bg: &mut RegularBackground, group: &LetterGroup) {
         let dynamic_origin = vec2(self.origin.x.rem_euclid(8), self.origin.y.rem_euclid(8));
         let tile_offset = vec2(self.origin.x / 8, self.origin.y / 8);
@@ -143,6 +151,263 @@ impl RegularBackgroundTextRenderer {
     }
 }
 
+/// The background tile based renderer backend for [`LetterGroup`]s on
+/// [256 colour](crate::display::tiled::TileFormat::EightBpp) regular
+/// backgrounds. It works the same way as [`RegularBackgroundTextRenderer`].
+///
+/// The letters are drawn using colour `palette_id * 16 + palette_index` of the
+/// 256 colour palette, so the colours line up with those you would get from
+/// [`VRAM_MANAGER.set_background_palette(palette_id, ...)`](crate::display::tiled::VRamManager::set_background_palette).
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::display::{
+///     Palette16, Rgb15, Priority,
+///     font::{Font, Layout, LayoutSettings, RegularBackgroundTextRenderer256},
+///     tiled::{RegularBackground, VRAM_MANAGER, RegularBackgroundSize, TileFormat},
+/// };
+///
+/// static FONT: Font = agb::include_font!("examples/font/pixelated.ttf", 8);
+///
+/// # #[agb::doctest]
+/// # fn test(mut gba: agb::Gba) {
+/// // colour 1 of the 256 colour palette
+/// VRAM_MANAGER.set_background_palette_colour(0, 1, Rgb15::WHITE);
+/// let mut bg = RegularBackground::new(
+///     Priority::P0,
+///     RegularBackgroundSize::Background32x32,
+///     TileFormat::EightBpp,
+/// );
+///
+/// let layout = Layout::new("Hello, world!", &FONT, &LayoutSettings::new());
+/// let mut text_renderer = RegularBackgroundTextRenderer256::new((0, 0), 0);
+///
+/// for letter_group in layout {
+///     text_renderer.show(&mut bg, &letter_group);
+/// }
+/// # }
+/// ```
+pub struct RegularBackgroundTextRenderer256 {
+    tiles: TextTiles256,
+}
+
+impl RegularBackgroundTextRenderer256 {
+    /// Creates a new background renderer with a given origin. All text is
+    /// rendered with respect to this origin.
+    ///
+    /// Uses the 16 colours starting at `palette_id * 16` for the drawn tiles.
+    #[must_use]
+    pub fn new(origin: impl Into<Vector2D<i32>>, palette_id: u8) -> Self {
+        Self {
+            tiles: TextTiles256::new(origin.into(), palette_id),
+        }
+    }
+
+    /// Displays the given letter group on the given background, which must be
+    /// a 256 colour background.
+    pub fn show(&mut self, bg: &mut RegularBackground, group: &LetterGroup) {
+        self.tiles.show(group, |pos| {
+            let tile = DynamicTile256::new().fill_with(0);
+            bg.set_tile_dynamic256(pos, &tile, TileEffect::default());
+            tile
+        });
+    }
+
+    /// Clears all the text drawn by this renderer. The tiles stay on the
+    /// background, so new text can be drawn in the same place.
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+    }
+}
+
+/// The background tile based renderer backend for [`LetterGroup`]s on
+/// [`AffineBackground`]s, so that rotated or scaled backgrounds can show
+/// dynamic text. It works the same way as [`RegularBackgroundTextRenderer256`].
+///
+/// Only 256 distinct tiles can be used across all affine backgrounds, and each
+/// 8x8 area this draws to uses one of them, so keep the text short.
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::display::{
+///     Priority, Rgb15,
+///     font::{AffineBackgroundTextRenderer, Font, Layout, LayoutSettings},
+///     tiled::{AffineBackground, AffineBackgroundSize, AffineBackgroundWrapBehaviour, VRAM_MANAGER},
+/// };
+///
+/// static FONT: Font = agb::include_font!("examples/font/pixelated.ttf", 8);
+///
+/// # #[agb::doctest]
+/// # fn test(mut gba: agb::Gba) {
+/// VRAM_MANAGER.set_background_palette_colour(0, 1, Rgb15::WHITE);
+/// let mut bg = AffineBackground::new(
+///     Priority::P0,
+///     AffineBackgroundSize::Background16x16,
+///     AffineBackgroundWrapBehaviour::NoWrap,
+/// );
+///
+/// let layout = Layout::new("Title", &FONT, &LayoutSettings::new());
+/// let mut text_renderer = AffineBackgroundTextRenderer::new((8, 8), 0);
+///
+/// for letter_group in layout {
+///     text_renderer.show(&mut bg, &letter_group);
+/// }
+/// # }
+/// ```
+pub struct AffineBackgroundTextRenderer {
+    tiles: TextTiles256,
+}
+
+impl AffineBackgroundTextRenderer {
+    /// Creates a new affine background renderer with a given origin. All text
+    /// is rendered with respect to this origin.
+    ///
+    /// Uses the 16 colours starting at `palette_id * 16` for the drawn tiles.
+    #[must_use]
+    pub fn new(origin: impl Into<Vector2D<i32>>, palette_id: u8) -> Self {
+        Self {
+            tiles: TextTiles256::new(origin.into(), palette_id),
+        }
+    }
+
+    /// Displays the given letter group on the given background.
+    pub fn show(&mut self, bg: &mut AffineBackground, group: &LetterGroup) {
+        self.tiles.show(group, |pos| {
+            let tile = DynamicTile256::new_affine().fill_with(0);
+            bg.set_tile_dynamic256(pos, &tile);
+            tile
+        });
+    }
+
+    /// Clears all the text drawn by this renderer. The tiles stay on the
+    /// background, so new text can be drawn in the same place.
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+    }
+}
+
+/// The 256 colour tiles text has been drawn to, shared between the regular and
+/// affine renderers which only differ in how tiles get put on the background.
+struct TextTiles256 {
+    tiles: Vec<Vec<Option<DynamicTile256>>>,
+    origin: Vector2D<i32>,
+    colour_offset: u32,
+}
+
+impl TextTiles256 {
+    fn new(origin: Vector2D<i32>, palette_id: u8) -> Self {
+        Self {
+            tiles: Vec::new(),
+            origin,
+            colour_offset: u32::from(palette_id) * 16,
+        }
+    }
+
+    fn show(
+        &mut self,
+        group: &LetterGroup,
+        mut place_tile: impl FnMut(Vector2D<i32>) -> DynamicTile256,
+    ) {
+        self.ensure_drawing_space(group, &mut place_tile);
+
+        let dynamic_origin = vec2(self.origin.x.rem_euclid(8), self.origin.y.rem_euclid(8));
+
+        for (px_start, px) in group.pixels_packed() {
+            let pos = px_start + dynamic_origin + group.position();
+            let (pixels, mask) = self.expand(px);
+
+            let x = pos.x as usize / 8;
+            let y = pos.y as usize / 8;
+            let y_index = pos.y.rem_euclid(8) as usize;
+            let x_in_tile = pos.x.rem_euclid(8) as u32 * 8;
+
+            let row = &mut self.tiles[y];
+
+            let tile_left = row[x].as_mut().expect("should have ensured space");
+            blit_row(tile_left, y_index, pixels << x_in_tile, mask << x_in_tile);
+
+            if x_in_tile > 0 {
+                let tile_right = row[x + 1].as_mut().expect("should have ensured space");
+                blit_row(
+                    tile_right,
+                    y_index,
+                    pixels >> (64 - x_in_tile),
+                    mask >> (64 - x_in_tile),
+                );
+            }
+        }
+    }
+
+    // Turns 8 packed 4bpp pixels into 8 packed 8bpp pixels, along with a mask of
+    // which of them are drawn so the transparent ones don't overwrite anything.
+    fn expand(&self, px: u32) -> (u64, u64) {
+        let mut pixels = 0;
+        let mut mask = 0;
+
+        for i in 0..8 {
+            let palette_index = (px >> (i * 4)) & 0xF;
+            if palette_index != 0 {
+                pixels |= u64::from(palette_index + self.colour_offset) << (i * 8);
+                mask |= 0xFF << (i * 8);
+            }
+        }
+
+        (pixels, mask)
+    }
+
+    fn clear(&mut self) {
+        for tile in self.tiles.iter_mut().flatten().flatten() {
+            tile.data_mut().fill(0);
+        }
+    }
+
+    fn ensure_drawing_space(
+        &mut self,
+        group: &LetterGroup,
+        place_tile: &mut impl FnMut(Vector2D<i32>) -> DynamicTile256,
+    ) {
+        let dynamic_origin = vec2(self.origin.x.rem_euclid(8), self.origin.y.rem_euclid(8));
+        let tile_offset = vec2(self.origin.x / 8, self.origin.y / 8);
+
+        let bounds = group.bounds();
+        let top_left_tile = group.position() / 8;
+
+        let bottom_right_tile = (dynamic_origin + bounds + group.position()) / 8 + vec2(1, 0);
+        if self.tiles.len() <= bottom_right_tile.y as usize {
+            self.tiles
+                .resize_with(bottom_right_tile.y as usize + 1, Vec::new);
+        }
+
+        for row_idx in top_left_tile.y..(bottom_right_tile.y + 1) {
+            let row = &mut self.tiles[row_idx as usize];
+            if row.len() <= bottom_right_tile.x as usize {
+                row.resize_with(bottom_right_tile.x as usize + 1, || None);
+            }
+
+            for column_idx in top_left_tile.x..(bottom_right_tile.x + 1) {
+                if row[column_idx as usize].is_none() {
+                    let tile_pos = vec2(column_idx, row_idx) + tile_offset;
+                    row[column_idx as usize] = Some(place_tile(tile_pos));
+                }
+            }
+        }
+    }
+}
+
+// A row of a 256 colour tile is 8 bytes, stored in 2 words
+fn blit_row(tile: &mut DynamicTile256, y: usize, pixels: u64, mask: u64) {
+    let row = &mut tile.data_mut()[y * 2..y * 2 + 2];
+
+    for (i, word) in row.iter_mut().enumerate() {
+        let mask = (mask >> (i * 32)) as u32;
+        if mask != 0 {
+            *word = (*word & !mask) | (pixels >> (i * 32)) as u32;
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -242,6 +507,70 @@ mod test {
         assert_image_output("gfx/test_output/bg_font_render_japanese.png");
     }
 
+    #[test_case]
+    fn background_text_render_256_colours(_: &mut Gba) {
+        let mut bg = RegularBackground::new(
+            Priority::P0,
+            RegularBackgroundSize::Background32x32,
+            TileFormat::EightBpp,
+        );
+
+        const CHANGE2: ChangeColour = ChangeColour::new(2);
+
+        let origin = vec2(19, 13);
+        let mut bg_text_render = RegularBackgroundTextRenderer256::new(origin, 3);
+
+        let mut expected = alloc::collections::BTreeMap::new();
+        let layout = Layout::new(
+            &format!("Hello, {CHANGE2}world!"),
+            &FONT,
+            &LayoutSettings::new()
+                .with_drop_shadow(4)
+                .with_max_group_width(20),
+        );
+        for lg in layout {
+            bg_text_render.show(&mut bg, &lg);
+            for (pos, palette_index) in lg.pixels() {
+                let pos = pos + lg.position();
+                expected.insert((pos.y, pos.x), palette_index + 3 * 16);
+            }
+        }
+
+        let dynamic_origin = vec2(origin.x.rem_euclid(8), origin.y.rem_euclid(8));
+        let mut drawn = alloc::collections::BTreeMap::new();
+        for (tile_y, row) in bg_text_render.tiles.tiles.iter().enumerate() {
+            for (tile_x, tile) in row.iter().enumerate() {
+                let Some(tile) = tile else { continue };
+                for (i, byte) in tile
+                    .data()
+                    .iter()
+                    .flat_map(|word| word.to_le_bytes())
+                    .enumerate()
+                {
+                    if byte != 0 {
+                        let pos = vec2(tile_x as i32 * 8, tile_y as i32 * 8)
+                            + vec2(i as i32 % 8, i as i32 / 8)
+                            - dynamic_origin;
+                        drawn.insert((pos.y, pos.x), byte);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(drawn, expected);
+
+        bg_text_render.clear();
+        assert!(
+            bg_text_render
+                .tiles
+                .tiles
+                .iter()
+                .flatten()
+                .flatten()
+                .all(|tile| tile.data().iter().all(|&word| word == 0))
+        );
+    }
+
     #[test_case]
     fn background_text_single_group(gba: &mut Gba) {
         static PALETTE: Palette16 = const {
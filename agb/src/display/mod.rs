@@ -70,7 +70,7 @@ use tiled::{BackgroundFrame, DisplayControlRegister, VRAM_MANAGER};
 
 use object::{Oam, OamFrame, initilise_oam};
 
-pub use colours::{Rgb, Rgb15, include_colours};
+pub use colours::{Hsv, Rgb, Rgb15, include_colours};
 pub use palette16::Palette16;
 
 /// Graphics mode 3. Bitmap mode that provides a 16-bit colour framebuffer.
@@ -87,6 +87,7 @@ pub mod utils;
 mod affine;
 pub use affine::AffineMatrix;
 mod blend;
+mod debug_console;
 mod window;
 
 pub mod font;
@@ -100,6 +101,8 @@ pub use blend::{Blend, BlendAlphaEffect, BlendFadeEffect, BlendObjectTransparenc
 
 pub use window::{MovableWindow, WinIn, Window, Windows};
 
+pub use debug_console::DebugConsole;
+
 /// Width of the Game Boy advance screen in pixels
 pub const WIDTH: i32 = 240;
 /// Height of the Game Boy advance screen in pixels
@@ -2,15 +2,17 @@
 #![deny(missing_docs)]
 
 mod affine;
+mod metasprite;
 mod sprites;
 mod unmanaged;
 
 pub use sprites::{
-    DynamicSprite16, DynamicSprite256, PaletteMulti, PaletteVram, PaletteVramMulti,
+    DynamicSprite16, DynamicSprite256, PaletteMulti, PaletteSwap, PaletteVram, PaletteVramMulti,
     PaletteVramSingle, Size, Sprite, SpriteVram, Tag, include_aseprite,
 };
 
 pub use affine::AffineMatrixObject;
+pub use metasprite::{MetaObject, MetaSprite, MetaSpritePiece, MetaTag};
 pub use unmanaged::{AffineMode, GraphicsMode, Object, ObjectAffine};
 pub(crate) use unmanaged::{Oam, OamFrame};
 
@@ -0,0 +1,382 @@
+use agb_fixnum::Vector2D;
+use alloc::vec::Vec;
+
+use crate::display::{GraphicsFrame, Priority};
+
+use super::{GraphicsMode, Object, PaletteVram, Sprite, sprites::Direction};
+
+/// A single hardware sprite which makes up part of a [`MetaSprite`].
+pub struct MetaSpritePiece {
+    sprite: &'static Sprite,
+    x: u16,
+    y: u16,
+}
+
+impl MetaSpritePiece {
+    #[doc(hidden)]
+    /// Creates a piece from its sprite and offset from the top left of the
+    /// meta sprite. Used internally by [include_aseprite](super::include_aseprite)
+    /// and should generally not be used outside it.
+    #[must_use]
+    pub const fn new(sprite: &'static Sprite, x: u16, y: u16) -> Self {
+        Self { sprite, x, y }
+    }
+
+    /// The sprite used for this piece
+    #[must_use]
+    pub const fn sprite(&self) -> &'static Sprite {
+        self.sprite
+    }
+
+    /// The offset of this piece from the top left of the meta sprite
+    #[must_use]
+    pub fn offset(&self) -> Vector2D<i32> {
+        Vector2D::new(self.x.into(), self.y.into())
+    }
+}
+
+/// A frame which is too large, or the wrong shape, to be displayed by a single
+/// hardware sprite. It is made up of several [`MetaSpritePiece`]s which are
+/// shown together using a [`MetaObject`].
+///
+/// Fully transparent areas of the frame have no pieces, so a mostly empty
+/// frame costs very little sprite memory.
+pub struct MetaSprite {
+    pieces: &'static [MetaSpritePiece],
+    width: u16,
+    height: u16,
+}
+
+impl MetaSprite {
+    #[doc(hidden)]
+    /// Creates a meta sprite from its pieces. Used internally by
+    /// [include_aseprite](super::include_aseprite) and should generally not be
+    /// used outside it.
+    #[must_use]
+    pub const fn new(pieces: &'static [MetaSpritePiece], width: u16, height: u16) -> Self {
+        Self {
+            pieces,
+            width,
+            height,
+        }
+    }
+
+    /// The pieces which make up this meta sprite
+    #[must_use]
+    pub const fn pieces(&self) -> &'static [MetaSpritePiece] {
+        self.pieces
+    }
+
+    /// The width and height of the original frame in pixels
+    #[must_use]
+    pub fn size(&self) -> Vector2D<i32> {
+        Vector2D::new(self.width.into(), self.height.into())
+    }
+}
+
+/// A sequence of meta sprites from aseprite. This behaves the same as
+/// [`Tag`](super::Tag) but for files included with the `meta` option.
+pub struct MetaTag {
+    sprites: &'static [MetaSprite],
+    direction: Direction,
+}
+
+unsafe impl Sync for MetaTag {}
+
+impl MetaTag {
+    /// The individual meta sprites that make up the animation themselves.
+    #[must_use]
+    pub fn sprites(&self) -> &'static [MetaSprite] {
+        self.sprites
+    }
+
+    /// A single meta sprite referred to by index in the animation sequence.
+    #[must_use]
+    pub const fn sprite(&self, idx: usize) -> &'static MetaSprite {
+        &self.sprites[idx]
+    }
+
+    /// A meta sprite that follows the animation sequence. See
+    /// [`Tag::animation_sprite`](super::Tag::animation_sprite).
+    #[inline]
+    #[must_use]
+    pub fn animation_sprite(&self, idx: usize) -> &'static MetaSprite {
+        self.sprite(self.direction.animation_index(idx, self.sprites.len()))
+    }
+
+    /// Takes an index shifts by the divider, if the index is out of bounds of
+    /// the MetaTag then it will be reset to zero. See
+    /// [`Tag::animation_frame`](super::Tag::animation_frame).
+    pub fn animation_frame(&self, idx: &mut usize, divider: u32) -> &'static MetaSprite {
+        &self.sprites[self
+            .direction
+            .animation_frame_index(idx, divider, self.sprites.len())]
+    }
+
+    #[doc(hidden)]
+    /// Creates a new meta tag from it's constituent parts. Used internally by
+    /// [include_aseprite](super::include_aseprite) and should generally not be
+    /// used elsewhere.
+    #[must_use]
+    pub const fn new(sprites: &'static [MetaSprite], direction: usize) -> Self {
+        Self {
+            sprites,
+            direction: Direction::from_usize(direction),
+        }
+    }
+}
+
+/// Displays a [`MetaSprite`] using one [`Object`] per piece. The position,
+/// flip, priority and graphics mode are shared by every piece.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::object::MetaObject, include_aseprite};
+///
+/// include_aseprite!(
+///     mod sprites,
+///     meta "examples/gfx/crab.aseprite"
+/// );
+///
+/// # fn test(gba: &mut agb::Gba) {
+/// let mut gfx = gba.graphics.get();
+///
+/// let mut crab = MetaObject::new(sprites::IDLE.sprite(0));
+/// crab.set_pos((50, 20)).set_hflip(true);
+///
+/// let mut frame = gfx.frame();
+/// crab.show(&mut frame);
+/// frame.commit();
+/// # }
+/// ```
+pub struct MetaObject {
+    meta_sprite: &'static MetaSprite,
+    objects: Vec<Object>,
+    position: Vector2D<i32>,
+    hflip: bool,
+    vflip: bool,
+    priority: Priority,
+    graphics_mode: GraphicsMode,
+    palette: Option<PaletteVram>,
+}
+
+impl MetaObject {
+    #[must_use]
+    /// Creates a meta object showing the given meta sprite. This loads every
+    /// piece into sprite vram.
+    pub fn new(meta_sprite: &'static MetaSprite) -> Self {
+        let mut object = Self {
+            meta_sprite,
+            objects: Vec::new(),
+            position: Vector2D::new(0, 0),
+            hflip: false,
+            vflip: false,
+            priority: Priority::default(),
+            graphics_mode: GraphicsMode::Normal,
+            palette: None,
+        };
+
+        object.set_meta_sprite(meta_sprite);
+        object
+    }
+
+    /// Show every piece of the meta object on the current frame. Pieces take up
+    /// one object slot each, see [`Object::show`] for the ordering rules.
+    pub fn show(&self, frame: &mut GraphicsFrame) {
+        for object in &self.objects {
+            object.show(frame);
+        }
+    }
+
+    /// Sets the current meta sprite, keeping the position, flip and priority.
+    pub fn set_meta_sprite(&mut self, meta_sprite: &'static MetaSprite) -> &mut Self {
+        self.meta_sprite = meta_sprite;
+
+        self.objects.clear();
+        self.objects
+            .extend(meta_sprite.pieces().iter().map(|piece| {
+                let mut object = Object::new(piece.sprite());
+                object
+                    .set_priority(self.priority)
+                    .set_graphics_mode(self.graphics_mode);
+                if let Some(palette) = &self.palette {
+                    object.set_palette(palette.clone());
+                }
+                object
+            }));
+
+        self.update_pieces();
+
+        self
+    }
+
+    /// Returns the current meta sprite
+    #[must_use]
+    pub fn meta_sprite(&self) -> &'static MetaSprite {
+        self.meta_sprite
+    }
+
+    /// Sets the position of the top left of the meta object.
+    /// Use [pos](Self::pos) to get the value
+    pub fn set_pos(&mut self, position: impl Into<Vector2D<i32>>) -> &mut Self {
+        self.position = position.into();
+        self.update_pieces();
+
+        self
+    }
+
+    /// Returns the position of the meta object
+    /// Use [set_pos](Self::set_pos) to set the value
+    #[must_use]
+    pub fn pos(&self) -> Vector2D<i32> {
+        self.position
+    }
+
+    /// Sets the horizontal flip of the whole meta object, including the
+    /// arrangement of the pieces.
+    /// Use [hflip](Self::hflip) to get the value
+    pub fn set_hflip(&mut self, flip: bool) -> &mut Self {
+        self.hflip = flip;
+        self.update_pieces();
+
+        self
+    }
+
+    /// Returns the horizontal flip
+    /// Use [set_hflip](Self::set_hflip) to set the value
+    #[must_use]
+    pub fn hflip(&self) -> bool {
+        self.hflip
+    }
+
+    /// Sets the vertical flip of the whole meta object, including the
+    /// arrangement of the pieces.
+    /// Use [vflip](Self::vflip) to get the value
+    pub fn set_vflip(&mut self, flip: bool) -> &mut Self {
+        self.vflip = flip;
+        self.update_pieces();
+
+        self
+    }
+
+    /// Returns the vertical flip
+    /// Use [set_vflip](Self::set_vflip) to set the value
+    #[must_use]
+    pub fn vflip(&self) -> bool {
+        self.vflip
+    }
+
+    /// Sets the priority of every piece relative to the backgrounds priority.
+    /// Use [priority](Self::priority) to get the value
+    pub fn set_priority(&mut self, priority: Priority) -> &mut Self {
+        self.priority = priority;
+        for object in &mut self.objects {
+            object.set_priority(priority);
+        }
+
+        self
+    }
+
+    /// Returns the priority of the meta object
+    /// Use [set_priority](Self::set_priority) to set the value
+    #[must_use]
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Sets the graphics mode of every piece
+    pub fn set_graphics_mode(&mut self, mode: GraphicsMode) -> &mut Self {
+        self.graphics_mode = mode;
+        for object in &mut self.objects {
+            object.set_graphics_mode(mode);
+        }
+
+        self
+    }
+
+    /// Draws every piece using `palette` instead of their own palettes until
+    /// [`clear_palette`](Self::clear_palette) is called. See
+    /// [`Object::set_palette`].
+    pub fn set_palette(&mut self, palette: impl Into<PaletteVram>) -> &mut Self {
+        let palette = palette.into();
+        for object in &mut self.objects {
+            object.set_palette(palette.clone());
+        }
+        self.palette = Some(palette);
+
+        self
+    }
+
+    /// Goes back to drawing every piece with its own palette
+    pub fn clear_palette(&mut self) -> &mut Self {
+        self.palette = None;
+        for object in &mut self.objects {
+            object.clear_palette();
+        }
+
+        self
+    }
+
+    fn update_pieces(&mut self) {
+        let size = self.meta_sprite.size();
+
+        for (object, piece) in self.objects.iter_mut().zip(self.meta_sprite.pieces()) {
+            let (width, height) = piece.sprite().size().to_width_height();
+            let mut offset = piece.offset();
+
+            if self.hflip {
+                offset.x = size.x - offset.x - width as i32;
+            }
+            if self.vflip {
+                offset.y = size.y - offset.y - height as i32;
+            }
+
+            object
+                .set_pos(self.position + offset)
+                .set_hflip(self.hflip)
+                .set_vflip(self.vflip);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::include_aseprite;
+
+    use super::*;
+
+    #[test_case]
+    fn meta_object_flips_pieces(_gba: &mut crate::Gba) {
+        include_aseprite!(
+            mod sprites,
+            meta "examples/gfx/crab.aseprite",
+        );
+
+        let meta_sprite = sprites::IDLE.sprite(0);
+
+        let mut object = MetaObject::new(meta_sprite);
+        object.set_pos((10, 20));
+
+        let unflipped: Vec<_> = object.objects.iter().map(Object::pos).collect();
+
+        object.set_hflip(true).set_vflip(true);
+        let size = meta_sprite.size();
+
+        for ((object, piece), unflipped) in object
+            .objects
+            .iter()
+            .zip(meta_sprite.pieces())
+            .zip(unflipped)
+        {
+            let (width, height) = piece.sprite().size().to_width_height();
+            assert_eq!(
+                object.pos(),
+                Vector2D::new(
+                    20 + size.x - unflipped.x - width as i32,
+                    40 + size.y - unflipped.y - height as i32
+                )
+            );
+        }
+    }
+}
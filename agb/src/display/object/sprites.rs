@@ -1,9 +1,12 @@
+mod palette_swap;
 mod sprite;
 mod sprite_allocator;
 
 const BYTES_PER_TILE_4BPP: usize = 32;
 const BYTES_PER_TILE_8BPP: usize = 64;
 
+pub use palette_swap::PaletteSwap;
+pub(crate) use sprite::{Direction, Trim};
 pub use sprite::{PaletteMulti, Size, Sprite, Tag, include_aseprite};
 pub use sprite_allocator::{
     DynamicSprite16, DynamicSprite256, PaletteVram, PaletteVramMulti, PaletteVramSingle, SpriteVram,
@@ -0,0 +1,163 @@
+use core::ptr;
+
+use crate::display::Palette16;
+
+use super::{
+    SpriteVram,
+    sprite::{Palette, PaletteMulti, Sprite},
+    sprite_allocator::SPRITE_LOADER,
+};
+
+/// An alternative set of colours for sprites, generated at build time with
+/// the `palette_swap` option of [`include_aseprite!`](super::include_aseprite).
+///
+/// Each swapped palette lines up index for index with the palette it replaces,
+/// so any sprite from the same macro call can be shown in the swapped colours
+/// without any extra sprite data.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::object::Object, include_aseprite};
+///
+/// include_aseprite!(
+///     mod sprites,
+///     "examples/gfx/crab.aseprite",
+///     palette_swap BLUE { "f66700" => "0067f6", "cb5400" => "0054cb" }
+/// );
+///
+/// # fn test(gba: &mut agb::Gba) {
+/// let blue_crab = Object::new(sprites::BLUE.sprite(sprites::IDLE.sprite(0)));
+/// # }
+/// ```
+pub struct PaletteSwap {
+    kind: PaletteSwapKind,
+}
+
+enum PaletteSwapKind {
+    Single {
+        base: &'static [Palette16],
+        swapped: &'static [Palette16],
+    },
+    Multi {
+        base: &'static PaletteMulti,
+        swapped: &'static PaletteMulti,
+    },
+}
+
+impl PaletteSwap {
+    #[doc(hidden)]
+    /// Creates a palette swap for 16 colour sprites. Used internally by
+    /// [include_aseprite](super::include_aseprite) and should generally not
+    /// be used outside it.
+    #[must_use]
+    pub const fn new(base: &'static [Palette16], swapped: &'static [Palette16]) -> Self {
+        assert!(base.len() == swapped.len());
+
+        Self {
+            kind: PaletteSwapKind::Single { base, swapped },
+        }
+    }
+
+    #[doc(hidden)]
+    /// Creates a palette swap for 256 colour sprites. Used internally by
+    /// [include_aseprite_256](crate::include_aseprite_256) and should generally
+    /// not be used outside it.
+    #[must_use]
+    pub const fn new_multi(base: &'static PaletteMulti, swapped: &'static PaletteMulti) -> Self {
+        assert!(base.palettes().len() == swapped.palettes().len());
+
+        Self {
+            kind: PaletteSwapKind::Multi { base, swapped },
+        }
+    }
+
+    /// The swapped palettes. These are in the same order as the palettes used
+    /// by the original sprites.
+    #[must_use]
+    pub fn palettes(&self) -> &'static [Palette16] {
+        match self.kind {
+            PaletteSwapKind::Single { swapped, .. } => swapped,
+            PaletteSwapKind::Multi { swapped, .. } => swapped.palettes(),
+        }
+    }
+
+    fn swapped_palette(&self, palette: Palette) -> Option<Palette> {
+        match (&self.kind, palette) {
+            (PaletteSwapKind::Single { base, swapped }, Palette::Single(palette)) => base
+                .iter()
+                .position(|base| ptr::eq(base, palette))
+                .map(|index| Palette::Single(&swapped[index])),
+            (PaletteSwapKind::Multi { base, swapped }, Palette::Multi(palette)) => {
+                ptr::eq(*base, palette).then_some(Palette::Multi(swapped))
+            }
+            _ => None,
+        }
+    }
+
+    /// Loads the sprite into vram using the swapped colours. The sprite data
+    /// is shared with any other copies of the same sprite, only the palette
+    /// differs.
+    ///
+    /// # Panics
+    ///
+    /// If the sprite wasn't included in the same macro call as this palette
+    /// swap, or there isn't space in vram for the sprite or palette.
+    #[must_use]
+    pub fn sprite(&self, sprite: &'static Sprite) -> SpriteVram {
+        let palette = self
+            .swapped_palette(sprite.palette)
+            .expect("sprite should be from the same include as the palette swap");
+
+        unsafe { SPRITE_LOADER.sprite_with_palette(sprite, palette) }
+            .expect("have space for sprites")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::include_aseprite;
+
+    use super::*;
+
+    #[test_case]
+    fn palette_swap_replaces_colours(_gba: &mut crate::Gba) {
+        use crate::display::Rgb;
+
+        include_aseprite!(
+            mod sprites,
+            "examples/gfx/crab.aseprite",
+            palette_swap BLUE { "f66700" => "0067f6" },
+        );
+
+        let orange = Rgb::new(0xf6, 0x67, 0x00).to_rgb15();
+        let blue = Rgb::new(0x00, 0x67, 0xf6).to_rgb15();
+
+        let sprite = sprites::IDLE.sprite(0);
+        let Palette::Single(palette) = sprite.palette else {
+            panic!("crab should use a 16 colour palette");
+        };
+
+        let Some(Palette::Single(swapped)) = sprites::BLUE.swapped_palette(sprite.palette) else {
+            panic!("crab should have a swapped palette");
+        };
+
+        assert!(!ptr::eq(swapped, palette));
+
+        let index = palette
+            .colours
+            .iter()
+            .position(|&colour| colour == orange)
+            .expect("crab should contain orange");
+        assert!(!palette.colours.contains(&blue));
+        assert_eq!(swapped.colours[index], blue);
+
+        for (i, (original, swapped)) in palette.colours.iter().zip(&swapped.colours).enumerate() {
+            if i != index {
+                assert_eq!(original, swapped);
+            }
+        }
+
+        let _ = sprites::BLUE.sprite(sprite);
+    }
+}
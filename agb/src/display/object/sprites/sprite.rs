@@ -1,5 +1,7 @@
 use core::alloc::Layout;
 
+use agb_fixnum::Vector2D;
+
 use crate::display::palette16::Palette16;
 
 use super::{BYTES_PER_TILE_4BPP, BYTES_PER_TILE_8BPP};
@@ -9,6 +11,48 @@ pub struct Sprite {
     pub(crate) palette: Palette,
     pub(crate) data: &'static [u8],
     pub(crate) size: Size,
+    pub(crate) trim: Trim,
+}
+
+/// Where a sprite sits within the aseprite frame it was created from. Sprites
+/// which haven't been trimmed fill their entire frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Trim {
+    x: u16,
+    y: u16,
+    frame_width: u16,
+    frame_height: u16,
+}
+
+impl Trim {
+    pub(crate) const fn untrimmed(size: Size) -> Self {
+        let (width, height) = size.to_width_height();
+        Self {
+            x: 0,
+            y: 0,
+            frame_width: width as u16,
+            frame_height: height as u16,
+        }
+    }
+
+    /// The offset of a sprite of the given size from the top left of the
+    /// frame. Flipping the sprite also flips where it sits within the frame.
+    pub(crate) fn offset(self, size: Size, hflip: bool, vflip: bool) -> Vector2D<i32> {
+        let (width, height) = size.to_width_height();
+
+        let x = if hflip {
+            i32::from(self.frame_width) - i32::from(self.x) - width as i32
+        } else {
+            i32::from(self.x)
+        };
+        let y = if vflip {
+            i32::from(self.frame_height) - i32::from(self.y) - height as i32
+        } else {
+            i32::from(self.y)
+        };
+
+        Vector2D::new(x, y)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -74,6 +118,7 @@ impl Sprite {
             palette: Palette::Single(palette),
             data,
             size,
+            trim: Trim::untrimmed(size),
         }
     }
 
@@ -94,6 +139,24 @@ impl Sprite {
             palette: Palette::Multi(palettes),
             data,
             size,
+            trim: Trim::untrimmed(size),
+        }
+    }
+
+    #[doc(hidden)]
+    /// Records where a trimmed sprite sits within its original frame, used
+    /// internally by [include_aseprite] and should generally not be used
+    /// outside it.
+    #[must_use]
+    pub const fn with_trim(self, x: u16, y: u16, frame_width: u16, frame_height: u16) -> Self {
+        Self {
+            trim: Trim {
+                x,
+                y,
+                frame_width,
+                frame_height,
+            },
+            ..self
         }
     }
 
@@ -102,6 +165,15 @@ impl Sprite {
     pub fn size(&self) -> Size {
         self.size
     }
+
+    #[must_use]
+    /// The offset of the sprite from the top left of the frame it was created
+    /// from. This is only non zero for sprites included with `trim`, and is
+    /// applied automatically when the sprite is shown using an
+    /// [`Object`](crate::display::object::Object).
+    pub fn offset(&self) -> Vector2D<i32> {
+        self.trim.offset(self.size, false, false)
+    }
 }
 
 /// The sizes of sprite supported by the GBA.
@@ -218,12 +290,85 @@ macro_rules! align_bytes {
 ///     "examples/gfx/small_item.aseprite"
 /// );
 /// ```
+///
+/// For frames which are larger than 64x64, or which aren't a valid sprite size,
+/// you can put `meta` before a file path. Each frame is then split into a set of
+/// hardware sized pieces, skipping any fully transparent 8x8 tiles. Tags from
+/// these files are generated as [`MetaTag`](crate::display::object::MetaTag)s rather than
+/// [`Tag`]s, and each frame can be displayed with a
+/// [`MetaObject`](crate::display::object::MetaObject).
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::include_aseprite;
+/// include_aseprite!(
+///     mod sprites,
+///     meta "examples/gfx/crab.aseprite"
+/// );
+///
+/// use sprites::IDLE;
+/// ```
+///
+/// Putting `trim` before a file path trims each frame down to its content and
+/// picks the smallest [Size] which fits, saving sprite vram and rom for mostly
+/// transparent frames. The position of the content within the frame is recorded
+/// as the [`Sprite::offset`], which [`Object`](crate::display::object::Object)
+/// applies automatically so the trimmed sprite appears in the same place as
+/// the original frame would.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::include_aseprite;
+/// include_aseprite!(
+///     mod sprites,
+///     trim "examples/gfx/crab.aseprite"
+/// );
+/// ```
+///
+/// If a file has more colours than fit in a palette, putting `quantise` before
+/// the file path reduces the colours of all its frames to a shared set of 15.
+/// Use `dither_ordered` or `dither_floyd_steinberg` instead to also dither the
+/// result. These can be combined with the other options.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::include_aseprite;
+/// include_aseprite!(
+///     mod sprites,
+///     dither_ordered trim "examples/gfx/crab.aseprite"
+/// );
+/// ```
+///
+/// To recolour sprites at runtime, add a `palette_swap` entry naming a
+/// [`PaletteSwap`](crate::display::object::PaletteSwap) to generate. This is
+/// either a base file and a recoloured copy of it with the same frames, or a
+/// list of colours to replace.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::include_aseprite;
+/// include_aseprite!(
+///     mod sprites,
+///     "examples/gfx/crab.aseprite",
+///     palette_swap GREEN { "f66700" => "00f667", "cb5400" => "00cb54" }
+/// );
+///
+/// # fn test() {
+/// let green_crab = sprites::GREEN.sprite(sprites::IDLE.sprite(0));
+/// # }
+/// ```
 #[macro_export]
 macro_rules! include_aseprite {
     ($v: vis mod $module: ident, $($aseprite_args: tt)*) => {
         $v mod $module {
             #[allow(unused_imports)]
-            use $crate::display::object::{Size, Sprite, Tag};
+            use $crate::display::object::{
+                MetaSprite, MetaSpritePiece, MetaTag, PaletteSwap, Size, Sprite, Tag,
+            };
             use $crate::display::{Palette16, Rgb15};
             use $crate::align_bytes;
 
@@ -249,14 +394,20 @@ macro_rules! include_aseprite {
 /// ```
 ///
 /// Like [`include_aseprite!`], you can specify a target sprite size before a
-/// file path to split frames into smaller sprites. See [`include_aseprite!`]
-/// for details.
+/// file path to split frames into smaller sprites, `meta` to generate meta
+/// sprites, `trim` to trim frames to their content or `quantise` to reduce the
+/// number of colours, along with `palette_swap` entries. See [`include_aseprite!`]
+/// for details. Quantised files are reduced to at most 255 colours each rather
+/// than 256, since the first colour of the palette is reserved for transparency.
+/// Transparent pixels don't count towards the 255 and stay transparent.
 #[macro_export]
 macro_rules! include_aseprite_256 {
     ($v: vis mod $module: ident, $($aseprite_args: tt)*) => {
         $v mod $module {
             #[allow(unused_imports)]
-            use $crate::display::object::{Size, Sprite, Tag, PaletteMulti};
+            use $crate::display::object::{
+                MetaSprite, MetaSpritePiece, MetaTag, PaletteMulti, PaletteSwap, Size, Sprite, Tag,
+            };
             use $crate::display::{Palette16, Rgb15};
             use $crate::align_bytes;
 
@@ -268,14 +419,14 @@ macro_rules! include_aseprite_256 {
 pub use include_aseprite;
 
 #[derive(Clone, Copy)]
-enum Direction {
+pub(crate) enum Direction {
     Forward,
     Backward,
     PingPong,
 }
 
 impl Direction {
-    const fn from_usize(a: usize) -> Self {
+    pub(crate) const fn from_usize(a: usize) -> Self {
         match a {
             0 => Direction::Forward,
             1 => Direction::Backward,
@@ -283,6 +434,49 @@ impl Direction {
             _ => panic!("Invalid direction, this is a bug in image converter or agb"),
         }
     }
+
+    pub(crate) fn animation_index(self, idx: usize, len: usize) -> usize {
+        let len_sub_1 = len - 1;
+        match self {
+            Direction::Forward => idx % len,
+            Direction::Backward => len_sub_1 - (idx % len),
+            Direction::PingPong => {
+                (((idx + len_sub_1) % (len_sub_1 * 2)) as isize - len_sub_1 as isize).unsigned_abs()
+            }
+        }
+    }
+
+    pub(crate) fn animation_frame_index(self, idx: &mut usize, divider: u32, len: usize) -> usize {
+        let divided = *idx >> divider;
+        match self {
+            Direction::Forward => {
+                if divided >= len {
+                    *idx = 0;
+                    0
+                } else {
+                    divided
+                }
+            }
+            Direction::Backward => {
+                if divided >= len {
+                    *idx = 0;
+                    len - 1
+                } else {
+                    len - 1 - divided
+                }
+            }
+            Direction::PingPong => {
+                if divided >= (len - 1) * 2 {
+                    *idx = 0;
+                    0
+                } else if divided >= len {
+                    (len - 1) * 2 - divided
+                } else {
+                    divided
+                }
+            }
+        }
+    }
 }
 
 /// A sequence of sprites from aseprite.
@@ -317,52 +511,16 @@ impl Tag {
     #[inline]
     #[must_use]
     pub fn animation_sprite(&self, idx: usize) -> &'static Sprite {
-        let len_sub_1 = self.sprites.len() - 1;
-        match self.direction {
-            Direction::Forward => self.sprite(idx % self.sprites.len()),
-            Direction::Backward => self.sprite(len_sub_1 - (idx % self.sprites.len())),
-            Direction::PingPong => self.sprite(
-                (((idx + len_sub_1) % (len_sub_1 * 2)) as isize - len_sub_1 as isize)
-                    .unsigned_abs(),
-            ),
-        }
+        self.sprite(self.direction.animation_index(idx, self.sprites.len()))
     }
 
     /// Takes an index shifts by the divider, if the index is out of bounds of
     /// the Tag then it will be reset to zero. This is incredibly useful for
     /// animating sprites efficiently.
     pub fn animation_frame(&self, idx: &mut usize, divider: u32) -> &'static Sprite {
-        let divided = *idx >> divider;
-        let idx = match self.direction {
-            Direction::Forward => {
-                if divided >= self.sprites.len() {
-                    *idx = 0;
-                    0
-                } else {
-                    divided
-                }
-            }
-            Direction::Backward => {
-                if divided >= self.sprites.len() {
-                    *idx = 0;
-                    self.sprites.len() - 1
-                } else {
-                    self.sprites.len() - 1 - divided
-                }
-            }
-            Direction::PingPong => {
-                if divided >= (self.sprites.len() - 1) * 2 {
-                    *idx = 0;
-                    0
-                } else if divided >= self.sprites.len() {
-                    (self.sprites.len() - 1) * 2 - divided
-                } else {
-                    divided
-                }
-            }
-        };
-
-        &self.sprites[idx]
+        &self.sprites[self
+            .direction
+            .animation_frame_index(idx, divider, self.sprites.len())]
     }
 
     #[doc(hidden)]
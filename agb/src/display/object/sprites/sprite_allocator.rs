@@ -113,8 +113,13 @@ impl SpriteLoaderInner {
         }
     }
 
-    fn try_allocate_sprite(&mut self, sprite: &'static Sprite) -> Result<SpriteVram, LoaderError> {
-        let palette = self.try_allocate_palette(sprite.palette)?;
+    fn try_allocate_sprite(
+        &mut self,
+        sprite: &'static Sprite,
+        palette: Palette,
+    ) -> Result<SpriteVram, LoaderError> {
+        let palette = self.try_allocate_palette(palette)?;
+        let trim = sprite.trim;
         let sprite = match self.try_allocate_sprite_inner(sprite) {
             Ok(sprite) => sprite,
             Err(_) => {
@@ -123,7 +128,7 @@ impl SpriteLoaderInner {
             }
         };
 
-        Ok(SpriteVram::new(sprite, palette))
+        Ok(SpriteVram::new(sprite, palette).with_trim(trim))
     }
 }
 
@@ -138,7 +143,16 @@ impl SpriteLoader {
     }
 
     pub unsafe fn sprite(&self, sprite: &'static Sprite) -> Result<SpriteVram, LoaderError> {
-        unsafe { self.with(|x| x.try_allocate_sprite(sprite)) }
+        unsafe { self.with(|x| x.try_allocate_sprite(sprite, sprite.palette)) }
+    }
+
+    /// Loads the sprite's tiles, but displays them using a different palette
+    pub unsafe fn sprite_with_palette(
+        &self,
+        sprite: &'static Sprite,
+        palette: Palette,
+    ) -> Result<SpriteVram, LoaderError> {
+        unsafe { self.with(|x| x.try_allocate_sprite(sprite, palette)) }
     }
 
     pub unsafe fn palette(&self, palette: Palette) -> Result<PaletteVram, LoaderError> {
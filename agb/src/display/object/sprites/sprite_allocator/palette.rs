@@ -4,9 +4,11 @@ use crate::{
     ExternalAllocator,
     agb_alloc::single_allocator::create_allocator_arena,
     display::{
+        Rgb15,
         object::{PaletteMulti, sprites::sprite::Palette},
         palette16::Palette16,
     },
+    memory_mapped::MemoryMapped1DArray,
     refcount::{RefCount, RefCountInner},
 };
 
@@ -42,6 +44,8 @@ impl Drop for MultiPaletteAllocation {
 }
 
 const PALETTE_VRAM: *mut [Palette16; 16] = PALETTE_SPRITE as *mut _;
+const PALETTE_VRAM_COLOURS: MemoryMapped1DArray<Rgb15, 256> =
+    unsafe { MemoryMapped1DArray::new(PALETTE_SPRITE) };
 
 impl PaletteAllocator {
     const fn new() -> Self {
@@ -121,12 +125,39 @@ static PALETTE_ALLOCATOR: PaletteAllocator = PaletteAllocator::new();
 
 #[derive(Debug)]
 #[repr(align(4))]
-#[expect(dead_code, reason = "the drop implementation is used and is important")]
 enum PaletteAllocation {
     Single(SinglePaletteAllocation),
     Multi(MultiPaletteAllocation),
 }
 
+impl PaletteAllocation {
+    /// The index of the first colour in sprite palette ram, and the number of
+    /// colours in this allocation
+    fn colour_range(&self) -> (usize, usize) {
+        match self {
+            PaletteAllocation::Single(allocation) => (allocation.0 as usize * 16, 16),
+            PaletteAllocation::Multi(allocation) => (
+                allocation.0.trailing_zeros() as usize * 16,
+                allocation.0.count_ones() as usize * 16,
+            ),
+        }
+    }
+
+    fn set_colour(&self, index: usize, colour: Rgb15) {
+        let (start, len) = self.colour_range();
+        assert!(index < len, "colour index {index} out of range for palette");
+
+        PALETTE_VRAM_COLOURS.set(start + index, colour);
+    }
+
+    fn colour(&self, index: usize) -> Rgb15 {
+        let (start, len) = self.colour_range();
+        assert!(index < len, "colour index {index} out of range for palette");
+
+        PALETTE_VRAM_COLOURS.get(start + index)
+    }
+}
+
 type RefCountedAllocation = RefCount<PaletteAllocation, PaletteArena>;
 
 /// A palette containing 16 colours that is currently allocated to vram. To use
@@ -164,6 +195,53 @@ impl PaletteVramSingle {
                 .unwrap_unchecked()
         }
     }
+
+    /// Allocates a palette where every colour is `colour`. Showing a sprite
+    /// with this palette draws it as a solid silhouette, which is useful for
+    /// flashing a sprite when it takes damage.
+    ///
+    /// ```rust,no_run
+    /// # #![no_std]
+    /// # #![no_main]
+    /// use agb::display::{Rgb15, object::{Object, PaletteVramSingle}};
+    /// # fn test(crab: &mut Object) {
+    /// let flash = PaletteVramSingle::try_allocate_solid(Rgb15::WHITE).unwrap();
+    ///
+    /// crab.set_palette(flash);
+    /// // some frames later
+    /// crab.clear_palette();
+    /// # }
+    /// ```
+    pub fn try_allocate_solid(colour: Rgb15) -> Result<Self, LoaderError> {
+        Self::try_allocate_new(&Palette16::new([colour; 16]))
+    }
+
+    /// Sets the colour at `index` in this palette. The change is visible
+    /// immediately on every sprite using this palette, including every sprite
+    /// which shares a palette allocated with [`new`](Self::new). Allocate a
+    /// palette with [`try_allocate_new`](Self::try_allocate_new) if you only
+    /// want to change some sprites.
+    ///
+    /// Index must be less than 16 or this function will panic.
+    pub fn set_colour(&self, index: usize, colour: Rgb15) {
+        self.0.set_colour(index, colour);
+    }
+
+    /// Gets the colour at `index` in this palette as it currently is in vram.
+    ///
+    /// Index must be less than 16 or this function will panic.
+    #[must_use]
+    pub fn colour(&self, index: usize) -> Rgb15 {
+        self.0.colour(index)
+    }
+
+    /// Replaces every colour in this palette. See [`set_colour`](Self::set_colour)
+    /// for which sprites are affected.
+    pub fn set_palette(&self, palette: &Palette16) {
+        for (index, &colour) in palette.colours.iter().enumerate() {
+            self.set_colour(index, colour);
+        }
+    }
 }
 
 /// A palette that can contain more than 16 colours allocated to vram. To use
@@ -201,6 +279,26 @@ impl PaletteVramMulti {
                 .unwrap_unchecked()
         }
     }
+
+    /// Sets the colour at `index` in this palette, where 16 colours from the
+    /// start of the palette is the first colour of the second 16 colour
+    /// palette. The change is visible immediately on every sprite using this
+    /// palette.
+    ///
+    /// Index must be less than 16 times the number of palettes or this
+    /// function will panic.
+    pub fn set_colour(&self, index: usize, colour: Rgb15) {
+        self.0.set_colour(index, colour);
+    }
+
+    /// Gets the colour at `index` in this palette as it currently is in vram.
+    ///
+    /// Index must be less than 16 times the number of palettes or this
+    /// function will panic.
+    #[must_use]
+    pub fn colour(&self, index: usize) -> Rgb15 {
+        self.0.colour(index)
+    }
 }
 
 /// A single or multi palette allocated to vram. This is reference counted and
@@ -6,7 +6,10 @@ use crate::{
         block_allocator::BlockAllocator, bump_allocator::StartEnd, impl_zst_allocator,
         single_allocator::create_allocator_arena,
     },
-    display::object::{Size, Sprite, sprites::BYTES_PER_TILE_4BPP},
+    display::object::{
+        Size, Sprite,
+        sprites::{BYTES_PER_TILE_4BPP, Trim},
+    },
     refcount::{RefCount, RefCountInner},
 };
 
@@ -44,12 +47,57 @@ create_allocator_arena!(
 pub struct SpriteVram {
     sprite: SpriteVramInner,
     palette: PaletteVram,
+    trim: Trim,
 }
 
 impl SpriteVram {
     #[must_use]
     pub(crate) fn new(sprite: SpriteVramInner, palette: PaletteVram) -> Self {
-        Self { sprite, palette }
+        let trim = Trim::untrimmed(sprite.0.size);
+        Self {
+            sprite,
+            palette,
+            trim,
+        }
+    }
+
+    /// Returns a copy of this sprite which is drawn using a different palette.
+    /// The sprite data in vram is shared, so this is cheap.
+    ///
+    /// # Panics
+    ///
+    /// If the palette is a multi palette and the sprite uses 16 colours, or the
+    /// other way round.
+    #[must_use]
+    pub fn with_palette(&self, palette: impl Into<PaletteVram>) -> Self {
+        let palette = palette.into();
+        assert_eq!(
+            palette.is_multi(),
+            self.sprite.0.multi_palette,
+            "palette should have the same number of colours as the sprite"
+        );
+
+        Self {
+            sprite: self.sprite.clone(),
+            palette,
+            trim: self.trim,
+        }
+    }
+
+    /// The palette this sprite is drawn with
+    #[must_use]
+    pub fn palette(&self) -> PaletteVram {
+        self.palette.clone()
+    }
+
+    #[must_use]
+    pub(crate) fn with_trim(self, trim: Trim) -> Self {
+        Self { trim, ..self }
+    }
+
+    #[must_use]
+    pub(crate) fn trim(&self) -> Trim {
+        self.trim
     }
 
     #[must_use]
@@ -6,7 +6,9 @@ use alloc::{boxed::Box, vec, vec::Vec};
 use crate::display::{
     GraphicsFrame, Priority,
     object::{
-        AffineMatrixObject, OBJECT_ATTRIBUTE_MEMORY, affine::AffineMatrixVram, sprites::SpriteVram,
+        AffineMatrixObject, OBJECT_ATTRIBUTE_MEMORY,
+        affine::AffineMatrixVram,
+        sprites::{PaletteVram, SpriteVram},
     },
 };
 
@@ -131,6 +133,8 @@ impl Oam<'_> {
 pub struct Object {
     attributes: AttributesRegular,
     sprite: SpriteVram,
+    /// The sprite's own palette while it is replaced by [`Object::set_palette`]
+    original_palette: Option<PaletteVram>,
 }
 
 impl Object {
@@ -154,6 +158,7 @@ impl Object {
             let mut object = Object {
                 attributes: AttributesRegular::default(),
                 sprite,
+                original_palette: None,
             };
 
             if let Some(palette_location) = palette_location {
@@ -170,6 +175,7 @@ impl Object {
             object
                 .attributes
                 .set_sprite(sprite_location.idx(), shape, size);
+            object.set_pos((0, 0));
 
             object
         }
@@ -182,7 +188,9 @@ impl Object {
     /// Sets the horizontal flip, note that this only has a visible affect in Normal mode.  
     /// Use [hflip](Self::hflip) to get the value
     pub fn set_hflip(&mut self, flip: bool) -> &mut Self {
+        let position = self.pos();
         self.attributes.set_hflip(flip);
+        self.set_pos(position);
 
         self
     }
@@ -197,7 +205,9 @@ impl Object {
     /// Sets the vertical flip, note that this only has a visible affect in Normal mode.  
     /// Use [vflip](Self::vflip) to get the value
     pub fn set_vflip(&mut self, flip: bool) -> &mut Self {
+        let position = self.pos();
         self.attributes.set_vflip(flip);
+        self.set_pos(position);
 
         self
     }
@@ -224,10 +234,11 @@ impl Object {
         self.attributes.priority()
     }
 
-    /// Sets the position of the object.  
+    /// Sets the position of the object. For sprites included with `trim`, this
+    /// is the position of the top left of the original frame.  
     /// Use [pos](Self::pos) to get the value
     pub fn set_pos(&mut self, position: impl Into<Vector2D<i32>>) -> &mut Self {
-        let position = position.into();
+        let position = position.into() + self.trim_offset();
         self.attributes.set_y(position.y.rem_euclid(1 << 9) as u16);
         self.attributes.set_x(position.x.rem_euclid(1 << 9) as u16);
 
@@ -238,7 +249,17 @@ impl Object {
     /// Use [set_pos](Self::set_pos) to set the value
     #[must_use]
     pub fn pos(&self) -> Vector2D<i32> {
-        Vector2D::new(self.attributes.x() as i32, self.attributes.y() as i32)
+        let offset = self.trim_offset();
+        Vector2D::new(
+            (self.attributes.x() as i32 - offset.x).rem_euclid(1 << 9),
+            (self.attributes.y() as i32 - offset.y).rem_euclid(1 << 8),
+        )
+    }
+
+    fn trim_offset(&self) -> Vector2D<i32> {
+        self.sprite
+            .trim()
+            .offset(self.sprite.size(), self.hflip(), self.vflip())
     }
 
     fn set_sprite_attributes(&mut self, sprite: &SpriteVram) -> &mut Self {
@@ -258,12 +279,57 @@ impl Object {
         self
     }
 
-    /// Sets the current sprite for the object.
+    /// Sets the current sprite for the object. The offset of sprites included
+    /// with `trim` is applied automatically, so the position is unchanged.
     pub fn set_sprite(&mut self, sprite: impl Into<SpriteVram>) -> &mut Self {
         let sprite = sprite.into();
+        let sprite = match &mut self.original_palette {
+            Some(original_palette) => {
+                *original_palette = sprite.palette();
+                sprite.with_palette(self.sprite.palette())
+            }
+            None => sprite,
+        };
+
+        let position = self.pos();
         self.set_sprite_attributes(&sprite);
 
         self.sprite = sprite;
+        self.set_pos(position);
+
+        self
+    }
+
+    /// Draws the object using `palette` instead of its sprite's palette until
+    /// [`clear_palette`](Self::clear_palette) is called. This is kept when the
+    /// sprite is changed, so an animated object keeps flashing. See
+    /// [`PaletteVramSingle::try_allocate_solid`](crate::display::object::PaletteVramSingle::try_allocate_solid)
+    /// for a palette which draws the object as a silhouette.
+    ///
+    /// # Panics
+    ///
+    /// If the palette doesn't have the same number of colours as the sprite.
+    pub fn set_palette(&mut self, palette: impl Into<PaletteVram>) -> &mut Self {
+        let sprite = self.sprite.with_palette(palette);
+        if self.original_palette.is_none() {
+            self.original_palette = Some(self.sprite.palette());
+        }
+
+        self.set_sprite_attributes(&sprite);
+        self.sprite = sprite;
+
+        self
+    }
+
+    /// Goes back to drawing the object with its sprite's own palette after a
+    /// call to [`set_palette`](Self::set_palette).
+    pub fn clear_palette(&mut self) -> &mut Self {
+        if let Some(original_palette) = self.original_palette.take() {
+            let sprite = self.sprite.with_palette(original_palette);
+
+            self.set_sprite_attributes(&sprite);
+            self.sprite = sprite;
+        }
 
         self
     }
@@ -281,6 +347,8 @@ pub struct ObjectAffine {
     attributes: AttributesAffine,
     sprite: SpriteVram,
     matrix: AffineMatrixVram,
+    /// The sprite's own palette while it is replaced by [`ObjectAffine::set_palette`]
+    original_palette: Option<PaletteVram>,
 }
 
 impl ObjectAffine {
@@ -313,6 +381,7 @@ impl ObjectAffine {
                 attributes: AttributesAffine::new(affine_mode),
                 sprite,
                 matrix: affine_matrix.vram(),
+                original_palette: None,
             };
 
             if let Some(palette_location) = palette_location {
@@ -329,6 +398,7 @@ impl ObjectAffine {
             object
                 .attributes
                 .set_sprite(sprite_location.idx(), shape, size);
+            object.set_pos((0, 0));
 
             object
         }
@@ -369,7 +439,7 @@ impl ObjectAffine {
     /// Sets the position of the object.  
     /// Use [pos](Self::pos) to get the value
     pub fn set_pos(&mut self, position: impl Into<Vector2D<i32>>) -> &mut Self {
-        let position = position.into();
+        let position = position.into() + self.trim_offset();
         self.attributes.set_y(position.y.rem_euclid(1 << 9) as u16);
         self.attributes.set_x(position.x.rem_euclid(1 << 9) as u16);
 
@@ -380,7 +450,15 @@ impl ObjectAffine {
     /// Use [set_pos](Self::set_pos) to set the value
     #[must_use]
     pub fn pos(&self) -> Vector2D<i32> {
-        Vector2D::new(self.attributes.x() as i32, self.attributes.y() as i32)
+        let offset = self.trim_offset();
+        Vector2D::new(
+            (self.attributes.x() as i32 - offset.x).rem_euclid(1 << 9),
+            (self.attributes.y() as i32 - offset.y).rem_euclid(1 << 8),
+        )
+    }
+
+    fn trim_offset(&self) -> Vector2D<i32> {
+        self.sprite.trim().offset(self.sprite.size(), false, false)
     }
 
     fn set_sprite_attributes(&mut self, sprite: &SpriteVram) -> &mut Self {
@@ -403,9 +481,53 @@ impl ObjectAffine {
     /// Sets the current sprite for the object.
     pub fn set_sprite(&mut self, sprite: impl Into<SpriteVram>) -> &mut Self {
         let sprite = sprite.into();
+        let sprite = match &mut self.original_palette {
+            Some(original_palette) => {
+                *original_palette = sprite.palette();
+                sprite.with_palette(self.sprite.palette())
+            }
+            None => sprite,
+        };
+
+        let position = self.pos();
         self.set_sprite_attributes(&sprite);
 
         self.sprite = sprite;
+        self.set_pos(position);
+
+        self
+    }
+
+    /// Draws the object using `palette` instead of its sprite's palette until
+    /// [`clear_palette`](Self::clear_palette) is called. This is kept when the
+    /// sprite is changed, so an animated object keeps flashing. See
+    /// [`PaletteVramSingle::try_allocate_solid`](crate::display::object::PaletteVramSingle::try_allocate_solid)
+    /// for a palette which draws the object as a silhouette.
+    ///
+    /// # Panics
+    ///
+    /// If the palette doesn't have the same number of colours as the sprite.
+    pub fn set_palette(&mut self, palette: impl Into<PaletteVram>) -> &mut Self {
+        let sprite = self.sprite.with_palette(palette);
+        if self.original_palette.is_none() {
+            self.original_palette = Some(self.sprite.palette());
+        }
+
+        self.set_sprite_attributes(&sprite);
+        self.sprite = sprite;
+
+        self
+    }
+
+    /// Goes back to drawing the object with its sprite's own palette after a
+    /// call to [`set_palette`](Self::set_palette).
+    pub fn clear_palette(&mut self) -> &mut Self {
+        if let Some(original_palette) = self.original_palette.take() {
+            let sprite = self.sprite.with_palette(original_palette);
+
+            self.set_sprite_attributes(&sprite);
+            self.sprite = sprite;
+        }
 
         self
     }
@@ -455,4 +577,110 @@ mod tests {
             frame.commit();
         }
     }
+
+    #[test_case]
+    fn trimmed_sprite_offset_is_applied(_gba: &mut crate::Gba) {
+        include_aseprite!(
+            mod untrimmed,
+            "examples/gfx/crab.aseprite",
+        );
+        include_aseprite!(
+            mod trimmed,
+            trim "examples/gfx/crab.aseprite",
+        );
+
+        let (frame_width, frame_height) = untrimmed::IDLE.sprite(0).size().to_width_height();
+
+        let sprite = trimmed::IDLE.sprite(0);
+        let offset = sprite.offset();
+        let (width, height) = sprite.size().to_width_height();
+
+        let mut obj = Object::new(sprite);
+        obj.set_pos((10, 20));
+
+        assert_eq!(obj.pos(), Vector2D::new(10, 20));
+        assert_eq!(obj.attributes.x() as i32, 10 + offset.x);
+        assert_eq!(obj.attributes.y() as i32, 20 + offset.y);
+
+        obj.set_hflip(true).set_vflip(true);
+
+        assert_eq!(obj.pos(), Vector2D::new(10, 20));
+        assert_eq!(
+            obj.attributes.x() as i32,
+            10 + frame_width as i32 - offset.x - width as i32
+        );
+        assert_eq!(
+            obj.attributes.y() as i32,
+            20 + frame_height as i32 - offset.y - height as i32
+        );
+    }
+
+    #[test_case]
+    fn sprite_trimmed_on_left_and_top_is_offset(_gba: &mut crate::Gba) {
+        use crate::display::{
+            Palette16, Rgb15,
+            object::{Size, Sprite},
+        };
+
+        static PALETTE: Palette16 = Palette16::new([Rgb15::BLACK; 16]);
+        // An 8x8 sprite cut from 4 pixels in and 10 pixels down a 32x48 frame
+        static SPRITE: Sprite = unsafe {
+            Sprite::new(
+                &PALETTE,
+                crate::align_bytes!(u32, b"00000000000000000000000000000000"),
+                Size::S8x8,
+            )
+        }
+        .with_trim(4, 10, 32, 48);
+
+        assert_eq!(SPRITE.offset(), Vector2D::new(4, 10));
+
+        let mut obj = Object::new(&SPRITE);
+        obj.set_pos((10, 20));
+
+        assert_eq!(obj.pos(), Vector2D::new(10, 20));
+        assert_eq!(obj.attributes.x(), 14);
+        assert_eq!(obj.attributes.y(), 30);
+
+        obj.set_hflip(true);
+
+        assert_eq!(obj.pos(), Vector2D::new(10, 20));
+        assert_eq!(obj.attributes.x(), 10 + 32 - 4 - 8);
+        assert_eq!(obj.attributes.y(), 30);
+
+        obj.set_vflip(true);
+
+        assert_eq!(obj.pos(), Vector2D::new(10, 20));
+        assert_eq!(obj.attributes.y(), 20 + 48 - 10 - 8);
+    }
+
+    #[test_case]
+    fn palette_override_survives_sprite_changes(_gba: &mut crate::Gba) {
+        use crate::display::{Rgb15, object::PaletteVramSingle};
+
+        include_aseprite!(
+            mod sprites,
+            "examples/gfx/crab.aseprite",
+        );
+
+        let flash = PaletteVramSingle::try_allocate_solid(Rgb15::WHITE).unwrap();
+        flash.set_colour(1, Rgb15::BLACK);
+        assert_eq!(flash.colour(1), Rgb15::BLACK);
+        assert_eq!(flash.colour(2), Rgb15::WHITE);
+
+        let flash_index = flash.clone().palette().single_palette_index();
+
+        let mut obj = Object::new(sprites::IDLE.sprite(0));
+        let original_index = obj.sprite.single_palette_index();
+        assert_ne!(flash_index, original_index);
+
+        obj.set_palette(flash);
+        assert_eq!(obj.sprite.single_palette_index(), flash_index);
+
+        obj.set_sprite(sprites::IDLE.sprite(1));
+        assert_eq!(obj.sprite.single_palette_index(), flash_index);
+
+        obj.clear_palette();
+        assert_eq!(obj.sprite.single_palette_index(), original_index);
+    }
 }
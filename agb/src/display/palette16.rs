@@ -1,5 +1,6 @@
 #![warn(missing_docs)]
 use super::Rgb15;
+use crate::fixnum::Num;
 
 /// Represents a palette of 16 colours.
 ///
@@ -34,4 +35,78 @@ impl Palette16 {
     pub const fn colour(&self, index: usize) -> Rgb15 {
         self.colours[index]
     }
+
+    /// Linearly interpolates every colour towards the matching colour in
+    /// `other`. See [`Rgb15::lerp`].
+    #[must_use]
+    pub const fn lerp(&self, other: &Palette16, amount: Num<i32, 8>) -> Self {
+        let mut colours = self.colours;
+        let mut i = 0;
+        while i < colours.len() {
+            colours[i] = colours[i].lerp(other.colours[i], amount);
+            i += 1;
+        }
+
+        Self::new(colours)
+    }
+
+    /// Linearly interpolates every colour towards `colour`, which is useful
+    /// for fading to black or white. See [`Rgb15::lerp`].
+    #[must_use]
+    pub const fn fade_to(&self, colour: Rgb15, amount: Num<i32, 8>) -> Self {
+        self.lerp(&Self::new([colour; 16]), amount)
+    }
+
+    /// Adds `colour` to every colour in the palette. See [`Rgb15::blend_add`].
+    #[must_use]
+    pub const fn blend_add(&self, colour: Rgb15) -> Self {
+        let mut colours = self.colours;
+        let mut i = 0;
+        while i < colours.len() {
+            colours[i] = colours[i].blend_add(colour);
+            i += 1;
+        }
+
+        Self::new(colours)
+    }
+
+    /// Multiplies every colour in the palette by `colour`, which is useful for
+    /// tinting. See [`Rgb15::blend_multiply`].
+    #[must_use]
+    pub const fn blend_multiply(&self, colour: Rgb15) -> Self {
+        let mut colours = self.colours;
+        let mut i = 0;
+        while i < colours.len() {
+            colours[i] = colours[i].blend_multiply(colour);
+            i += 1;
+        }
+
+        Self::new(colours)
+    }
+
+    /// Converts every colour in the palette to grey. See [`Rgb15::greyscale`].
+    #[must_use]
+    pub const fn greyscale(&self) -> Self {
+        let mut colours = self.colours;
+        let mut i = 0;
+        while i < colours.len() {
+            colours[i] = colours[i].greyscale();
+            i += 1;
+        }
+
+        Self::new(colours)
+    }
+
+    /// Rotates the hue of every colour in the palette. See [`Rgb15::hue_shift`].
+    #[must_use]
+    pub const fn hue_shift(&self, amount: u8) -> Self {
+        let mut colours = self.colours;
+        let mut i = 0;
+        while i < colours.len() {
+            colours[i] = colours[i].hue_shift(amount);
+            i += 1;
+        }
+
+        Self::new(colours)
+    }
 }
@@ -154,6 +154,98 @@ extern crate self as agb;
 /// );
 /// ```
 ///
+/// # Quantisation
+///
+/// Backgrounds normally have to already fit in the available palettes, which is at most 15
+/// colours per 8x8 tile and 16 palettes for 16 colour backgrounds, or 255 colours for 256 colour
+/// backgrounds. If you put `quantise` before the file name, the colours will be reduced until the
+/// background fits. Tiles with similar colours are grouped together to share a palette.
+///
+/// Reducing the colours can cause visible banding in gradients, so you can use `dither_ordered`
+/// or `dither_floyd_steinberg` instead of `quantise` to also dither the result. Ordered dithering
+/// gives a regular pattern which tends to look better in animations and compresses well, whereas
+/// Floyd–Steinberg dithering gives a smoother but noisier result.
+///
+/// For 256 colour backgrounds, `quantise` reduces each image to at most 255 colours rather than 256,
+/// because the first colour of the palette is always the transparent colour. Fully transparent
+/// pixels, and pixels matching the transparent colour if you've set one, aren't counted towards
+/// the 255 and stay transparent.
+///
+/// Each image is quantised on its own, so importing several quantised images in the same call can
+/// still run out of palettes. All the 256 colour backgrounds in a call share one 256 colour
+/// palette, so two quantised 256 colour images can't both use their full 255 colours.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// agb::include_background_gfx!(
+///     mod backgrounds,
+///     BEACH => 256 dither_floyd_steinberg "examples/gfx/beach-background.aseprite",
+///     HUD => quantise "examples/gfx/hud.aseprite",
+/// );
+/// ```
+///
+/// # Palette groups
+///
+/// Every call to `include_background_gfx!` optimises its own set of 16 palettes, so backgrounds
+/// from separate calls will overwrite each other's palettes if they're shown at the same time.
+/// Backgrounds which share palettes form a palette group. Within a single call, you can keep the
+/// backgrounds of a group in separate modules by wrapping them in `mod name { ... }`, and every
+/// module in the call shares the one `PALETTES`.
+///
+/// To put backgrounds from separate includes into the same group, convert them from your
+/// `build.rs` with `agb_image_converter_core::AssetPipeline` and give their `[[backgrounds]]`
+/// entries the same `group`. Each entry keeps its own module, and the palettes of the whole group
+/// are in the module named after the group.
+///
+/// If the images in a group need more than the 16 available palettes, you'll get an error
+/// listing how many palettes each image needs on its own so you know which ones to simplify,
+/// [quantise](#quantisation) or move to a different group.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// agb::include_background_gfx!(
+///     mod world,
+///     mod beach {
+///         BEACH => deduplicate "examples/gfx/beach-background.aseprite",
+///     },
+///     mod hud {
+///         HUD => "examples/gfx/hud.aseprite",
+///     },
+/// );
+///
+/// # fn test() {
+/// agb::display::tiled::VRAM_MANAGER.set_background_palettes(world::PALETTES);
+/// let beach = &world::beach::BEACH;
+/// let hud = &world::hud::HUD;
+/// # }
+/// ```
+///
+/// # Palette swaps
+///
+/// You can generate extra sets of palettes which recolour the backgrounds by adding a
+/// `palette_swap` entry. This is either a base image and a recoloured copy of it, where each
+/// colour in the base is replaced by the colour at the same position in the copy, or a list of
+/// colours to replace. Each one becomes a `static` alongside `PALETTES` which lines up with it
+/// index for index, so it can be passed straight to
+/// [`VRAM_MANAGER.set_background_palettes`](display::tiled::VRamManager::set_background_palettes).
+/// Colours which aren't replaced keep their original values.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// agb::include_background_gfx!(
+///     mod backgrounds,
+///     BEACH => "examples/gfx/beach-background.aseprite",
+///     palette_swap SUNSET { "00bdfe" => "f4902c", "5fcde4" => "f4b45f" },
+/// );
+///
+/// # fn test() {
+/// agb::display::tiled::VRAM_MANAGER.set_background_palettes(backgrounds::SUNSET);
+/// # }
+/// ```
+///
 /// # Module visibility
 ///
 /// The resulting module that's being exported can have a different visibility if you want it to.
@@ -245,13 +337,16 @@ pub use agb_image_converter::include_font as include_font_inner;
 #[doc(hidden)]
 pub use agb_image_converter::include_colours_inner;
 
+#[doc(hidden)]
+pub use agb_image_converter::include_strings_inner;
+
 #[doc(hidden)]
 pub use agb_image_converter::include_aseprite_256_inner;
 
 #[macro_export]
-/// Includes a ttf font to be usable by dynamic font rendering.
+/// Includes a font to be usable by dynamic font rendering.
 ///
-/// The first parameter is the filepath and the second is the point size of the font.
+/// For a ttf font, the first parameter is the filepath and the second is the point size of the font.
 ///
 /// ```rust
 /// # #![no_std]
@@ -263,13 +358,111 @@ pub use agb_image_converter::include_aseprite_256_inner;
 /// # #[agb::doctest]
 /// # fn test(gba: agb::Gba) {}
 /// ```
+///
+/// # Bitmap fonts
+///
+/// Pixel art fonts can be included exactly as they were drawn, without any anti-aliasing.
+///
+/// A [BDF](https://en.wikipedia.org/wiki/Glyph_Bitmap_Distribution_Format) font only needs its
+/// filepath. The glyphs, their positions and their widths are taken from the file.
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::font::Font, include_font};
+///
+/// static FONT: Font = include_font!("fnt/ark-pixel-10px-proportional-latin.bdf");
+///
+/// # #[agb::doctest]
+/// # fn test(gba: agb::Gba) {}
+/// ```
+///
+/// A png glyph sheet is split into a grid of cells, given by the second and third parameters as
+/// the width and height of each cell. The last parameter lists the character in each cell, read
+/// left to right and top to bottom. Newlines in this list are ignored, so you can write one row of
+/// the sheet per line.
+///
+/// Transparent pixels, and pixels the same colour as the top left pixel of the image, are
+/// background. Every other pixel is part of the glyph. The bottom of each cell is the baseline,
+/// and each glyph advances by one pixel past its right most pixel, so you control the spacing of
+/// each letter by where you draw it in its cell. Empty cells, like space, advance by half the cell
+/// width.
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::font::Font, include_font};
+///
+/// static FONT: Font = include_font!(
+///     "fnt/ark-pixel-10px-proportional-latin-grid.png",
+///     10,
+///     12,
+///     " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~"
+/// );
+///
+/// # #[agb::doctest]
+/// # fn test(gba: agb::Gba) {}
+/// ```
+///
+/// # Subsetting
+///
+/// By default, every letter in the font is included. For large fonts, you can include only the
+/// letters you use with `subset`, which takes a string or a list of strings, and `subset_files`,
+/// which takes a list of text files to scan for letters, such as `subset_files = ["dialogue.txt"]`.
+/// Space is always included. Use
+/// [`LayoutSettings::with_fallback_fonts`](display::font::LayoutSettings::with_fallback_fonts) to
+/// combine a subset font with other fonts.
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::font::Font, include_font};
+///
+/// static FONT: Font = include_font!(
+///     "examples/font/ark-pixel-10px-proportional-ja.ttf",
+///     10,
+///     subset = ["はい", "いいえ"],
+/// );
+///
+/// # #[agb::doctest]
+/// # fn test(gba: agb::Gba) {}
+/// ```
 macro_rules! include_font {
-    ($font_path: literal, $font_size: literal) => {{
+    ($font_path: literal $(, $($args: tt)*)?) => {{
         use $crate::display::font::{Font, FontLetter};
-        $crate::include_font_inner!($font_path, $font_size)
+        $crate::include_font_inner!($font_path $(, $($args)*)?)
     }};
 }
 
+/// Includes translation files, creating a module with typed ids for each string.
+///
+/// The first parameter is the name of the module to create, followed by a
+/// [Fluent](https://projectfluent.org) `.ftl` file for each language. The name
+/// of each file is its language code, so `lang/pt-BR.ftl` becomes `Language::PtBr`.
+/// Each message id becomes a variant of `StringId`, so `items-found` becomes
+/// `StringId::ItemsFound`.
+///
+/// See the [`localisation`](display::font::localisation) module for the syntax
+/// which is supported and how to format the strings.
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// agb::include_strings!(mod text, "examples/lang/en.ftl", "examples/lang/fr.ftl");
+///
+/// # #[agb::doctest]
+/// # fn test(_: agb::Gba) {
+/// let strings = text::strings(text::Language::Fr);
+/// assert_eq!(strings.get(text::StringId::Title), "La Quête du Crabe");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! include_strings {
+    (mod $module: ident, $($files: literal),+ $(,)?) => {
+        $crate::include_strings_inner!($crate, mod $module, $($files),+);
+    };
+}
+
 /// This macro declares the entry point to your game written using `agb`.
 ///
 /// It is already included in the template, but your `main` function must be annotated with `#[agb::entry]`, takes 1 argument and never returns.
@@ -316,10 +509,9 @@ pub use agb_sound_converter::include_wav as include_wav_inner;
 /// The parameter is the path to the sound file relative to the root of your crate.
 /// This macro can be thought of returning a [`SoundData`](sound::mixer::SoundData).
 ///
-/// The `include_wav` macro does not do any resampling, so it is up to you to make
-/// sure that the frequency of the wav file matches the one you've configured the
-/// mixer with. If there is a mismatch, the audio will play at the wrong speed
-/// resulting in a higher or lower pitch.
+/// Despite the name, as well as `wav` files this can also load `ogg` (Vorbis), `flac` and `mp3`
+/// files which are decoded while compiling. They then go through the same conversion as `wav`
+/// files, so they take up just as much space in the final ROM as the equivalent `wav` file.
 ///
 /// You can import stereo, but you need to call [`SoundChannel::stereo()`](sound::mixer::SoundChannel::stereo)
 /// or it'll play as mono at half speed.
@@ -333,11 +525,112 @@ pub use agb_sound_converter::include_wav as include_wav_inner;
 /// # #[agb::doctest]
 /// # fn test(gba: agb::Gba) {}
 /// ```
+///
+/// # Frequency
+///
+/// The wav file needs to have the same sample rate as the [`Frequency`](sound::mixer::Frequency)
+/// you've configured the mixer with, otherwise it will play at the wrong speed resulting in a
+/// higher or lower pitch. If you pass the frequency after the file name, then you'll get a
+/// compile error if they don't match. Adding `resample` will instead convert the sound to the
+/// right sample rate while compiling.
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{sound::mixer::SoundData, include_wav};
+///
+/// // jump.wav is 10512Hz, so this would fail to compile without `resample`
+/// static JUMP_SOUND: SoundData = include_wav!("examples/sfx/jump.wav", Hz18157, resample);
+/// # #[agb::doctest]
+/// # fn test(gba: agb::Gba) {}
+/// ```
+///
+/// Rather than passing the frequency to every `include_wav!`, you can set the `AGB_SOUND_FREQUENCY`
+/// environment variable to a frequency such as `Hz18157`, for example in the `[env]` section of
+/// your `.cargo/config.toml`.
+///
+/// # Channels
+///
+/// Add `mono` to mix a sound down to a single channel, or `stereo` to play a mono sound on both
+/// channels. Sounds with more than 2 channels must be mixed down with `mono`.
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{sound::mixer::SoundData, include_wav};
+///
+/// static MUSIC: SoundData =
+///     include_wav!("examples/JoshWoodward-CrazyGlue.wav", Hz32768, mono);
+/// # #[agb::doctest]
+/// # fn test(gba: agb::Gba) {}
+/// ```
+///
+/// # Loop points
+///
+/// If a `wav` file has loop points in its `smpl` chunk, such as those set by many sample editors,
+/// they are stored with the sound and used when the channel [loops](sound::mixer::SoundChannel::should_loop).
+/// Files without a `smpl` chunk can mark the start of the loop with a cue marker, and the end
+/// of the loop with a second one. The loop points are moved to match if the sound is resampled.
+///
+/// # ADPCM
+///
+/// Add `adpcm` to store the sound compressed with IMA ADPCM, which takes up about half the space
+/// in ROM at the cost of some quality, especially for quiet sounds. The mixer decodes it as it
+/// plays, so compressed sounds can be used everywhere an uncompressed one can.
+///
+/// Decoding takes extra CPU time for each channel playing a compressed sound, roughly doubling
+/// the cost of that channel at normal speed. Every sample needs to be decoded even if it is
+/// skipped over, so the cost goes up with the [playback speed](sound::mixer::SoundChannel::playback)
+/// which can be at most 32.
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{sound::mixer::SoundData, include_wav};
+///
+/// static MUSIC: SoundData =
+///     include_wav!("examples/JoshWoodward-CrazyGlue.wav", Hz32768, adpcm);
+/// # #[agb::doctest]
+/// # fn test(gba: agb::Gba) {}
+/// ```
 #[macro_export]
 macro_rules! include_wav {
-    ($filepath: literal) => {{
+    ($filepath: literal $(, $($options: tt)*)?) => {{
+        use $crate::sound::mixer::SoundData;
+        $crate::include_wav_inner!($filepath $(, $($options)*)?)
+    }};
+}
+
+#[doc(hidden)]
+pub use agb_sound_converter::include_sfxr as include_sfxr_inner;
+
+/// Synthesise a sound effect from an sfxr parameter file while compiling.
+///
+/// [sfxr](https://www.drpetter.se/project_sfxr.html) and its browser based version
+/// [jsfxr](https://sfxr.me) generate retro sound effects from a small set of parameters.
+/// Rather than exporting a wav file, you can save those parameters and include them directly,
+/// so the sound effect is just a small text file in your repository. Both the JSON files jsfxr
+/// saves and the `.sfs` files the original sfxr saves can be used.
+///
+/// The sound is generated at the mixer's [`Frequency`](sound::mixer::Frequency), which must be
+/// passed after the file name or set with the `AGB_SOUND_FREQUENCY` environment variable in the
+/// same way as for [`include_wav!`]. This macro can be thought of returning a mono
+/// [`SoundData`](sound::mixer::SoundData).
+///
+/// ```rust
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{sound::mixer::SoundData, include_sfxr};
+///
+/// static COIN_SOUND: SoundData = include_sfxr!("examples/sfx/coin.json", Hz18157);
+/// # #[agb::doctest]
+/// # fn test(gba: agb::Gba) {}
+/// ```
+#[macro_export]
+macro_rules! include_sfxr {
+    ($filepath: literal $(, $($options: tt)*)?) => {{
         use $crate::sound::mixer::SoundData;
-        $crate::include_wav_inner!($filepath)
+        $crate::include_sfxr_inner!($filepath $(, $($options)*)?)
     }};
 }
 
@@ -475,6 +768,8 @@ pub struct Gba {
     pub graphics: display::GraphicsDist,
     /// Manages access to the Game Boy Advance's direct sound mixer for playing raw wav files.
     pub mixer: sound::mixer::MixerController,
+    /// Manages access to the Game Boy Advance's DMG sound channels for playing tones and noise.
+    pub psg: sound::psg::PsgController,
     /// Manages access to the Game Boy Advance cartridge's save chip.
     pub save: save::SaveManager,
     /// Manages access to the Game Boy Advance's 4 timers.
@@ -500,6 +795,7 @@ impl Gba {
         Self {
             graphics: display::GraphicsDist,
             mixer: sound::mixer::MixerController::new(),
+            psg: sound::psg::PsgController::new(),
             save: save::SaveManager::new(),
             timers: timer::TimerController::new(),
         }
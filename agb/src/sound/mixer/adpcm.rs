@@ -0,0 +1,247 @@
+//! Decoding for sounds imported with the `adpcm` option of [`include_wav!`](crate::include_wav).
+//!
+//! The data is IMA ADPCM split into blocks of [`BLOCK_SAMPLES`] samples for each
+//! channel. Each block starts with the decoder state so that we can start
+//! playing from anywhere without decoding the entire sound up to that point.
+//! Stereo sounds store the left block followed by the right block.
+//!
+//! Each block for a single channel is laid out as follows:
+//! - the predictor as a little endian `i16`
+//! - the step index as a `u8`
+//! - a padding byte
+//! - [`BLOCK_SAMPLES`] 4-bit samples, with the first sample in the low nibble
+//!
+//! This must be kept in sync with the encoder in `agb-sound-converter`.
+
+/// The number of samples in each block
+pub(crate) const BLOCK_SAMPLES: usize = 256;
+/// The number of bytes each channel of a block takes up
+const BLOCK_BYTES: usize = 4 + BLOCK_SAMPLES / 2;
+
+/// The fastest playback speed possible for ADPCM sounds. Every sample has to be
+/// decoded even if it is skipped over, so playing faster than this would be far
+/// too slow anyway.
+pub(crate) const MAX_PLAYBACK_SPEED: u32 = 32;
+
+static STEP_TABLE: [u16; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+static INDEX_TABLE: [i8; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+#[derive(Clone, Copy, Default)]
+struct ChannelState {
+    predictor: i32,
+    step_index: i32,
+}
+
+impl ChannelState {
+    fn from_header(header: &[u8]) -> Self {
+        Self {
+            predictor: i32::from(i16::from_le_bytes([header[0], header[1]])),
+            step_index: i32::from(header[2]).min(88),
+        }
+    }
+
+    #[inline(always)]
+    fn step(&mut self, nibble: u8) -> i8 {
+        let step = i32::from(STEP_TABLE[self.step_index as usize]);
+
+        let mut difference = step >> 3;
+        if nibble & 1 != 0 {
+            difference += step >> 2;
+        }
+        if nibble & 2 != 0 {
+            difference += step >> 1;
+        }
+        if nibble & 4 != 0 {
+            difference += step;
+        }
+        if nibble & 8 != 0 {
+            difference = -difference;
+        }
+
+        self.predictor = (self.predictor + difference).clamp(i16::MIN.into(), i16::MAX.into());
+        self.step_index =
+            (self.step_index + i32::from(INDEX_TABLE[usize::from(nibble)])).clamp(0, 88);
+
+        self.sample()
+    }
+
+    fn sample(&self) -> i8 {
+        (self.predictor >> 8) as i8
+    }
+}
+
+/// Keeps track of where a channel is in a sound so that playing the next part
+/// carries on decoding rather than starting again from the beginning of a block.
+#[derive(Clone)]
+pub(crate) struct Decoder {
+    /// The next sample that will be decoded, or `None` if we need to start
+    /// from the beginning of a block
+    next: Option<usize>,
+    state: [ChannelState; 2],
+}
+
+impl Decoder {
+    pub(crate) const fn new() -> Self {
+        Self {
+            next: None,
+            state: [
+                ChannelState {
+                    predictor: 0,
+                    step_index: 0,
+                },
+                ChannelState {
+                    predictor: 0,
+                    step_index: 0,
+                },
+            ],
+        }
+    }
+
+    /// Decodes the samples starting at `start` into `output` with `channels`
+    /// interleaved channels, in the same signed 8-bit format as uncompressed
+    /// sounds. `output` must not go past the end of the sound.
+    #[unsafe(link_section = ".iwram.agb_rs__adpcm_decode")]
+    #[instruction_set(arm::a32)]
+    pub(crate) fn decode(&mut self, data: &[u8], channels: usize, start: usize, output: &mut [u8]) {
+        if output.is_empty() {
+            return;
+        }
+
+        let mut output = output;
+
+        match self.next {
+            // the last sample we decoded is the first one we need, which is
+            // the same as the current value of the predictor
+            Some(next) if next == start + 1 => {
+                let (first, rest) = output.split_at_mut(channels);
+                for (sample, state) in first.iter_mut().zip(&self.state) {
+                    *sample = state.sample() as u8;
+                }
+                output = rest;
+            }
+            // we're only going forwards a little bit, so it's faster to skip
+            // the samples in between than to start from the beginning of a block
+            Some(next) if start >= next && start - next < BLOCK_SAMPLES => {
+                self.decode_samples(data, channels, start - next, None);
+            }
+            _ => {
+                let block_start = start - start % BLOCK_SAMPLES;
+                let block_offset = (block_start / BLOCK_SAMPLES) * channels * BLOCK_BYTES;
+
+                for (channel, state) in self.state.iter_mut().take(channels).enumerate() {
+                    let header = block_offset + channel * BLOCK_BYTES;
+                    *state = ChannelState::from_header(&data[header..header + 4]);
+                }
+
+                self.next = Some(block_start);
+                self.decode_samples(data, channels, start - block_start, None);
+            }
+        }
+
+        self.decode_samples(data, channels, output.len() / channels, Some(output));
+    }
+
+    /// Decodes the next `count` samples, writing them to `output` if it is given
+    #[inline(always)]
+    fn decode_samples(
+        &mut self,
+        data: &[u8],
+        channels: usize,
+        count: usize,
+        mut output: Option<&mut [u8]>,
+    ) {
+        let mut position = self.next.expect("decoder should have a position");
+        let end = position + count;
+        let mut written = 0;
+
+        while position < end {
+            let block = position / BLOCK_SAMPLES;
+            let first_in_block = position % BLOCK_SAMPLES;
+            let run = (BLOCK_SAMPLES - first_in_block).min(end - position);
+
+            for (channel, state) in self.state.iter_mut().take(channels).enumerate() {
+                let block_data = (block * channels + channel) * BLOCK_BYTES + 4;
+                let nibbles = &data[block_data..block_data + BLOCK_BYTES - 4];
+
+                for i in 0..run {
+                    let index = first_in_block + i;
+                    let nibble = (nibbles[index / 2] >> ((index & 1) * 4)) & 0xF;
+                    let sample = state.step(nibble);
+
+                    if let Some(output) = output.as_deref_mut() {
+                        output[(written + i) * channels + channel] = sample as u8;
+                    }
+                }
+            }
+
+            position += run;
+            written += run;
+        }
+
+        self.next = Some(position);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    // Some arbitrary samples with each block's header set to the decoder's state
+    // at the start of it, which is what the encoder does
+    fn test_data(channels: usize) -> Vec<u8> {
+        let mut data = vec![];
+        for block in 0..3 {
+            for channel in 0..channels {
+                let mut state = ChannelState::default();
+                let nibbles = (0..BLOCK_SAMPLES)
+                    .map(|i| ((i + block * 3 + channel * 5) % 16) as u8)
+                    .collect::<Vec<_>>();
+
+                // work out the header by decoding the blocks before this one
+                for previous in 0..block {
+                    for i in 0..BLOCK_SAMPLES {
+                        state.step(((i + previous * 3 + channel * 5) % 16) as u8);
+                    }
+                }
+
+                data.extend((state.predictor as i16).to_le_bytes());
+                data.push(state.step_index as u8);
+                data.push(0);
+                data.extend(nibbles.chunks(2).map(|pair| pair[0] | (pair[1] << 4)));
+            }
+        }
+        data
+    }
+
+    #[test_case]
+    fn decoding_from_anywhere_matches_decoding_everything(_: &mut crate::Gba) {
+        for channels in [1, 2] {
+            let data = test_data(channels);
+            let length = 3 * BLOCK_SAMPLES;
+
+            let mut everything = vec![0; length * channels];
+            Decoder::new().decode(&data, channels, 0, &mut everything);
+
+            let mut decoder = Decoder::new();
+            for (start, count) in [(10, 20), (29, 300), (329, 4), (700, 68), (5, 500)] {
+                let mut part = vec![0; count * channels];
+                decoder.decode(&data, channels, start, &mut part);
+                assert_eq!(
+                    part,
+                    &everything[start * channels..(start + count) * channels],
+                    "{channels} channels from {start}"
+                );
+            }
+        }
+    }
+}
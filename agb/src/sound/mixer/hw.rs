@@ -78,8 +78,12 @@ pub(super) fn set_sound_control_register_for_mixer() {
     let sound_b_lout: u16 = 0 << 13;
     let sound_b_fifo_reset: u16 = 1 << 15;
 
+    // keep the DMG sound volume so that the psg can be used alongside the mixer
+    let psg_volume = SOUND_CONTROL.get() & 0b11;
+
     SOUND_CONTROL.set(
-        sound_a_volume_100
+        psg_volume
+            | sound_a_volume_100
             | sound_a_rout
             | sound_a_lout
             | sound_a_fifo_reset
@@ -97,6 +101,7 @@ pub(super) fn set_sound_control_register_for_mixer() {
 }
 
 pub(super) fn set_timer_counter_for_frequency_and_enable(timer: &mut Timer, frequency: i32) {
-    timer.set_overflow_amount((16777216 / frequency) as u16);
+    // the frequencies are rounded, so round the period too to get the one they came from
+    timer.set_overflow_amount(((16777216 + frequency / 2) / frequency) as u16);
     timer.set_enabled(true);
 }
@@ -90,6 +90,104 @@ mono_add_fn_loop agb_rs__mixer_add_mono_loop false true
 mono_add_fn_loop agb_rs__mixer_add_mono_first true false
 mono_add_fn_loop agb_rs__mixer_add_mono false false
 
+.macro mono_add_fn_interpolated fn_name:req is_first:req is_loop:req
+agb_arm_func \fn_name
+    @ Arguments are the same as for mono_add_fn_loop, but rather than picking the
+    @ nearest sample, this interpolates linearly between the samples either side of
+    @ the current position. That needs more registers, so this only does 2 samples
+    @ at a time rather than 4.
+    @
+    @ r0 - pointer to the sample data from the beginning
+    @ r1 - pointer to the target sample buffer &[i32; BUFFER_SIZE]
+    @ r2 - BUFFER_SIZE - the length of the array in r1. Must be a multiple of 4
+    @ r3 - (length - restart point) (how much to rewind by)
+    @ Stack position 1 - channel length
+    @ Stack position 2 - current channel position
+    @ Stack position 3 - the playback speed
+    @ Stack position 4 - the amount to multiply by
+    @
+    @ Returns the new channel position
+    push {{r4-r11}}
+
+    ldr r4, [sp, #(8*4)] @ load the channel length into r4
+    ldr r5, [sp, #(9*4)] @ load the current channel position into r5
+    ldr r6, [sp, #(10*4)] @ load the playback speed into r6
+    ldr r12, [sp, #(11*4)] @ load the amount to multiply by into r12
+
+@ The core loop
+1:
+.ifc \is_first,false
+    ldm r1, {{r7-r8}}
+.endif
+
+.irp reg, r7,r8
+    cmp r4, r5, lsr #8          @ check if we're overflowing
+.ifc \is_loop,true
+    suble r5, r5, r3            @ if we are, subtract the overflow amount
+.else
+    ble 2f                      @ if we are, zero the rest of the buffer
+.endif
+
+    mov r11, r5, lsr #8         @ calculate the location of the sample before the position
+    ldrsb r9, [r0, r11]         @ load it
+    add r11, r11, #1            @ and the location of the sample after
+    cmp r4, r11                 @ check if that is past the end of the sound
+.ifc \is_loop,true
+    suble r11, r11, r3, lsr #8  @ if it is, the sample after is the one at the restart point
+    ldrsb r10, [r0, r11]
+.else
+    ldrsbgt r10, [r0, r11]      @ only load it if it is still part of the sound,
+    movle r10, r9               @ otherwise just use the sample before again
+.endif
+
+    sub r10, r10, r9            @ the difference between the two samples
+    and r11, r5, #0xff          @ how far we are between them
+    mul r10, r11, r10
+    add r9, r9, r10, asr #8     @ r9 = before + (after - before) * fraction
+
+.ifc \is_first,true             @ multiply the sample value, but only add if not the first call
+    mul \reg, r9, r12
+.else
+    mla \reg, r9, r12, \reg
+.endif
+
+    add r5, r5, r6              @ calculate the next sample read location
+.endr
+
+    stmia r1!, {{r7-r8}}
+
+    subs r2, r2, #2
+    bne 1b
+
+.ifc \is_loop,false
+    b 3f
+
+2:
+.ifc \is_first,true             @ zero the rest of the buffer as this sample has ended
+    cmp r2, #0
+    beq 3f
+
+    mov r7, #0
+5:
+    stmia r1!, {{r7}}
+    subs r2, r2, #1
+    bne 5b
+.endif
+3:
+.endif
+
+    mov r0, r5 @ return the playback position
+    pop {{r4-r11}}
+
+    bx lr
+agb_arm_end \fn_name
+.endm
+
+mono_add_fn_interpolated agb_rs__mixer_add_mono_interpolated_loop_first true true
+mono_add_fn_interpolated agb_rs__mixer_add_mono_interpolated_loop false true
+mono_add_fn_interpolated agb_rs__mixer_add_mono_interpolated_first true false
+mono_add_fn_interpolated agb_rs__mixer_add_mono_interpolated false false
+
 .macro stereo_add_fn fn_name:req is_first:req
 agb_arm_func \fn_name
     @ Arguments
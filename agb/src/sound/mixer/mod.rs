@@ -9,7 +9,8 @@
 //! have to use a software mixer.
 //!
 //! agb's software mixer allows for up to 8 simultaneous sounds played at once at
-//! various speeds and volumes.
+//! various speeds and volumes by default, and you can choose a different number of
+//! channels with [`MixerController::mixer_with_channels()`].
 //!
 //! # Concepts
 //!
@@ -18,6 +19,8 @@
 //!
 //! All wav files you use within your application / game must use this _exact_ frequency.
 //! If you don't use this frequency, the sound will play either too slowly or too quickly.
+//! [`include_wav!`](crate::include_wav) can check this for you, and resample the files which
+//! don't match while compiling.
 //!
 //! The mixer can play both mono and stereo sounds, but only mono sound effects can have
 //! effects applied to them (such as changing the speed at which they play or the panning).
@@ -69,11 +72,15 @@
 //!
 //! ## Loading a sample
 //!
-//! To load a sample, you must have it in `wav` format (both stereo and mono work) at exactly the
-//! selected frequency based on the frequency passed to the `mixer` function.
+//! To load a sample, you must have it in `wav`, `ogg`, `flac` or `mp3` format (both stereo and mono
+//! work) at exactly the selected frequency based on the frequency passed to the `mixer` function.
+//! The format is picked from the file extension, and `wav` files with any other extension, such as
+//! `.wave`, are recognised from their header.
 //!
-//! Use the [`include_wav!`](crate::include_wav) macro in order to load the sound. This will produce
-//! an error if your wav file is of the wrong frequency.
+//! Use the [`include_wav!`](crate::include_wav) macro in order to load the sound. If you pass it
+//! the mixer's frequency, this will produce an error if your wav file is of the wrong frequency,
+//! or you can add `resample` to convert it. Adding `adpcm` compresses the sound to save ROM space,
+//! at the cost of some quality and extra CPU time while it plays.
 //!
 //! ```rust
 //! # #![no_std]
@@ -94,8 +101,18 @@
 //!
 //! See the [`SoundChannel`] struct for more details on how you can configure the sounds to play.
 //!
+//! ## Volume
+//!
+//! Each channel has its own volume, and belongs to a [`Bus`] such as music or sound effects.
+//! The volume of a whole bus can be changed with [`Mixer::set_bus_volume()`], and everything
+//! with [`Mixer::set_master_volume()`], which is useful for volume sliders in an options menu.
+//!
+//! To fade a sound in or out, use [`SoundChannel::fade_volume()`] or [`SoundChannel::fade_out()`]
+//! rather than changing the volume every frame yourself.
+//!
 //! Once you have run [`play_sound`](Mixer::play_sound), the mixer will play that sound until
 //! it has finished.
+mod adpcm;
 mod hw;
 mod sw_mixer;
 
@@ -117,8 +134,36 @@ impl MixerController {
     }
 
     /// Get a [`Mixer`] in order to start producing sounds.
+    ///
+    /// The mixer can play up to 8 sounds at once. Use [`mixer_with_channels()`](Self::mixer_with_channels)
+    /// to change that.
     pub fn mixer(&mut self, frequency: Frequency) -> Mixer<'_> {
-        Mixer::new(frequency)
+        Mixer::new(frequency, 8)
+    }
+
+    /// Get a [`Mixer`] which can play up to `channels` sounds at once.
+    ///
+    /// Each sound playing takes CPU time every frame, so fewer channels can be used to
+    /// put a limit on how much time the mixer takes. Channels which aren't playing
+    /// anything don't take any time.
+    ///
+    /// ```rust
+    /// # #![no_std]
+    /// # #![no_main]
+    /// use agb::sound::mixer::Frequency;
+    ///
+    /// # #[agb::doctest]
+    /// # fn test(mut gba: agb::Gba) {
+    /// let mut mixer = gba.mixer.mixer_with_channels(Frequency::Hz21024, 16);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channels` is 0.
+    pub fn mixer_with_channels(&mut self, frequency: Frequency, channels: usize) -> Mixer<'_> {
+        assert!(channels > 0, "The mixer needs at least 1 channel");
+        Mixer::new(frequency, channels)
     }
 }
 
@@ -128,18 +173,66 @@ enum SoundPriority {
     Low,
 }
 
+/// A group of channels which share a volume, so that the volume of all the music
+/// or all the sound effects can be changed at once, such as from an options menu.
+///
+/// Assign a channel to a bus with [`SoundChannel::bus()`], and change the bus's
+/// volume with [`Mixer::set_bus_volume()`]. Channels are on the
+/// [`Effects`](Bus::Effects) bus unless you say otherwise.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Bus {
+    /// Background music
+    Music,
+    /// Sound effects
+    Effects,
+}
+
+impl Bus {
+    const COUNT: usize = 2;
+}
+
+/// How the mixer works out what to play when a sound's [playback speed](SoundChannel::playback)
+/// means that the next sample is part way between two samples in the sound.
+///
+/// The cycle counts below are for the mixing loop with the sound copied into IWRAM,
+/// which the mixer does for playback speeds up to 1.5. Faster sounds are read straight
+/// from ROM, which adds the ROM wait states to every sample.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Interpolation {
+    /// Use the sample just before the position. This is the fastest option, at around
+    /// 17 cycles per sample, but sounds harsh when a sound is played much slower than normal.
+    #[default]
+    Nearest,
+    /// Blend between the samples either side of the position. This is much smoother
+    /// for sounds played at a different speed, but takes around 30 cycles per sample
+    /// to mix for each channel which uses it.
+    Linear,
+}
+
 /// The supported frequencies within AGB.
 ///
 /// These are chosen to work well with/ the hardware. Note that the higher
 /// the frequency, the better the quality of the sound but the more CPU time
 ///  sound mixing will take.
+///
+/// All of these except for [`Hz32768`](Frequency::Hz32768) produce an exact
+/// number of samples every frame, so the buffers line up with the frames.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum Frequency {
     /// 10512Hz
     Hz10512,
+    /// 13379Hz
+    Hz13379,
     /// 18157Hz
     Hz18157,
+    /// 21024Hz
+    Hz21024,
+    /// 26758Hz
+    Hz26758,
+    /// 31536Hz
+    Hz31536,
     /// 32768Hz
     Hz32768,
 }
@@ -151,7 +244,11 @@ impl Frequency {
 
         match self {
             Hz10512 => 10512,
+            Hz13379 => 13379,
             Hz18157 => 18157,
+            Hz21024 => 21024,
+            Hz26758 => 26758,
+            Hz31536 => 31536,
             Hz32768 => 32768,
         }
     }
@@ -161,7 +258,11 @@ impl Frequency {
 
         match self {
             Hz10512 => 176,
+            Hz13379 => 224,
             Hz18157 => 304,
+            Hz21024 => 352,
+            Hz26758 => 448,
+            Hz31536 => 528,
             Hz32768 => 560,
         }
     }
@@ -175,6 +276,18 @@ impl Frequency {
 pub struct SoundData {
     data: *const u8,
     len: usize,
+    // the number of samples once decoded, which is only different from `len` for ADPCM sounds
+    samples: usize,
+    encoding: SoundEncoding,
+    // the loop region in frames, if the file had one
+    loop_start: usize,
+    loop_end: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SoundEncoding {
+    Pcm,
+    Adpcm,
 }
 
 impl SoundData {
@@ -190,7 +303,41 @@ impl SoundData {
         // check that ptr is correctly aligned
         // assert!((ptr as usize) & 3 == 0);
 
-        Self { data: ptr, len }
+        Self {
+            data: ptr,
+            len,
+            samples: len,
+            encoding: SoundEncoding::Pcm,
+            loop_start: 0,
+            loop_end: None,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// data must be 4-byte aligned, and be ADPCM data as produced by
+    /// [`include_wav!()`](crate::include_wav) which decodes to `samples` samples.
+    #[doc(hidden)]
+    #[must_use]
+    pub const unsafe fn new_adpcm(data: &'static [u8], samples: usize) -> Self {
+        Self {
+            data: data.as_ptr(),
+            len: data.len(),
+            samples,
+            encoding: SoundEncoding::Adpcm,
+            loop_start: 0,
+            loop_end: None,
+        }
+    }
+
+    /// Sets the region which [`SoundChannel::should_loop()`] repeats, in frames
+    /// from the start of the sound. `end` is exclusive.
+    #[doc(hidden)]
+    #[must_use]
+    pub const fn with_loop(mut self, start: usize, end: usize) -> Self {
+        self.loop_start = start;
+        self.loop_end = Some(end);
+        self
     }
 
     #[must_use]
@@ -268,9 +415,14 @@ unsafe impl Sync for SoundData {}
 /// ```
 pub struct SoundChannel {
     data: &'static [u8],
+    // the number of samples in the sound, which is not the length of `data` for ADPCM sounds
+    len: usize,
+    adpcm: Option<adpcm::Decoder>,
     pos: Num<u32, 8>,
     should_loop: bool,
     restart_point: Num<u32, 8>,
+    // where to go back to the restart point when looping, in frames
+    loop_end: Option<usize>,
 
     is_playing: bool,
     playback_speed: Num<u32, 8>,
@@ -282,6 +434,47 @@ pub struct SoundChannel {
     is_stereo: bool,
 
     priority: SoundPriority,
+    bus: Bus,
+    interpolation: Interpolation,
+
+    volume_ramp: Option<Ramp>,
+    panning_ramp: Option<Ramp>,
+    playback_ramp: Option<Ramp>,
+    // stop the channel once the volume ramp finishes
+    stop_after_volume_ramp: bool,
+}
+
+/// A linear change from one value to another over a number of frames, stored as
+/// the raw fixed point values so it can be used for any of the channel's settings.
+#[derive(Clone, Copy)]
+struct Ramp {
+    start: i32,
+    target: i32,
+    frames: u16,
+    elapsed: u16,
+}
+
+impl Ramp {
+    fn new(start: i32, target: i32, frames: u16) -> Self {
+        Self {
+            start,
+            target,
+            frames,
+            elapsed: 0,
+        }
+    }
+
+    /// Moves on by a frame, returning the new value and whether the ramp has finished
+    fn advance(&mut self) -> (i32, bool) {
+        self.elapsed = self.elapsed.saturating_add(1).min(self.frames);
+        if self.elapsed >= self.frames {
+            return (self.target, true);
+        }
+
+        let progress =
+            i64::from(self.target - self.start) * i64::from(self.elapsed) / i64::from(self.frames);
+        (self.start + progress as i32, false)
+    }
 }
 
 impl SoundChannel {
@@ -315,6 +508,8 @@ impl SoundChannel {
     pub fn new(data: SoundData) -> Self {
         SoundChannel {
             data: data.data(),
+            len: data.samples,
+            adpcm: (data.encoding == SoundEncoding::Adpcm).then(adpcm::Decoder::new),
             pos: 0.into(),
             should_loop: false,
             playback_speed: 1.into(),
@@ -324,7 +519,14 @@ impl SoundChannel {
             priority: SoundPriority::Low,
             volume: 1.into(),
             is_stereo: false,
-            restart_point: 0.into(),
+            restart_point: Num::new(data.loop_start as u32),
+            loop_end: data.loop_end,
+            bus: Bus::Effects,
+            interpolation: Interpolation::Nearest,
+            volume_ramp: None,
+            panning_ramp: None,
+            playback_ramp: None,
+            stop_after_volume_ramp: false,
         }
     }
 
@@ -366,6 +568,11 @@ impl SoundChannel {
 
     /// Sets that a sound channel should loop back to the start once it has
     /// finished playing rather than stopping.
+    ///
+    /// If the sound was imported from a wav file with loop points, then only
+    /// the loop region is repeated. The sound plays from the start up until the
+    /// end of the loop, and then goes back to the start of the loop rather than
+    /// the start of the sound. Anything after the end of the loop is never played.
     #[inline(always)]
     pub fn should_loop(&mut self) -> &mut Self {
         self.should_loop = true;
@@ -375,32 +582,61 @@ impl SoundChannel {
     /// Sets the point at which the sample should restart once it loops. Does nothing
     /// unless you also call [`should_loop()`](SoundChannel::should_loop()).
     ///
-    /// Useful if your song has an introduction or similar.
+    /// Useful if your song has an introduction or similar. This replaces the start of
+    /// the loop region from the wav file if it had one.
     #[inline(always)]
     pub fn restart_point(&mut self, restart_point: impl Into<Num<u32, 8>>) -> &mut Self {
         self.restart_point = restart_point.into();
         assert!(
-            self.restart_point.floor() as usize <= self.data.len(),
+            self.restart_point.floor() as usize <= self.len,
             "restart point must be shorter than the length of the sample"
         );
         self
     }
 
+    /// The position the sound ends at, which is the end of the loop region if there is
+    /// one and the sound is looping. This is in the same units as `pos`, so counts each
+    /// channel of stereo sounds separately.
+    pub(super) fn end(&self) -> usize {
+        match self.loop_end {
+            Some(loop_end) if self.should_loop => {
+                let channels = if self.is_stereo { 2 } else { 1 };
+                (loop_end * channels).min(self.len)
+            }
+            _ => self.len,
+        }
+    }
+
     /// Sets the speed at which this should channel should be played. Defaults
     /// to 1 with values between 0 and 1 being slower above 1 being faster.
     ///
     /// Note that this only works for mono sounds. Stereo sounds will not change
     /// how fast they play.
+    ///
+    /// Sounds imported with the `adpcm` option of [`include_wav!`](crate::include_wav)
+    /// can't be played more than 32 times faster than normal, and take longer to
+    /// decode the faster they play.
     #[inline(always)]
     pub fn playback(&mut self, playback_speed: impl Into<Num<u32, 8>>) -> &mut Self {
-        let mut playback_speed = playback_speed.into();
-        let channel_len = Num::new(self.data.len() as u32);
+        self.playback_ramp = None;
+        self.set_playback(playback_speed.into());
+        self
+    }
+
+    #[inline(always)]
+    fn set_playback(&mut self, mut playback_speed: Num<u32, 8>) {
+        let channel_len = Num::new(self.len as u32);
         while playback_speed >= channel_len {
             playback_speed -= channel_len;
         }
 
+        assert!(
+            self.adpcm.is_none() || playback_speed <= adpcm::MAX_PLAYBACK_SPEED.into(),
+            "ADPCM sounds can be played at most {} times faster",
+            adpcm::MAX_PLAYBACK_SPEED
+        );
+
         self.playback_speed = playback_speed;
-        self
     }
 
     /// Sets how far left or right the sound effect should be played.
@@ -417,10 +653,50 @@ impl SoundChannel {
         debug_assert!(panning >= Num::new(-1), "panning value must be >= -1");
         debug_assert!(panning <= Num::new(1), "panning value must be <= 1");
 
+        self.panning_ramp = None;
         self.panning = panning;
         self
     }
 
+    /// Sets which [`Bus`] this channel belongs to. The channel's volume is multiplied
+    /// by the volume of its bus and the mixer's master volume. Defaults to
+    /// [`Bus::Effects`].
+    #[inline(always)]
+    pub fn bus(&mut self, bus: Bus) -> &mut Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Sets how the channel is mixed when it is played at a speed other than 1. See
+    /// [`Interpolation`] for the options and how much CPU time they take.
+    /// Defaults to [`Interpolation::Nearest`].
+    ///
+    /// This only affects mono sounds, since stereo sounds always play at normal speed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![no_std]
+    /// # #![no_main]
+    /// # use agb::sound::mixer::*;
+    /// # use agb::*;
+    /// # #[agb::doctest]
+    /// # fn test(mut gba: Gba) {
+    /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
+    /// # static JUMP_SOUND: SoundData = include_wav!("examples/sfx/jump.wav");
+    /// use agb::fixnum::num;
+    ///
+    /// let mut jump = SoundChannel::new(JUMP_SOUND);
+    /// jump.playback(num!(0.37)).interpolation(Interpolation::Linear);
+    /// let _ = mixer.play_sound(jump);
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn interpolation(&mut self, interpolation: Interpolation) -> &mut Self {
+        self.interpolation = interpolation;
+        self
+    }
+
     /// Sets the volume for how loud the sound should be played. Note that if
     /// you play it too loud, the sound will clip sounding pretty terrible.
     ///
@@ -431,10 +707,139 @@ impl SoundChannel {
 
         assert!(volume >= Num::new(0), "volume must be >= 0");
 
+        self.volume_ramp = None;
+        self.stop_after_volume_ramp = false;
         self.volume = volume;
         self
     }
 
+    /// Changes the volume from its current value to `volume` evenly over the next
+    /// `frames` frames. This happens in [`Mixer::frame()`], so works on channels
+    /// which are already playing through [`Mixer::channel()`].
+    ///
+    /// Setting the volume directly with [`volume()`](Self::volume) cancels the fade.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![no_std]
+    /// # #![no_main]
+    /// # use agb::sound::mixer::*;
+    /// # use agb::*;
+    /// # #[agb::doctest]
+    /// # fn test(mut gba: Gba) {
+    /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
+    /// # static MY_BGM: SoundData = include_wav!("examples/sfx/my_bgm.wav");
+    /// // fade the music in over a second
+    /// let mut bgm = SoundChannel::new_high_priority(MY_BGM);
+    /// bgm.stereo().should_loop().volume(0).fade_volume(1, 60);
+    /// let bgm_id = mixer.play_sound(bgm).unwrap();
+    ///
+    /// // and later, fade it out over half a second and stop it
+    /// if let Some(bgm) = mixer.channel(&bgm_id) {
+    ///     bgm.fade_out(30);
+    /// }
+    /// # }
+    /// ```
+    #[inline]
+    pub fn fade_volume(&mut self, volume: impl Into<Num<i16, 8>>, frames: u16) -> &mut Self {
+        let volume = volume.into();
+        assert!(volume >= Num::new(0), "volume must be >= 0");
+
+        self.volume_ramp = Some(Ramp::new(
+            self.volume.to_raw().into(),
+            volume.to_raw().into(),
+            frames,
+        ));
+        self.stop_after_volume_ramp = false;
+        self
+    }
+
+    /// Fades the volume to 0 over the next `frames` frames, and then stops the channel.
+    #[inline]
+    pub fn fade_out(&mut self, frames: u16) -> &mut Self {
+        self.fade_volume(0, frames);
+        self.stop_after_volume_ramp = true;
+        self
+    }
+
+    /// Changes the panning from its current value to `panning` evenly over the next
+    /// `frames` frames. See [`panning()`](Self::panning) for the allowed values.
+    ///
+    /// Setting the panning directly with [`panning()`](Self::panning) cancels the fade.
+    #[inline]
+    pub fn fade_panning(&mut self, panning: impl Into<Num<i16, 8>>, frames: u16) -> &mut Self {
+        let panning = panning.into();
+
+        debug_assert!(panning >= Num::new(-1), "panning value must be >= -1");
+        debug_assert!(panning <= Num::new(1), "panning value must be <= 1");
+
+        self.panning_ramp = Some(Ramp::new(
+            self.panning.to_raw().into(),
+            panning.to_raw().into(),
+            frames,
+        ));
+        self
+    }
+
+    /// Changes the playback speed from its current value to `playback_speed` evenly
+    /// over the next `frames` frames. See [`playback()`](Self::playback) for more details.
+    ///
+    /// Setting the speed directly with [`playback()`](Self::playback) cancels the change.
+    #[inline]
+    pub fn fade_playback(
+        &mut self,
+        playback_speed: impl Into<Num<u32, 8>>,
+        frames: u16,
+    ) -> &mut Self {
+        let playback_speed = playback_speed.into();
+
+        self.playback_ramp = Some(Ramp::new(
+            self.playback_speed.to_raw() as i32,
+            playback_speed.to_raw() as i32,
+            frames,
+        ));
+        self
+    }
+
+    /// Moves any fades on by a frame. Called by the mixer once per frame.
+    pub(super) fn update_ramps(&mut self) {
+        if !self.is_playing || self.is_done {
+            return;
+        }
+
+        if let Some(ramp) = &mut self.volume_ramp {
+            let (volume, finished) = ramp.advance();
+            self.volume = Num::from_raw(volume as i16);
+
+            if finished {
+                self.volume_ramp = None;
+                if self.stop_after_volume_ramp {
+                    self.stop();
+                }
+            }
+        }
+
+        if let Some(ramp) = &mut self.panning_ramp {
+            let (panning, finished) = ramp.advance();
+            self.panning = Num::from_raw(panning as i16);
+
+            if finished {
+                self.panning_ramp = None;
+            }
+        }
+
+        if let Some(ramp) = &mut self.playback_ramp {
+            let (playback_speed, finished) = ramp.advance();
+
+            if finished {
+                self.playback_ramp = None;
+            }
+
+            self.set_playback(Num::from_raw(playback_speed as u32));
+        }
+    }
+
     /// Sets that the sound effect should be played in stereo. Not setting this
     /// will result in the sound playing at half speed and mono. Setting this on
     /// a mono sound will cause some interesting results (and play it at double speed).
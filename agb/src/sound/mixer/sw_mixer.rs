@@ -7,8 +7,8 @@ use alloc::vec::Vec;
 use critical_section::{CriticalSection, Mutex};
 
 use super::hw::LeftOrRight;
+use super::{Bus, Interpolation, SoundChannel, SoundPriority};
 use super::{Frequency, hw};
-use super::{SoundChannel, SoundPriority};
 
 use crate::{
     InternalAllocator,
@@ -60,6 +60,35 @@ unsafe extern "C" {
     add_mono_fn!(agb_rs__mixer_add_mono_loop);
     add_mono_fn!(agb_rs__mixer_add_mono_first);
     add_mono_fn!(agb_rs__mixer_add_mono);
+    add_mono_fn!(agb_rs__mixer_add_mono_interpolated_loop_first);
+    add_mono_fn!(agb_rs__mixer_add_mono_interpolated_loop);
+    add_mono_fn!(agb_rs__mixer_add_mono_interpolated_first);
+    add_mono_fn!(agb_rs__mixer_add_mono_interpolated);
+}
+
+type MonoFn = unsafe extern "C" fn(
+    sample_data: *const u8,
+    sample_buffer: *mut i32,
+    buffer_size: usize,
+    restart_amount: Num<u32, 8>,
+    channel_length: usize,
+    current_pos: Num<u32, 8>,
+    playback_speed: Num<u32, 8>,
+    mul_amount: i32,
+) -> Num<u32, 8>;
+
+/// Picks which of the mono functions in mixer.s to use
+fn mono_fn(is_first: bool, should_loop: bool, interpolation: Interpolation) -> MonoFn {
+    match (interpolation, should_loop, is_first) {
+        (Interpolation::Nearest, true, true) => agb_rs__mixer_add_mono_loop_first,
+        (Interpolation::Nearest, true, false) => agb_rs__mixer_add_mono_loop,
+        (Interpolation::Nearest, false, true) => agb_rs__mixer_add_mono_first,
+        (Interpolation::Nearest, false, false) => agb_rs__mixer_add_mono,
+        (Interpolation::Linear, true, true) => agb_rs__mixer_add_mono_interpolated_loop_first,
+        (Interpolation::Linear, true, false) => agb_rs__mixer_add_mono_interpolated_loop,
+        (Interpolation::Linear, false, true) => agb_rs__mixer_add_mono_interpolated_first,
+        (Interpolation::Linear, false, false) => agb_rs__mixer_add_mono_interpolated,
+    }
 }
 
 /// The main software mixer struct.
@@ -114,9 +143,10 @@ pub struct Mixer<'gba> {
     _interrupt_handler: InterruptHandler,
 
     buffer: raw_box::RawBoxDrop<MixerBuffer, InternalAllocator>,
-    channels: [Option<SoundChannel>; 8],
-    indices: [u32; 8],
+    channels: Box<[Option<SoundChannel>]>,
+    indices: Box<[u32]>,
     frequency: Frequency,
+    buses: Buses,
 
     working_buffer: Box<[Num<i16, 4>], InternalAllocator>,
     /// Copy all the data into here first before acting on it if it is deemed to
@@ -155,7 +185,7 @@ pub struct Mixer<'gba> {
 pub struct ChannelId(usize, NonZero<u32>);
 
 impl Mixer<'_> {
-    pub(super) fn new(frequency: Frequency) -> Self {
+    pub(super) fn new(frequency: Frequency, channel_count: usize) -> Self {
         let buffer =
             raw_box::RawBoxDrop::new(Box::new_in(MixerBuffer::new(frequency), InternalAllocator));
 
@@ -192,15 +222,17 @@ impl Mixer<'_> {
             Vec::with_capacity_in(frequency.buffer_size() * 2, InternalAllocator);
         working_buffer.resize(frequency.buffer_size() * 2, 0.into());
 
+        // Big enough for both the playback buffer and a frame's worth of decoded stereo ADPCM
         let mut temp_storage =
-            Vec::with_capacity_in(frequency.buffer_size() * 3 / 2 + 1, InternalAllocator);
+            Vec::with_capacity_in(frequency.buffer_size() * 2 + 4, InternalAllocator);
         temp_storage.resize(temp_storage.capacity(), 0);
 
         let mut result = Self {
             frequency,
             buffer,
-            channels: Default::default(),
-            indices: Default::default(),
+            channels: (0..channel_count).map(|_| None).collect(),
+            indices: alloc::vec![0; channel_count].into_boxed_slice(),
+            buses: Buses::new(),
 
             interrupt_timer,
             _interrupt_handler: interrupt_handler,
@@ -259,18 +291,109 @@ impl Mixer<'_> {
             return;
         }
 
+        for channel in self.channels.iter_mut().flatten() {
+            channel.update_ramps();
+        }
+
         self.buffer.write_channels(
             &mut self.working_buffer,
             &mut self.temp_storage,
+            &self.buses,
             self.channels.iter_mut().flatten(),
         );
     }
 
+    /// Sets the volume every channel is multiplied by, on top of the volume of the
+    /// channel and its [`Bus`]. This also applies to channels which are already playing.
+    ///
+    /// Must be a value >= 0 and defaults to 1.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![no_std]
+    /// # #![no_main]
+    /// # use agb::sound::mixer::*;
+    /// # use agb::*;
+    /// # #[agb::doctest]
+    /// # fn test(mut gba: Gba) {
+    /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
+    /// use agb::fixnum::num;
+    ///
+    /// mixer.set_master_volume(num!(0.5));
+    /// # }
+    /// ```
+    pub fn set_master_volume(&mut self, volume: impl Into<Num<i16, 8>>) {
+        let volume = volume.into();
+        assert!(volume >= 0.into(), "volume must be >= 0");
+
+        self.buses.master = volume;
+    }
+
+    /// The volume set by [`set_master_volume()`](Self::set_master_volume)
+    #[must_use]
+    pub fn master_volume(&self) -> Num<i16, 8> {
+        self.buses.master
+    }
+
+    /// Sets the volume of every channel on the given [`Bus`], including channels
+    /// which are already playing.
+    ///
+    /// Must be a value >= 0 and defaults to 1.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![no_std]
+    /// # #![no_main]
+    /// # use agb::sound::mixer::*;
+    /// # use agb::*;
+    /// # #[agb::doctest]
+    /// # fn test(mut gba: Gba) {
+    /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
+    /// # static MY_BGM: SoundData = include_wav!("examples/sfx/my_bgm.wav");
+    /// use agb::fixnum::num;
+    ///
+    /// let mut bgm = SoundChannel::new_high_priority(MY_BGM);
+    /// bgm.stereo().should_loop().bus(Bus::Music);
+    /// let _ = mixer.play_sound(bgm);
+    ///
+    /// // from the options menu
+    /// mixer.set_bus_volume(Bus::Music, num!(0.25));
+    /// # }
+    /// ```
+    pub fn set_bus_volume(&mut self, bus: Bus, volume: impl Into<Num<i16, 8>>) {
+        let volume = volume.into();
+        assert!(volume >= 0.into(), "volume must be >= 0");
+
+        self.buses.volumes[bus as usize] = volume;
+    }
+
+    /// The volume of the given [`Bus`] set by [`set_bus_volume()`](Self::set_bus_volume)
+    #[must_use]
+    pub fn bus_volume(&self, bus: Bus) -> Num<i16, 8> {
+        self.buses.volumes[bus as usize]
+    }
+
+    /// Silences every channel on the given [`Bus`] without changing its volume, so
+    /// that unmuting it goes back to the volume it had before. Channels carry on
+    /// playing while muted, so they stay in time and sound effects still finish.
+    pub fn set_bus_muted(&mut self, bus: Bus, muted: bool) {
+        self.buses.muted[bus as usize] = muted;
+    }
+
+    /// Whether the given [`Bus`] has been muted with [`set_bus_muted()`](Self::set_bus_muted)
+    #[must_use]
+    pub fn is_bus_muted(&self, bus: Bus) -> bool {
+        self.buses.muted[bus as usize]
+    }
+
     /// Start playing a given [`SoundChannel`].
     ///
     /// Returns a [`ChannelId`] which you can later use to modify the playing sound.
     ///
-    /// Will first try to play the sound in an unused channel (of the 8 possible channels)
+    /// Will first try to play the sound in an unused channel (there are 8 unless you chose
+    /// otherwise with [`MixerController::mixer_with_channels()`](super::MixerController::mixer_with_channels))
     /// followed by overriding a low priority sound (if the sound channel being passed in
     /// is high priority).
     ///
@@ -323,7 +446,10 @@ impl Mixer<'_> {
             return Some(ChannelId(i, generation));
         }
 
-        panic!("Cannot play more than 8 sounds at once");
+        panic!(
+            "Cannot play more than {} sounds at once",
+            self.channels.len()
+        );
     }
 
     fn next_index(&mut self, i: usize) -> NonZero<u32> {
@@ -370,6 +496,32 @@ impl Mixer<'_> {
     }
 }
 
+/// The volumes which apply to groups of channels on top of their own volume
+struct Buses {
+    master: Num<i16, 8>,
+    volumes: [Num<i16, 8>; Bus::COUNT],
+    muted: [bool; Bus::COUNT],
+}
+
+impl Buses {
+    fn new() -> Self {
+        Self {
+            master: 1.into(),
+            volumes: [1.into(); Bus::COUNT],
+            muted: [false; Bus::COUNT],
+        }
+    }
+
+    /// The volume to actually play the channel at
+    fn volume(&self, channel: &SoundChannel) -> Num<i16, 8> {
+        if self.muted[channel.bus as usize] {
+            return 0.into();
+        }
+
+        channel.volume * self.volumes[channel.bus as usize] * self.master
+    }
+}
+
 struct SoundBuffer(Box<[i8], InternalAllocator>);
 
 impl SoundBuffer {
@@ -457,26 +609,37 @@ impl MixerBuffer {
         &self,
         working_buffer: &mut [Num<i16, 4>],
         temp_storage: &mut [u8],
+        buses: &Buses,
         channels: impl Iterator<Item = &'a mut SoundChannel>,
     ) {
         let mut channels = channels
-            .filter(|channel| !channel.is_done && channel.volume != 0.into() && channel.is_playing);
+            .filter(|channel| !channel.is_done && channel.is_playing)
+            .filter_map(|channel| {
+                let volume = buses.volume(channel);
+                if volume == 0.into() {
+                    // nothing to hear, but the channel still needs to keep its place
+                    self.skip(channel);
+                    None
+                } else {
+                    Some((volume, channel))
+                }
+            });
 
-        if let Some(channel) = channels.next() {
+        if let Some((volume, channel)) = channels.next() {
             if channel.is_stereo {
-                self.write_stereo(channel, working_buffer, true);
+                self.write_stereo(channel, volume, working_buffer, temp_storage, true);
             } else {
-                self.write_mono(channel, working_buffer, temp_storage, true);
+                self.write_mono(channel, volume, working_buffer, temp_storage, true);
             }
         } else {
             working_buffer.fill(0.into());
         }
 
-        for channel in channels {
+        for (volume, channel) in channels {
             if channel.is_stereo {
-                self.write_stereo(channel, working_buffer, false);
+                self.write_stereo(channel, volume, working_buffer, temp_storage, false);
             } else {
-                self.write_mono(channel, working_buffer, temp_storage, false);
+                self.write_mono(channel, volume, working_buffer, temp_storage, false);
             }
         }
 
@@ -492,15 +655,54 @@ impl MixerBuffer {
         }
     }
 
+    /// Moves the channel on by a frame's worth of samples without mixing it in, in the
+    /// same way as [`write_stereo()`](Self::write_stereo) and [`write_mono()`](Self::write_mono)
+    /// would.
+    fn skip(&self, channel: &mut SoundChannel) {
+        let buffer_size = self.frequency.buffer_size() as u32;
+        let channel_len = Num::<u32, 8>::new(channel.end() as u32);
+
+        if channel.is_stereo {
+            if (channel.pos + 2 * buffer_size).floor() >= channel.end() as u32 {
+                if channel.should_loop {
+                    channel.pos = channel.restart_point * 2;
+                } else {
+                    channel.is_done = true;
+                    return;
+                }
+            }
+
+            channel.pos += 2 * buffer_size;
+            return;
+        }
+
+        channel.pos += channel.playback_speed * buffer_size;
+
+        if channel.pos < channel_len {
+            return;
+        }
+
+        if channel.should_loop {
+            let loop_len = channel_len - channel.restart_point;
+            channel.pos = if loop_len == 0.into() {
+                channel.restart_point
+            } else {
+                channel.restart_point + (channel.pos - channel_len) % loop_len
+            };
+        } else {
+            channel.is_done = true;
+        }
+    }
+
     fn write_stereo(
         &self,
         channel: &mut SoundChannel,
+        volume: Num<i16, 8>,
         working_buffer: &mut [Num<i16, 4>],
+        temp_storage: &mut [u8],
         is_first: bool,
     ) {
-        if (channel.pos + 2 * self.frequency.buffer_size() as u32).floor()
-            >= channel.data.len() as u32
-        {
+        if (channel.pos + 2 * self.frequency.buffer_size() as u32).floor() >= channel.end() as u32 {
             if channel.should_loop {
                 channel.pos = channel.restart_point * 2;
             } else {
@@ -511,19 +713,29 @@ impl MixerBuffer {
                 return;
             }
         }
+
+        let data = if let Some(decoder) = channel.adpcm.as_mut() {
+            let decoded = &mut temp_storage[..2 * self.frequency.buffer_size()];
+            decoder.decode(channel.data, 2, channel.pos.floor() as usize / 2, decoded);
+            decoded.as_ptr()
+        } else {
+            // SAFETY: checked above that there is a full buffer of data left to play
+            unsafe { channel.data.as_ptr().add(channel.pos.floor() as usize) }
+        };
+
         unsafe {
             if is_first {
                 agb_rs__mixer_add_stereo_first(
-                    channel.data.as_ptr().add(channel.pos.floor() as usize),
+                    data,
                     working_buffer.as_mut_ptr(),
-                    channel.volume.change_base(),
+                    volume.change_base(),
                     self.frequency.buffer_size(),
                 );
             } else {
                 agb_rs__mixer_add_stereo(
-                    channel.data.as_ptr().add(channel.pos.floor() as usize),
+                    data,
                     working_buffer.as_mut_ptr(),
-                    channel.volume.change_base(),
+                    volume.change_base(),
                     self.frequency.buffer_size(),
                 );
             }
@@ -535,17 +747,18 @@ impl MixerBuffer {
     fn write_mono(
         &self,
         channel: &mut SoundChannel,
+        volume: Num<i16, 8>,
         working_buffer: &mut [Num<i16, 4>],
         temp_storage: &mut [u8],
         is_first: bool,
     ) {
-        let right_amount = ((channel.panning + 1) / 2) * channel.volume;
-        let left_amount = ((-channel.panning + 1) / 2) * channel.volume;
+        let right_amount = ((channel.panning + 1) / 2) * volume;
+        let left_amount = ((-channel.panning + 1) / 2) * volume;
 
         let right_amount: Num<i16, 4> = right_amount.change_base();
         let left_amount: Num<i16, 4> = left_amount.change_base();
 
-        let channel_len = Num::<u32, 8>::new(channel.data.len() as u32);
+        let channel_len = Num::<u32, 8>::new(channel.end() as u32);
 
         // SAFETY: always aligned correctly by construction
         let working_buffer_i32: &mut [i32] = unsafe {
@@ -558,36 +771,138 @@ impl MixerBuffer {
         let mul_amount =
             ((left_amount.to_raw() as i32) << 16) | (right_amount.to_raw() as i32 & 0x0000ffff);
 
+        if channel.adpcm.is_some() {
+            Self::write_mono_adpcm(
+                channel,
+                working_buffer_i32,
+                temp_storage,
+                is_first,
+                mul_amount,
+            );
+            return;
+        }
+
         let playback_buffer =
             playback_buffer::PlaybackBuffer::new(channel, self.frequency, temp_storage);
 
-        macro_rules! call_mono_fn {
-            ($fn_name:ident) => {
-                channel.pos = unsafe {
-                    $fn_name(
-                        playback_buffer.as_ptr(),
-                        working_buffer_i32.as_mut_ptr(),
-                        working_buffer_i32.len(),
-                        channel_len - channel.restart_point,
-                        channel.data.len(),
-                        channel.pos,
-                        channel.playback_speed,
-                        mul_amount,
-                    )
-                }
-            };
+        let mono_fn = mono_fn(is_first, channel.should_loop, channel.interpolation);
+
+        channel.pos = unsafe {
+            mono_fn(
+                playback_buffer.as_ptr(),
+                working_buffer_i32.as_mut_ptr(),
+                working_buffer_i32.len(),
+                channel_len - channel.restart_point,
+                channel.end(),
+                channel.pos,
+                channel.playback_speed,
+                mul_amount,
+            )
+        };
+
+        if !channel.should_loop {
+            channel.is_done = channel.pos >= channel_len;
         }
+    }
+
+    /// ADPCM sounds can't be read directly by the mono functions, so we decode
+    /// as much as fits into `temp_storage` and play from there, repeating until
+    /// the buffer is full. The more samples are skipped over by a high playback
+    /// speed, the more chunks this takes.
+    fn write_mono_adpcm(
+        channel: &mut SoundChannel,
+        working_buffer_i32: &mut [i32],
+        temp_storage: &mut [u8],
+        is_first: bool,
+        mul_amount: i32,
+    ) {
+        let length = channel.end();
 
-        match (is_first, channel.should_loop) {
-            (true, true) => call_mono_fn!(agb_rs__mixer_add_mono_loop_first),
-            (false, true) => call_mono_fn!(agb_rs__mixer_add_mono_loop),
-            (true, false) => {
-                call_mono_fn!(agb_rs__mixer_add_mono_first);
-                channel.is_done = channel.pos >= channel_len;
+        let Some(decoder) = channel.adpcm.as_mut() else {
+            return;
+        };
+
+        let restart_point = (channel.restart_point.floor() as usize).min(length);
+        let loop_length = Num::new((length - restart_point) as u32);
+
+        // Interpolation also reads the sample after the last one we play
+        let extra = usize::from(channel.interpolation == Interpolation::Linear);
+        let mono_fn = mono_fn(is_first, false, channel.interpolation);
+
+        // The most output samples we can produce from a full temp_storage, making sure
+        // that floor(frac + speed * (chunk - 1)) + extra < temp_storage.len()
+        let max_chunk = ((temp_storage.len() - 1 - extra) as u32) << 8;
+        let max_chunk = max_chunk
+            .checked_div(channel.playback_speed.to_raw())
+            .map_or(usize::MAX, |chunk| chunk as usize + 1);
+        let max_chunk = (max_chunk & !3).max(4);
+
+        let mut written = 0;
+        while written < working_buffer_i32.len() {
+            let start = channel.pos.floor() as usize;
+            if start >= length {
+                channel.is_done = true;
+                if is_first {
+                    working_buffer_i32[written..].fill(0);
+                }
+                return;
             }
-            (false, false) => {
-                call_mono_fn!(agb_rs__mixer_add_mono);
-                channel.is_done = channel.pos >= channel_len;
+
+            let chunk = max_chunk.min(working_buffer_i32.len() - written);
+            let pos = Num::from_raw(channel.pos.to_raw() & 0xff);
+
+            let needed =
+                ((pos + channel.playback_speed * (chunk as u32 - 1)).floor() as usize + 1 + extra)
+                    .min(temp_storage.len());
+
+            // Fill the temporary storage, going back to the restart point if we reach
+            // the end of a looping sound
+            let mut available = 0;
+            let mut next = start;
+            while available < needed {
+                let run = (needed - available).min(length - next);
+                if run == 0 {
+                    break;
+                }
+
+                decoder.decode(
+                    channel.data,
+                    1,
+                    next,
+                    &mut temp_storage[available..available + run],
+                );
+                available += run;
+                next += run;
+
+                if next >= length {
+                    if !channel.should_loop {
+                        break;
+                    }
+                    next = restart_point;
+                }
+            }
+
+            let output = &mut working_buffer_i32[written..written + chunk];
+            let end = unsafe {
+                mono_fn(
+                    temp_storage.as_ptr(),
+                    output.as_mut_ptr(),
+                    output.len(),
+                    0.into(),
+                    available,
+                    pos,
+                    channel.playback_speed,
+                    mul_amount,
+                )
+            };
+
+            written += chunk;
+            channel.pos = Num::new(start as u32) + end;
+
+            if channel.should_loop {
+                while channel.pos >= Num::new(length as u32) && loop_length != 0.into() {
+                    channel.pos -= loop_length;
+                }
             }
         }
     }
@@ -621,7 +936,7 @@ mod playback_buffer {
             frequency: Frequency,
             temp_storage: &'a mut [u8],
         ) -> Self {
-            let channel_len = Num::new(channel.data.len() as u32);
+            let channel_len = Num::new(channel.end() as u32);
 
             // 1.5 is approximately the multiple we can work with before it would be faster
             // to read from ROM rather than do the copy. We also allow copying the entire channel
@@ -629,8 +944,9 @@ mod playback_buffer {
             // speed is so high.
             //
             // If increasing this size, make sure to also increase the size of the temp_storage
-            // allocation since this guards overrunning that.
-            if channel.playback_speed > num!(1.5) && channel.data.len() > temp_storage.len() {
+            // allocation since this guards overrunning that. The extra 4 bytes leave room for
+            // the samples after the end of a partial copy below.
+            if channel.playback_speed > num!(1.5) && channel.end() + 4 > temp_storage.len() {
                 return PlaybackBuffer::Rom(channel.data);
             }
 
@@ -651,20 +967,22 @@ mod playback_buffer {
                 }
 
                 PlaybackBuffer::TempStorage(temp_storage, 0)
-            } else if channel.pos + total_to_play > channel_len {
-                // The playback is going to loop. We don't handle this case (yet) but
-                // fortunately it doesn't come up as often as the other two cases.
+            } else if channel.pos + total_to_play + 1 > channel_len {
+                // The playback is going to loop (or interpolate with the restart point).
+                // We don't handle this case (yet) but fortunately it doesn't come up as
+                // often as the other two cases.
                 PlaybackBuffer::Rom(channel.data)
             } else {
                 // We're not going to loop, and not going to play the entire sample. So
-                // we'll copy as much over as we can.
-                assert!((total_to_play as usize / 2 + 1) * 2 <= temp_storage.len());
+                // we'll copy as much over as we can, including the sample after the last
+                // one we play in case it is interpolated with.
+                assert!((total_to_play as usize / 2 + 2) * 2 <= temp_storage.len());
 
                 unsafe {
                     dma_copy16(
                         channel.data[channel.pos.floor() as usize..].as_ptr().cast(),
                         temp_storage.as_mut_ptr().cast(),
-                        total_to_play as usize / 2 + 1,
+                        total_to_play as usize / 2 + 2,
                     );
                 }
 
@@ -721,6 +1039,17 @@ mod test {
     use alloc::vec;
 
     use super::*;
+    use crate::sound::mixer::SoundData;
+
+    #[repr(align(4))]
+    struct AlignedSamples<const N: usize>([u8; N]);
+
+    static SILENCE: AlignedSamples<1024> = AlignedSamples([0; 1024]);
+
+    /// Correctly aligned sound data of the given length for tests which don't care what it plays
+    fn silence(len: usize) -> SoundData {
+        unsafe { SoundData::new(&SILENCE.0[..len]) }
+    }
 
     #[test_case]
     fn collapse_should_correctly_reduce_size_of_input(_: &mut crate::Gba) {
@@ -820,6 +1149,165 @@ mod test {
         assert_eq!(result, num!(7.0));
     }
 
+    #[test_case]
+    fn mono_add_interpolated_loop_first_should_work(_: &mut crate::Gba) {
+        let mut buffer = vec![0i32; 16];
+        let sample_data: [i8; 4] = [0, 100, -100, 50];
+        let restart_amount = num!(4.0);
+        let current_pos = num!(0.0);
+        let playback_speed = num!(0.5);
+
+        let mul_amount = 10;
+
+        let result = unsafe {
+            agb_rs__mixer_add_mono_interpolated_loop_first(
+                sample_data.as_ptr().cast(),
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                restart_amount,
+                sample_data.len(),
+                current_pos,
+                playback_speed,
+                mul_amount,
+            )
+        };
+
+        // the last sample is interpolated with the one at the restart point
+        assert_eq!(
+            buffer,
+            &[
+                0, 500, 1000, 0, -1000, -250, 500, 250, 0, 500, 1000, 0, -1000, -250, 500, 250
+            ]
+        );
+        assert_eq!(result, num!(4.0));
+    }
+
+    #[test_case]
+    fn looping_channel_ends_at_loop_end(_: &mut crate::Gba) {
+        let data = silence(16).with_loop(2, 6);
+
+        let mut channel = SoundChannel::new(data);
+        assert_eq!(channel.restart_point, num!(2.0));
+        assert_eq!(channel.end(), 16);
+
+        channel.should_loop();
+        assert_eq!(channel.end(), 6);
+
+        channel.stereo();
+        assert_eq!(channel.end(), 12);
+    }
+
+    #[test_case]
+    fn bus_volumes_multiply_channel_volume(_: &mut crate::Gba) {
+        let mut music = SoundChannel::new(silence(4));
+        music.volume(num!(0.5)).bus(Bus::Music);
+        let mut effect = SoundChannel::new(silence(4));
+        effect.volume(num!(0.5));
+
+        let mut buses = Buses::new();
+        buses.volumes[Bus::Music as usize] = num!(0.5);
+        buses.master = num!(0.5);
+
+        assert_eq!(buses.volume(&music), num!(0.125));
+        assert_eq!(buses.volume(&effect), num!(0.25));
+
+        buses.muted[Bus::Effects as usize] = true;
+        assert_eq!(buses.volume(&music), num!(0.125));
+        assert_eq!(buses.volume(&effect), num!(0.0));
+    }
+
+    #[test_case]
+    fn muted_channels_keep_playing(_: &mut crate::Gba) {
+        let frequency = Frequency::Hz10512;
+        let mixer_buffer = MixerBuffer::new(frequency);
+        let mut working_buffer = vec![Num::new(0); frequency.buffer_size() * 2];
+        let mut temp_storage = vec![0; frequency.buffer_size() * 2 + 4];
+
+        let mut buses = Buses::new();
+        buses.muted[Bus::Effects as usize] = true;
+
+        let mut effect = SoundChannel::new(silence(400));
+        let mut looping = SoundChannel::new(silence(400).with_loop(100, 400));
+        looping.should_loop();
+
+        for _ in 0..2 {
+            mixer_buffer.write_channels(
+                &mut working_buffer,
+                &mut temp_storage,
+                &buses,
+                [&mut effect, &mut looping].into_iter(),
+            );
+        }
+
+        assert!(!effect.is_done);
+        assert_eq!(effect.pos, num!(352.0));
+
+        mixer_buffer.write_channels(
+            &mut working_buffer,
+            &mut temp_storage,
+            &buses,
+            [&mut effect, &mut looping].into_iter(),
+        );
+
+        assert!(effect.is_done);
+        assert!(!looping.is_done);
+        assert_eq!(looping.pos, num!(228.0));
+    }
+
+    #[test_case]
+    fn fades_change_settings_each_frame(_: &mut crate::Gba) {
+        let mut channel = SoundChannel::new(silence(4));
+        channel
+            .volume(0)
+            .fade_volume(1, 4)
+            .fade_panning(num!(-1.0), 2)
+            .fade_playback(2, 4);
+
+        let mut volumes = vec![];
+        for _ in 0..5 {
+            channel.update_ramps();
+            volumes.push(channel.volume);
+        }
+
+        assert_eq!(
+            volumes,
+            [num!(0.25), num!(0.5), num!(0.75), num!(1.0), num!(1.0)]
+        );
+        assert_eq!(channel.panning, num!(-1.0));
+        assert_eq!(channel.playback_speed, num!(2.0));
+
+        channel.fade_out(2);
+        channel.update_ramps();
+        assert!(!channel.is_done);
+        channel.update_ramps();
+        assert!(channel.is_done);
+        assert_eq!(channel.volume, num!(0.0));
+    }
+
+    #[test_case]
+    fn frequencies_fill_whole_frames(_: &mut crate::Gba) {
+        const CYCLES_PER_FRAME: usize = 280896;
+
+        for frequency in [
+            Frequency::Hz10512,
+            Frequency::Hz13379,
+            Frequency::Hz18157,
+            Frequency::Hz21024,
+            Frequency::Hz26758,
+            Frequency::Hz31536,
+        ] {
+            let period =
+                (16777216 + frequency.frequency() as usize / 2) / frequency.frequency() as usize;
+
+            assert_eq!(
+                period * frequency.buffer_size(),
+                CYCLES_PER_FRAME,
+                "{frequency:?}"
+            );
+            assert_eq!(frequency.buffer_size() % 4, 0, "{frequency:?}");
+        }
+    }
+
     #[test_case]
     fn channel_id_none_niche(_: &mut crate::Gba) {
         assert_eq!(size_of::<Option<ChannelId>>(), size_of::<ChannelId>());
@@ -2,4 +2,8 @@
 //!
 //! The [`mixer`](crate::sound::mixer) module is high performance, and allows for playing wav files at
 //! various levels of quality. Check out the module documentation for more.
+//!
+//! The [`psg`](crate::sound::psg) module gives access to the Game Boy's original sound channels,
+//! which generate simple tones and noise in hardware and can be used alongside the mixer.
 pub mod mixer;
+pub mod psg;
@@ -0,0 +1,796 @@
+#![warn(missing_docs)]
+//! # DMG sound channels
+//!
+//! As well as the direct sound channels used by the [`mixer`](crate::sound::mixer), the
+//! Game Boy Advance has the 4 sound channels of the original Game Boy. These generate
+//! their sound in hardware, so they take almost no CPU time, which makes them a good fit
+//! for retro style sound effects and simple music.
+//!
+//! There are 4 channels:
+//! * two [square wave channels](SquareChannel), the first of which can also sweep its frequency,
+//! * a [wave channel](WaveChannel) which plays a short looping wave of 32 4-bit samples, and
+//! * a [noise channel](NoiseChannel) for explosions, drums and other percussive sounds.
+//!
+//! Each channel is configured and then started with `play()`. Most settings take effect the
+//! next time the channel is played, apart from the frequency, which changes straight away so
+//! that the pitch can be bent while a sound is playing.
+//!
+//! ```rust
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::sound::psg::{Duty, Envelope, EnvelopeDirection, Sweep, SweepDirection};
+//!
+//! # #[agb::doctest]
+//! # fn test(mut gba: agb::Gba) {
+//! let mut psg = gba.psg.psg();
+//!
+//! // a short jump sound which rises in pitch and fades out
+//! psg.square1()
+//!     .duty(Duty::Half)
+//!     .sweep(Sweep::new(2, SweepDirection::Up, 3))
+//!     .envelope(Envelope::new(15, EnvelopeDirection::Decrease, 2))
+//!     .frequency(440)
+//!     .play();
+//! # }
+//! ```
+//!
+//! The channels can be used at the same time as the mixer, and the volume of all of them
+//! together compared to the mixer can be set with [`Psg::set_volume()`].
+
+use core::marker::PhantomData;
+
+use crate::memory_mapped::{MemoryMapped, MemoryMapped1DArray};
+
+const SOUND1_SWEEP: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0060) };
+const SOUND1_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0062) };
+const SOUND1_FREQUENCY: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0064) };
+
+const SOUND2_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0068) };
+const SOUND2_FREQUENCY: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_006c) };
+
+const SOUND3_SELECT: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0070) };
+const SOUND3_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0072) };
+const SOUND3_FREQUENCY: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0074) };
+
+const SOUND4_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0078) };
+const SOUND4_FREQUENCY: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_007c) };
+
+const SOUND_CONTROL_L: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0080) };
+const SOUND_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0082) };
+const SOUND_CONTROL_X: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0084) };
+
+// The CPU can only access the bank of wave RAM which isn't currently selected for playing
+const WAVE_RAM: MemoryMapped1DArray<u32, 4> = unsafe { MemoryMapped1DArray::new(0x0400_0090) };
+
+const RESTART: u16 = 1 << 15;
+const LENGTH_ENABLE: u16 = 1 << 14;
+
+/// Controls access to the DMG sound channels. A zero sized type that ensures that access
+/// to them is exclusive.
+#[non_exhaustive]
+pub struct PsgController {}
+
+impl PsgController {
+    pub(crate) const fn new() -> Self {
+        PsgController {}
+    }
+
+    /// Get a [`Psg`] in order to start playing sounds on the DMG sound channels.
+    ///
+    /// All the channels start off silent, playing out of both speakers at full volume.
+    pub fn psg(&mut self) -> Psg<'_> {
+        Psg::new()
+    }
+}
+
+/// One of the 4 DMG sound channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PsgChannel {
+    /// The first square wave channel, which has a frequency sweep
+    Square1,
+    /// The second square wave channel
+    Square2,
+    /// The wave channel
+    Wave,
+    /// The noise channel
+    Noise,
+}
+
+impl PsgChannel {
+    fn index(self) -> u16 {
+        match self {
+            PsgChannel::Square1 => 0,
+            PsgChannel::Square2 => 1,
+            PsgChannel::Wave => 2,
+            PsgChannel::Noise => 3,
+        }
+    }
+}
+
+/// How loud the DMG sound channels are compared to the [`mixer`](crate::sound::mixer)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PsgVolume {
+    /// A quarter of the volume
+    Quarter,
+    /// Half of the volume
+    Half,
+    /// The full volume
+    #[default]
+    Full,
+}
+
+/// Plays sounds on the DMG sound channels. Get one with [`PsgController::psg()`].
+///
+/// Dropping this silences all the channels.
+pub struct Psg<'gba> {
+    square1: SquareChannel,
+    square2: SquareChannel,
+    wave: WaveChannel,
+    noise: NoiseChannel,
+
+    phantom: PhantomData<&'gba ()>,
+}
+
+impl Psg<'_> {
+    fn new() -> Self {
+        // the DMG registers can only be written to while sound is enabled
+        SOUND_CONTROL_X.set(1 << 7);
+
+        let left_and_right_volume = 7 | (7 << 4);
+        let all_channels = (0b1111 << 8) | (0b1111 << 12);
+        SOUND_CONTROL_L.set(left_and_right_volume | all_channels);
+
+        let mut psg = Self {
+            square1: SquareChannel::new(Some(SOUND1_SWEEP), SOUND1_CONTROL, SOUND1_FREQUENCY),
+            square2: SquareChannel::new(None, SOUND2_CONTROL, SOUND2_FREQUENCY),
+            wave: WaveChannel::new(),
+            noise: NoiseChannel::new(),
+
+            phantom: PhantomData,
+        };
+
+        psg.set_volume(PsgVolume::Full);
+        psg.square1.stop();
+        psg.square2.stop();
+        psg.wave.stop();
+        psg.noise.stop();
+
+        psg
+    }
+
+    /// The first square wave channel, which is the only one with a frequency [`Sweep`]
+    pub fn square1(&mut self) -> &mut SquareChannel {
+        &mut self.square1
+    }
+
+    /// The second square wave channel
+    pub fn square2(&mut self) -> &mut SquareChannel {
+        &mut self.square2
+    }
+
+    /// The wave channel
+    pub fn wave(&mut self) -> &mut WaveChannel {
+        &mut self.wave
+    }
+
+    /// The noise channel
+    pub fn noise(&mut self) -> &mut NoiseChannel {
+        &mut self.noise
+    }
+
+    /// Sets how loud all the DMG sound channels are compared to the mixer.
+    pub fn set_volume(&mut self, volume: PsgVolume) {
+        let bits = match volume {
+            PsgVolume::Quarter => 0,
+            PsgVolume::Half => 1,
+            PsgVolume::Full => 2,
+        };
+
+        SOUND_CONTROL.set_bits(bits, 2, 0);
+    }
+
+    /// Sets the volume of all the DMG sound channels for the left and right speakers,
+    /// between 0 and 7. A volume of 0 is quiet rather than silent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either volume is greater than 7.
+    pub fn set_master_volume(&mut self, left: u8, right: u8) {
+        assert!(left <= 7, "left volume must be <= 7");
+        assert!(right <= 7, "right volume must be <= 7");
+
+        SOUND_CONTROL_L.set_bits(u16::from(right) | (u16::from(left) << 4), 8, 0);
+    }
+
+    /// Chooses which speakers `channel` plays out of. A channel which plays out of neither
+    /// is silent.
+    pub fn set_output(&mut self, channel: PsgChannel, left: bool, right: bool) {
+        SOUND_CONTROL_L.set_bits(u16::from(right), 1, 8 + channel.index());
+        SOUND_CONTROL_L.set_bits(u16::from(left), 1, 12 + channel.index());
+    }
+
+    /// Whether `channel` is currently playing. A channel stops once its
+    /// [length](SquareChannel::length) runs out, or when it is stopped.
+    #[must_use]
+    pub fn is_playing(&self, channel: PsgChannel) -> bool {
+        SOUND_CONTROL_X.get() & (1 << channel.index()) != 0
+    }
+}
+
+impl Drop for Psg<'_> {
+    fn drop(&mut self) {
+        self.square1.stop();
+        self.square2.stop();
+        self.wave.stop();
+        self.noise.stop();
+
+        // leave the master sound enable alone since the mixer could still be using it
+        SOUND_CONTROL_L.set(0);
+    }
+}
+
+/// Whether an [`Envelope`] makes the channel louder or quieter over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeDirection {
+    /// The volume goes up
+    Increase,
+    /// The volume goes down
+    Decrease,
+}
+
+/// Changes the volume of a channel over time, which is how the square and noise channels
+/// control their volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Envelope {
+    initial_volume: u8,
+    direction: EnvelopeDirection,
+    step_time: u8,
+}
+
+impl Envelope {
+    /// Starts the channel at `initial_volume`, between 0 and 15, and changes it by 1
+    /// in `direction` every `step_time` 64ths of a second until it reaches 0 or 15.
+    /// A `step_time` of 0 keeps the volume constant.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `initial_volume` is greater than 15 or `step_time` is greater than 7.
+    #[must_use]
+    pub const fn new(initial_volume: u8, direction: EnvelopeDirection, step_time: u8) -> Self {
+        assert!(initial_volume <= 15, "initial volume must be <= 15");
+        assert!(step_time <= 7, "step time must be <= 7");
+
+        Self {
+            initial_volume,
+            direction,
+            step_time,
+        }
+    }
+
+    /// Keeps the channel at `volume`, between 0 and 15.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `volume` is greater than 15.
+    #[must_use]
+    pub const fn constant(volume: u8) -> Self {
+        Self::new(volume, EnvelopeDirection::Decrease, 0)
+    }
+
+    const fn bits(self) -> u16 {
+        let direction = match self.direction {
+            EnvelopeDirection::Increase => 1,
+            EnvelopeDirection::Decrease => 0,
+        };
+
+        ((self.step_time as u16) << 8) | (direction << 11) | ((self.initial_volume as u16) << 12)
+    }
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Self::constant(15)
+    }
+}
+
+/// Whether a [`Sweep`] makes the frequency higher or lower over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SweepDirection {
+    /// The frequency goes up
+    #[default]
+    Up,
+    /// The frequency goes down
+    Down,
+}
+
+/// Changes the frequency of the first square channel over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sweep {
+    time: u8,
+    direction: SweepDirection,
+    shift: u8,
+}
+
+impl Sweep {
+    /// Every `time` 128ths of a second, changes the frequency rate by itself shifted right
+    /// by `shift` in `direction`. A larger `shift` gives a smaller change each time.
+    ///
+    /// The channel stops if the frequency goes too high.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `time` or `shift` are greater than 7.
+    #[must_use]
+    pub const fn new(time: u8, direction: SweepDirection, shift: u8) -> Self {
+        assert!(time <= 7, "sweep time must be <= 7");
+        assert!(shift <= 7, "sweep shift must be <= 7");
+
+        Self {
+            time,
+            direction,
+            shift,
+        }
+    }
+
+    /// Keeps the frequency constant
+    #[must_use]
+    pub const fn none() -> Self {
+        Self::new(0, SweepDirection::Up, 0)
+    }
+
+    const fn bits(self) -> u16 {
+        let direction = match self.direction {
+            SweepDirection::Up => 0,
+            SweepDirection::Down => 1,
+        };
+
+        (self.shift as u16) | (direction << 3) | ((self.time as u16) << 4)
+    }
+}
+
+/// How much of each cycle of a square wave is high. Different duties give the sound a
+/// different character, but 25% and 75% sound the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Duty {
+    /// 12.5%
+    Eighth,
+    /// 25%
+    Quarter,
+    /// 50%
+    #[default]
+    Half,
+    /// 75%
+    ThreeQuarters,
+}
+
+impl Duty {
+    fn bits(self) -> u16 {
+        let duty = match self {
+            Duty::Eighth => 0,
+            Duty::Quarter => 1,
+            Duty::Half => 2,
+            Duty::ThreeQuarters => 3,
+        };
+
+        duty << 6
+    }
+}
+
+// Length counters count up to 64 (or 256 for the wave channel), so the register
+// holds how far from the end to start
+fn length_bits(length: Option<u16>, max: u16) -> u16 {
+    length.map_or(0, |length| {
+        assert!(
+            length > 0 && length <= max,
+            "length must be between 1 and {max}"
+        );
+        (max - length) & (max - 1)
+    })
+}
+
+fn length_enable(length: Option<u16>) -> u16 {
+    if length.is_some() { LENGTH_ENABLE } else { 0 }
+}
+
+// The square channels play at 131072 / (2048 - rate) Hz
+fn square_rate(frequency: u32) -> u16 {
+    assert!(
+        (64..=131_072).contains(&frequency),
+        "frequency must be between 64Hz and 131072Hz"
+    );
+
+    (2048 - (131_072 + frequency / 2) / frequency) as u16
+}
+
+// The wave channel plays 32 samples at 2097152 / (2048 - rate) Hz
+fn wave_rate(frequency: u32) -> u16 {
+    assert!(
+        (32..=65_536).contains(&frequency),
+        "frequency must be between 32Hz and 65536Hz"
+    );
+
+    (2048 - (65_536 + frequency / 2) / frequency) as u16
+}
+
+/// One of the square wave channels. Get one with [`Psg::square1()`] or [`Psg::square2()`].
+pub struct SquareChannel {
+    sweep_register: Option<MemoryMapped<u16>>,
+    control_register: MemoryMapped<u16>,
+    frequency_register: MemoryMapped<u16>,
+
+    sweep: Sweep,
+    duty: Duty,
+    envelope: Envelope,
+    length: Option<u16>,
+    rate: u16,
+}
+
+impl SquareChannel {
+    fn new(
+        sweep_register: Option<MemoryMapped<u16>>,
+        control_register: MemoryMapped<u16>,
+        frequency_register: MemoryMapped<u16>,
+    ) -> Self {
+        Self {
+            sweep_register,
+            control_register,
+            frequency_register,
+
+            sweep: Sweep::none(),
+            duty: Duty::default(),
+            envelope: Envelope::default(),
+            length: None,
+            rate: square_rate(440),
+        }
+    }
+
+    /// Sets the frequency sweep, which only the first square channel has.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is the second square channel.
+    pub fn sweep(&mut self, sweep: Sweep) -> &mut Self {
+        assert!(
+            self.sweep_register.is_some(),
+            "only the first square channel has a frequency sweep"
+        );
+
+        self.sweep = sweep;
+        self
+    }
+
+    /// Sets the duty of the square wave. Defaults to [`Duty::Half`].
+    pub fn duty(&mut self, duty: Duty) -> &mut Self {
+        self.duty = duty;
+        self
+    }
+
+    /// Sets the volume envelope. Defaults to a constant full volume.
+    pub fn envelope(&mut self, envelope: Envelope) -> &mut Self {
+        self.envelope = envelope;
+        self
+    }
+
+    /// Stops the channel after `length` 256ths of a second, between 1 and 64. `None`,
+    /// the default, plays until the channel is stopped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `length` is 0 or greater than 64.
+    pub fn length(&mut self, length: Option<u8>) -> &mut Self {
+        let length = length.map(u16::from);
+        length_bits(length, 64);
+
+        self.length = length;
+        self
+    }
+
+    /// Sets the frequency of the square wave in Hz, between 64Hz and 131072Hz, rounding to
+    /// the nearest one the hardware can play. This changes straight away if the channel
+    /// is playing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frequency` is outside the range the hardware can play.
+    pub fn frequency(&mut self, frequency: u32) -> &mut Self {
+        self.rate = square_rate(frequency);
+        self.frequency_register
+            .set(self.rate | length_enable(self.length));
+        self
+    }
+
+    /// Starts playing from the beginning with the current settings.
+    pub fn play(&mut self) {
+        if let Some(sweep_register) = &self.sweep_register {
+            sweep_register.set(self.sweep.bits());
+        }
+
+        self.control_register
+            .set(length_bits(self.length, 64) | self.duty.bits() | self.envelope.bits());
+        self.frequency_register
+            .set(self.rate | length_enable(self.length) | RESTART);
+    }
+
+    /// Stops the channel.
+    pub fn stop(&mut self) {
+        // a volume of 0 going down turns the channel off
+        self.control_register.set(0);
+    }
+}
+
+/// How loud the wave channel is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaveVolume {
+    /// Silent
+    Mute,
+    /// 25%
+    Quarter,
+    /// 50%
+    Half,
+    /// 75%
+    ThreeQuarters,
+    /// 100%
+    #[default]
+    Full,
+}
+
+impl WaveVolume {
+    fn bits(self) -> u16 {
+        match self {
+            WaveVolume::Mute => 0,
+            WaveVolume::Quarter => 3 << 13,
+            WaveVolume::Half => 2 << 13,
+            WaveVolume::ThreeQuarters => 1 << 15,
+            WaveVolume::Full => 1 << 13,
+        }
+    }
+}
+
+/// The wave channel, which repeatedly plays a wave of 32 4-bit samples. Get it with
+/// [`Psg::wave()`].
+pub struct WaveChannel {
+    bank: u16,
+    enabled: bool,
+    volume: WaveVolume,
+    length: Option<u16>,
+    rate: u16,
+}
+
+impl WaveChannel {
+    fn new() -> Self {
+        Self {
+            bank: 0,
+            enabled: false,
+            volume: WaveVolume::default(),
+            length: None,
+            rate: wave_rate(440),
+        }
+    }
+
+    fn write_select(&self) {
+        SOUND3_SELECT.set((self.bank << 6) | (u16::from(self.enabled) << 7));
+    }
+
+    /// Sets the wave to play. Each byte holds 2 samples between 0 and 15, with the first
+    /// in the high 4 bits. This changes straight away if the channel is playing.
+    pub fn wave(&mut self, wave: &[u8; 16]) -> &mut Self {
+        let (words, _) = wave.as_chunks::<4>();
+        for (i, word) in words.iter().enumerate() {
+            WAVE_RAM.set(i, u32::from_le_bytes(*word));
+        }
+
+        // switch to playing the bank we just wrote to, so the next wave goes in the other one
+        self.bank ^= 1;
+        self.write_select();
+        self
+    }
+
+    /// Sets the volume of the wave channel. Defaults to [`WaveVolume::Full`]. This changes
+    /// straight away if the channel is playing, but restarts its length.
+    pub fn volume(&mut self, volume: WaveVolume) -> &mut Self {
+        self.volume = volume;
+        SOUND3_CONTROL.set(length_bits(self.length, 256) | self.volume.bits());
+        self
+    }
+
+    /// Stops the channel after `length` 256ths of a second, between 1 and 256. `None`,
+    /// the default, plays until the channel is stopped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `length` is 0 or greater than 256.
+    pub fn length(&mut self, length: Option<u16>) -> &mut Self {
+        length_bits(length, 256);
+
+        self.length = length;
+        self
+    }
+
+    /// Sets how many times per second the whole wave is played, between 32Hz and 65536Hz,
+    /// rounding to the nearest one the hardware can play. This changes straight away if the
+    /// channel is playing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frequency` is outside the range the hardware can play.
+    pub fn frequency(&mut self, frequency: u32) -> &mut Self {
+        self.rate = wave_rate(frequency);
+        SOUND3_FREQUENCY.set(self.rate | length_enable(self.length));
+        self
+    }
+
+    /// Starts playing from the beginning of the wave with the current settings.
+    pub fn play(&mut self) {
+        self.enabled = true;
+        self.write_select();
+
+        SOUND3_CONTROL.set(length_bits(self.length, 256) | self.volume.bits());
+        SOUND3_FREQUENCY.set(self.rate | length_enable(self.length) | RESTART);
+    }
+
+    /// Stops the channel.
+    pub fn stop(&mut self) {
+        self.enabled = false;
+        self.write_select();
+    }
+}
+
+/// How many steps the noise channel takes before the noise repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoiseWidth {
+    /// Repeats after 32767 steps, which sounds like white noise
+    #[default]
+    Long,
+    /// Repeats after 127 steps, which sounds more metallic
+    Short,
+}
+
+/// How quickly the noise channel generates noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Noise {
+    shift: u8,
+    divider: u8,
+    width: NoiseWidth,
+}
+
+impl Noise {
+    /// The noise channel steps at `524288 / divider / 2^(shift + 1)` Hz, where a `divider`
+    /// of 0 counts as 0.5. Lower values give higher pitched noise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shift` is greater than 13 or `divider` is greater than 7.
+    #[must_use]
+    pub const fn new(shift: u8, divider: u8, width: NoiseWidth) -> Self {
+        assert!(shift <= 13, "noise shift must be <= 13");
+        assert!(divider <= 7, "noise divider must be <= 7");
+
+        Self {
+            shift,
+            divider,
+            width,
+        }
+    }
+
+    const fn bits(self) -> u16 {
+        let width = match self.width {
+            NoiseWidth::Long => 0,
+            NoiseWidth::Short => 1,
+        };
+
+        (self.divider as u16) | (width << 3) | ((self.shift as u16) << 4)
+    }
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self::new(4, 0, NoiseWidth::Long)
+    }
+}
+
+/// The noise channel. Get it with [`Psg::noise()`].
+pub struct NoiseChannel {
+    envelope: Envelope,
+    length: Option<u16>,
+    noise: Noise,
+}
+
+impl NoiseChannel {
+    fn new() -> Self {
+        Self {
+            envelope: Envelope::default(),
+            length: None,
+            noise: Noise::default(),
+        }
+    }
+
+    /// Sets the volume envelope. Defaults to a constant full volume.
+    pub fn envelope(&mut self, envelope: Envelope) -> &mut Self {
+        self.envelope = envelope;
+        self
+    }
+
+    /// Stops the channel after `length` 256ths of a second, between 1 and 64. `None`,
+    /// the default, plays until the channel is stopped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `length` is 0 or greater than 64.
+    pub fn length(&mut self, length: Option<u8>) -> &mut Self {
+        let length = length.map(u16::from);
+        length_bits(length, 64);
+
+        self.length = length;
+        self
+    }
+
+    /// Sets the kind of noise to play. This changes straight away if the channel is playing.
+    pub fn noise(&mut self, noise: Noise) -> &mut Self {
+        self.noise = noise;
+        SOUND4_FREQUENCY.set(self.noise.bits() | length_enable(self.length));
+        self
+    }
+
+    /// Starts playing with the current settings.
+    pub fn play(&mut self) {
+        SOUND4_CONTROL.set(length_bits(self.length, 64) | self.envelope.bits());
+        SOUND4_FREQUENCY.set(self.noise.bits() | length_enable(self.length) | RESTART);
+    }
+
+    /// Stops the channel.
+    pub fn stop(&mut self) {
+        // a volume of 0 going down turns the channel off
+        SOUND4_CONTROL.set(0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sound::mixer::Frequency;
+
+    #[test_case]
+    fn frequencies_round_to_the_nearest_rate(_: &mut crate::Gba) {
+        assert_eq!(square_rate(131_072), 2047);
+        assert_eq!(square_rate(64), 0);
+        // 131072 / 440 = 297.9
+        assert_eq!(square_rate(440), 2048 - 298);
+
+        assert_eq!(wave_rate(65_536), 2047);
+        assert_eq!(wave_rate(32), 0);
+        assert_eq!(wave_rate(440), 2048 - 149);
+    }
+
+    #[test_case]
+    fn lengths_count_from_the_end(_: &mut crate::Gba) {
+        assert_eq!(length_bits(None, 64), 0);
+        assert_eq!(length_bits(Some(1), 64), 63);
+        assert_eq!(length_bits(Some(64), 64), 0);
+        assert_eq!(length_bits(Some(256), 256), 0);
+        assert_eq!(length_bits(Some(56), 256), 200);
+    }
+
+    #[test_case]
+    fn output_is_chosen_per_channel(gba: &mut crate::Gba) {
+        let mut psg = gba.psg.psg();
+
+        psg.set_master_volume(3, 5);
+        psg.set_output(PsgChannel::Wave, true, false);
+        psg.set_output(PsgChannel::Noise, false, true);
+
+        assert_eq!(
+            SOUND_CONTROL_L.get(),
+            5 | (3 << 4) | (0b1011 << 8) | (0b0111 << 12)
+        );
+    }
+
+    #[test_case]
+    fn mixer_keeps_the_psg_volume(gba: &mut crate::Gba) {
+        let mut psg = gba.psg.psg();
+        psg.set_volume(PsgVolume::Half);
+
+        let _mixer = gba.mixer.mixer(Frequency::Hz18157);
+
+        assert_eq!(SOUND_CONTROL.get() & 0b11, 1);
+    }
+}